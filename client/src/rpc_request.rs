@@ -105,6 +105,21 @@ pub enum RpcRequest {
     EthTraceCallMany,
     EthTraceReplayTransaction,
     EthTraceReplayBlock,
+    VelasGetLogsCount,
+    VelasGetBlockRange,
+    VelasGetBlockByTimestamp,
+    VelasGetContractCreation,
+    VelasGetStorageAtMulti,
+    VelasGetReceiptWithTrace,
+    VelasGetBlocksByHashes,
+    VelasEstimateGasRange,
+    VelasGetTransactionPosition,
+    VelasGetEffectiveGasPrice,
+    VelasGetBlockHashesByRange,
+    VelasReplayBlockTransactionsWithResults,
+    VelasGetLogsDelta,
+    VelasGetCodeSize,
+    VelasGetNonEmptyBlocks,
 
     /// Velas Account scope
     GetVelasAccountsByOperationalKey,
@@ -200,6 +215,23 @@ impl fmt::Display for RpcRequest {
             RpcRequest::EthEstimateGas => "eth_estimateGas",
             RpcRequest::EthGetLogs => "eth_getLogs",
             RpcRequest::EthSyncing => "eth_syncing",
+            RpcRequest::VelasGetLogsCount => "velas_getLogsCount",
+            RpcRequest::VelasGetBlockRange => "velas_getBlockRange",
+            RpcRequest::VelasGetBlockByTimestamp => "velas_getBlockByTimestamp",
+            RpcRequest::VelasGetContractCreation => "velas_getContractCreation",
+            RpcRequest::VelasGetStorageAtMulti => "velas_getStorageAtMulti",
+            RpcRequest::VelasGetReceiptWithTrace => "velas_getReceiptWithTrace",
+            RpcRequest::VelasGetBlocksByHashes => "velas_getBlocksByHashes",
+            RpcRequest::VelasEstimateGasRange => "velas_estimateGasRange",
+            RpcRequest::VelasGetTransactionPosition => "velas_getTransactionPosition",
+            RpcRequest::VelasGetEffectiveGasPrice => "velas_getEffectiveGasPrice",
+            RpcRequest::VelasGetBlockHashesByRange => "velas_getBlockHashesByRange",
+            RpcRequest::VelasReplayBlockTransactionsWithResults => {
+                "velas_replayBlockTransactionsWithResults"
+            }
+            RpcRequest::VelasGetLogsDelta => "velas_getLogsDelta",
+            RpcRequest::VelasGetCodeSize => "velas_getCodeSize",
+            RpcRequest::VelasGetNonEmptyBlocks => "velas_getNonEmptyBlocks",
             RpcRequest::GetVelasAccountsByOperationalKey => "getVelasAccountsByOperationalKey",
             RpcRequest::GetVelasAccountsByOwnerKey => "getVelasAccountsByOwnerKey",
             RpcRequest::GetVelasRelyingPartiesByOwnerKey => "getVelasRelyingPartiesByOwnerKey",
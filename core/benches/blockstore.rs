@@ -153,3 +153,80 @@ fn bench_insert_data_shred_big(bench: &mut Bencher) {
     });
     Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
 }
+
+// Insert EVM block with `num_transactions` transactions into the ledger
+fn setup_evm_block(blockstore: &Blockstore, num_transactions: u64) -> u64 {
+    let block_number = 1;
+    let slot = 1;
+    let secret_key = evm_state::SecretKey::from_slice(&[3u8; 32]).unwrap();
+    let transactions: Vec<_> = (0..num_transactions)
+        .map(|nonce| {
+            let tx = evm_state::UnsignedTransaction {
+                nonce: nonce.into(),
+                gas_price: 1.into(),
+                gas_limit: 21_000.into(),
+                action: evm_state::TransactionAction::Call(evm_state::H160::repeat_byte(0x11)),
+                value: 0.into(),
+                input: vec![],
+            }
+            .sign(&secret_key, None);
+            let hash = tx.tx_id_hash();
+            let receipt = evm_state::TransactionReceipt::new(
+                tx.into(),
+                21_000,
+                block_number,
+                nonce + 1,
+                vec![],
+                (evm_state::ExitSucceed::Stopped.into(), vec![]),
+            );
+            (hash, receipt)
+        })
+        .collect();
+    let header = evm_state::BlockHeader::new(
+        evm_state::H256::zero(),
+        0,
+        evm_state::H256::zero(),
+        block_number,
+        0,
+        0,
+        slot,
+        evm_state::H256::zero(),
+        transactions.iter(),
+        evm_state::BlockVersion::VersionConsistentHashes,
+    );
+    blockstore.write_evm_block_header(&header).unwrap();
+    for (hash, receipt) in transactions {
+        blockstore
+            .write_evm_transaction(block_number, slot, hash, receipt)
+            .unwrap();
+    }
+    block_number
+}
+
+// Read full EVM block, with all transaction receipts
+#[bench]
+#[ignore]
+fn bench_read_evm_block(bench: &mut Bencher) {
+    let ledger_path = get_tmp_ledger_path!();
+    let blockstore =
+        Blockstore::open(&ledger_path).expect("Expected to be able to open database ledger");
+    let block_number = setup_evm_block(&blockstore, 1000);
+    bench.iter(move || {
+        let _ = blockstore.get_evm_block(block_number).unwrap();
+    });
+    Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+}
+
+// Read only header of the same EVM block, as done to resolve state root of block
+#[bench]
+#[ignore]
+fn bench_read_evm_block_header(bench: &mut Bencher) {
+    let ledger_path = get_tmp_ledger_path!();
+    let blockstore =
+        Blockstore::open(&ledger_path).expect("Expected to be able to open database ledger");
+    let block_number = setup_evm_block(&blockstore, 1000);
+    bench.iter(move || {
+        let _ = blockstore.get_evm_block_header(block_number).unwrap();
+    });
+    Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+}
@@ -0,0 +1,355 @@
+use std::collections::{BTreeSet, HashMap};
+
+use evm_state::{H256, U256};
+use lru::LruCache;
+
+const BLOCK_TIMESTAMPS_CACHE_SIZE: usize = 4096;
+/// Default number of block hash to block number lookups, kept in cache.
+pub const BLOCK_HASH_CACHE_SIZE: usize = 1024;
+/// Number of topics in topic0 index.
+const TOPIC0_INDEX_SIZE: usize = 256;
+/// Maximum number of blocks with logs, stored for single topic.
+const TOPIC0_MAX_BLOCKS: usize = 100_000;
+
+/// Caches of evm rpc, shared between requests.
+///
+/// Values that depend on latest block are dropped by `on_evm_block_advanced`,
+/// which is called by block recorder after new evm block is written.
+/// Values of historical blocks are kept, so only confirmed blocks should be stored there.
+#[derive(Debug)]
+pub struct EvmRpcCache {
+    latest_block: Option<u64>,
+    // latest-dependent
+    /// Results of block by timestamp lookups, `(timestamp, round_up) -> block`.
+    /// Lookup near the chain tip can change with new blocks.
+    timestamp_lookups: HashMap<(u64, bool), Option<u64>>,
+    /// Gas price, suggested by oracle from latest blocks.
+    gas_price: Option<U256>,
+    // historical
+    /// Timestamps of confirmed blocks.
+    block_timestamps: LruCache<u64, u64>,
+    /// Numbers of blocks by their hashes.
+    /// Number is part of hashed header, so entry stays valid after reorg,
+    /// but block with this hash may be no longer canonical, which is checked by caller.
+    block_ids: LruCache<H256, u64>,
+    /// Blocks with logs of event signature (topic0), filled by block recorder if index is enabled.
+    /// Blocks of every fork are recorded, so caller should filter logs of canonical block.
+    topic0_index: Option<Topic0Index>,
+}
+
+#[derive(Debug)]
+struct Topic0Index {
+    topics: LruCache<H256, Topic0Entry>,
+    /// Range of recorded blocks, starting from the first block written after start.
+    recorded: Option<(u64, u64)>,
+    /// Last block, at which some topic was evicted, history of topic added later starts after it.
+    evicted_at: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Topic0Entry {
+    /// Every block with logs of topic, starting from this one, is in `blocks`.
+    from: u64,
+    blocks: BTreeSet<u64>,
+}
+
+impl Default for EvmRpcCache {
+    fn default() -> Self {
+        Self::new(BLOCK_HASH_CACHE_SIZE, false)
+    }
+}
+
+impl EvmRpcCache {
+    pub fn new(block_hash_cache_size: usize, topic0_index: bool) -> Self {
+        Self {
+            latest_block: None,
+            timestamp_lookups: HashMap::new(),
+            gas_price: None,
+            block_timestamps: LruCache::new(BLOCK_TIMESTAMPS_CACHE_SIZE),
+            block_ids: LruCache::new(block_hash_cache_size.max(1)),
+            topic0_index: topic0_index.then(|| Topic0Index {
+                topics: LruCache::new(TOPIC0_INDEX_SIZE),
+                recorded: None,
+                evicted_at: None,
+            }),
+        }
+    }
+
+    pub fn latest_block(&self) -> Option<u64> {
+        self.latest_block
+    }
+
+    /// Invalidation hook, called with number of latest written evm block.
+    /// Clears all latest-dependent caches if block advanced, returns true in this case.
+    pub fn on_evm_block_advanced(&mut self, block: u64) -> bool {
+        if matches!(self.latest_block, Some(latest) if latest >= block) {
+            return false;
+        }
+        self.latest_block = Some(block);
+        self.timestamp_lookups.clear();
+        self.gas_price = None;
+        true
+    }
+
+    pub fn timestamp_lookup(&self, timestamp: u64, round_up: bool) -> Option<Option<u64>> {
+        self.timestamp_lookups.get(&(timestamp, round_up)).copied()
+    }
+
+    /// Stores lookup result computed at `latest_block`, outdated results are ignored.
+    pub fn set_timestamp_lookup(
+        &mut self,
+        latest_block: u64,
+        timestamp: u64,
+        round_up: bool,
+        block: Option<u64>,
+    ) {
+        if self.latest_block == Some(latest_block) {
+            self.timestamp_lookups.insert((timestamp, round_up), block);
+        }
+    }
+
+    pub fn gas_price(&self) -> Option<U256> {
+        self.gas_price
+    }
+
+    /// Stores gas price computed at `latest_block`, outdated price is ignored.
+    pub fn set_gas_price(&mut self, latest_block: u64, gas_price: U256) {
+        if self.latest_block == Some(latest_block) {
+            self.gas_price = Some(gas_price);
+        }
+    }
+
+    pub fn block_timestamp(&mut self, block: u64) -> Option<u64> {
+        self.block_timestamps.get(&block).copied()
+    }
+
+    pub fn set_block_timestamp(&mut self, block: u64, timestamp: u64) {
+        self.block_timestamps.put(block, timestamp);
+    }
+
+    pub fn block_id_by_hash(&mut self, hash: &H256) -> Option<u64> {
+        self.block_ids.get(hash).copied()
+    }
+
+    pub fn set_block_id_by_hash(&mut self, hash: H256, block: u64) {
+        self.block_ids.put(hash, block);
+    }
+
+    /// Returns blocks in `from..=to` with logs of `topic`, if whole range was indexed.
+    /// Topic without recorded logs is not covered, it might have been evicted.
+    pub fn topic0_blocks(&mut self, topic: &H256, from: u64, to: u64) -> Option<Vec<u64>> {
+        let index = self.topic0_index.as_mut()?;
+        let (_, recorded_to) = index.recorded?;
+        let entry = index.topics.get(topic)?;
+        if from < entry.from || to > recorded_to {
+            return None;
+        }
+        Some(entry.blocks.range(from..=to).copied().collect())
+    }
+
+    /// Records event signatures of block logs in topic0 index,
+    /// called by block recorder for every written evm block.
+    pub fn index_block_topic0(&mut self, block: &evm_state::Block) {
+        let index = match self.topic0_index.as_mut() {
+            Some(index) => index,
+            None => return,
+        };
+        let number = block.header.block_number;
+        // blocks of other forks can go below recorded range, they don't extend it
+        let recorded = index.recorded.get_or_insert((number, number));
+        recorded.1 = recorded.1.max(number);
+        let recorded_from = recorded.0;
+
+        let topics: BTreeSet<H256> = block
+            .transactions
+            .iter()
+            .flat_map(|(_, receipt)| receipt.logs.iter())
+            .filter_map(|log| log.topics.first().copied())
+            .collect();
+        for topic in topics {
+            if index.topics.get(&topic).is_none() {
+                let entry = Topic0Entry {
+                    from: index.evicted_at.map_or(recorded_from, |block| block + 1),
+                    blocks: BTreeSet::new(),
+                };
+                if index.topics.len() == index.topics.cap() {
+                    index.evicted_at = Some(number);
+                }
+                index.topics.put(topic, entry);
+            }
+            let entry = index
+                .topics
+                .get_mut(&topic)
+                .expect("entry is inserted above");
+            if entry.blocks.len() >= TOPIC0_MAX_BLOCKS {
+                entry.from = number;
+                entry.blocks.clear();
+            }
+            entry.blocks.insert(number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance_clears_only_latest_dependent() {
+        let mut cache = EvmRpcCache::default();
+        assert!(cache.on_evm_block_advanced(10));
+        cache.set_timestamp_lookup(10, 1000, false, Some(10));
+        cache.set_gas_price(10, 7.into());
+        cache.set_block_timestamp(5, 500);
+        assert_eq!(cache.timestamp_lookup(1000, false), Some(Some(10)));
+
+        // same or older block doesn't invalidate anything
+        assert!(!cache.on_evm_block_advanced(10));
+        assert!(!cache.on_evm_block_advanced(9));
+        assert_eq!(cache.timestamp_lookup(1000, false), Some(Some(10)));
+        assert_eq!(cache.gas_price(), Some(7.into()));
+
+        assert!(cache.on_evm_block_advanced(11));
+        assert_eq!(cache.latest_block(), Some(11));
+        assert_eq!(cache.timestamp_lookup(1000, false), None);
+        assert_eq!(cache.gas_price(), None);
+
+        // price computed at older block is not stored
+        cache.set_gas_price(10, 7.into());
+        assert_eq!(cache.gas_price(), None);
+        assert_eq!(cache.block_timestamp(5), Some(500));
+    }
+
+    fn block_with_topics(block_number: u64, topics: &[H256]) -> evm_state::Block {
+        let logs = topics
+            .iter()
+            .map(|topic| evm_state::Log {
+                address: evm_state::H160::zero(),
+                topics: vec![*topic],
+                data: vec![],
+            })
+            .collect();
+        let transaction = evm_state::UnsignedTransaction {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas_limit: 0.into(),
+            action: evm_state::TransactionAction::Create,
+            value: 0.into(),
+            input: vec![],
+        };
+        let receipt = evm_state::TransactionReceipt::new(
+            evm_state::TransactionInReceipt::Unsigned(evm_state::UnsignedTransactionWithCaller {
+                unsigned_tx: transaction,
+                caller: evm_state::H160::zero(),
+                chain_id: 0,
+                signed_compatible: true,
+            }),
+            0,
+            block_number,
+            0,
+            logs,
+            (evm_state::ExitSucceed::Stopped.into(), vec![]),
+        );
+        let transactions = vec![(H256::repeat_byte(block_number as u8), receipt)];
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            H256::zero(),
+            block_number,
+            0,
+            0,
+            block_number,
+            H256::zero(),
+            transactions.iter(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        evm_state::Block {
+            header,
+            transactions,
+        }
+    }
+
+    #[test]
+    fn test_topic0_index_coverage() {
+        let topic = H256::repeat_byte(1);
+        let other_topic = H256::repeat_byte(2);
+
+        // disabled index records nothing
+        let mut cache = EvmRpcCache::default();
+        cache.index_block_topic0(&block_with_topics(1, &[topic]));
+        assert_eq!(cache.topic0_blocks(&topic, 1, 1), None);
+
+        let mut cache = EvmRpcCache::new(BLOCK_HASH_CACHE_SIZE, true);
+        for block_number in 5..=10 {
+            let topics = match block_number {
+                6 => vec![topic, topic, other_topic],
+                9 => vec![topic],
+                _ => vec![other_topic],
+            };
+            cache.index_block_topic0(&block_with_topics(block_number, &topics));
+        }
+        assert_eq!(cache.topic0_blocks(&topic, 5, 10), Some(vec![6, 9]));
+        assert_eq!(cache.topic0_blocks(&topic, 7, 10), Some(vec![9]));
+        assert_eq!(
+            cache.topic0_blocks(&other_topic, 5, 8),
+            Some(vec![5, 6, 7, 8])
+        );
+        // before first recorded block and after last one
+        assert_eq!(cache.topic0_blocks(&topic, 4, 10), None);
+        assert_eq!(cache.topic0_blocks(&topic, 5, 11), None);
+        // topic without logs
+        assert_eq!(cache.topic0_blocks(&H256::repeat_byte(3), 5, 10), None);
+
+        // block of other fork is recorded too
+        cache.index_block_topic0(&block_with_topics(10, &[topic]));
+        assert_eq!(cache.topic0_blocks(&topic, 5, 10), Some(vec![6, 9, 10]));
+
+        // indexed data survives block advance
+        cache.on_evm_block_advanced(100);
+        assert_eq!(cache.topic0_blocks(&topic, 5, 10), Some(vec![6, 9, 10]));
+    }
+
+    #[test]
+    fn test_topic0_index_eviction() {
+        let mut cache = EvmRpcCache::new(BLOCK_HASH_CACHE_SIZE, true);
+        let topic = |i: usize| H256::from_low_u64_be(i as u64);
+        for i in 0..TOPIC0_INDEX_SIZE {
+            cache.index_block_topic0(&block_with_topics(1, &[topic(i)]));
+        }
+        assert_eq!(cache.topic0_blocks(&topic(0), 1, 1), Some(vec![1]));
+
+        // new topic evicts least recently used one
+        let new_topic = topic(TOPIC0_INDEX_SIZE);
+        cache.index_block_topic0(&block_with_topics(2, &[new_topic]));
+        assert_eq!(cache.topic0_blocks(&topic(1), 1, 2), None);
+        assert_eq!(cache.topic0_blocks(&new_topic, 2, 2), Some(vec![2]));
+
+        // history of topics, added after eviction, starts after evicting block
+        cache.index_block_topic0(&block_with_topics(3, &[topic(1)]));
+        assert_eq!(cache.topic0_blocks(&topic(1), 1, 3), None);
+        assert_eq!(cache.topic0_blocks(&topic(1), 3, 3), Some(vec![3]));
+    }
+
+    #[test]
+    fn test_block_ids_evicted_by_size() {
+        let mut cache = EvmRpcCache::new(2, false);
+        for block in 1..=3 {
+            cache.set_block_id_by_hash(H256::repeat_byte(block as u8), block);
+        }
+        assert_eq!(cache.block_id_by_hash(&H256::repeat_byte(1)), None);
+        assert_eq!(cache.block_id_by_hash(&H256::repeat_byte(3)), Some(3));
+
+        // block ids survive block advance
+        cache.on_evm_block_advanced(100);
+        assert_eq!(cache.block_id_by_hash(&H256::repeat_byte(2)), Some(2));
+    }
+
+    #[test]
+    fn test_outdated_lookup_ignored() {
+        let mut cache = EvmRpcCache::default();
+        cache.on_evm_block_advanced(11);
+        // computed before block 11 was seen
+        cache.set_timestamp_lookup(10, 1000, true, None);
+        assert_eq!(cache.timestamp_lookup(1000, true), None);
+    }
+}
@@ -8,27 +8,52 @@ use crate::rpc::JsonRpcRequestProcessor;
 use evm_rpc::error::EvmStateError;
 use evm_rpc::{
     chain::ChainERPC,
-    error::{into_native_error, BlockNotFound, Error, StateNotFoundForBlock},
+    error::{
+        into_native_error, BlockNotFound, Error, InputTooLarge, RlpError, StateNotFoundForBlock,
+    },
     general::GeneralERPC,
-    trace::{TraceERPC, TraceMeta},
-    BlockId, BlockRelId, Bytes, Either, Hex, RPCBlock, RPCLog, RPCLogFilter, RPCReceipt,
-    RPCTopicFilter, RPCTransaction,
-};
-use evm_state::{
-    AccountProvider, AccountState, Address, Gas, LogFilter, TransactionAction, H160, H256, U256,
+    trace::{TraceERPC, TraceMeta, TraceResultsWithTransactionHash},
+    velas::VelasERPC,
+    BlockId, BlockRelId, Bytes, Either, Hex, RPCBlock, RPCBlockRange, RPCContractCreation,
+    RPCGasRange, RPCLog, RPCLogFilter, RPCLogsCount, RPCLogsDelta, RPCMempoolStats,
+    RPCNonEmptyBlocks, RPCReceipt, RPCReceiptWithTrace, RPCReorgStats, RPCTopicFilter,
+    RPCTransaction, RPCTransactionLookup, RPCTransactionPosition, RPCTransactionReplay,
+    RPCTransactionStatus, StateOverrides,
 };
+use evm_state::{AccountProvider, AccountState, Address, Gas, TransactionAction, H160, H256, U256};
 use jsonrpc_core::BoxFuture;
 use snafu::ensure;
 use snafu::ResultExt;
 use solana_runtime::bank::Bank;
-use std::{cell::RefCell, future::ready, sync::Arc};
+use lru::LruCache;
+use std::{
+    cell::RefCell,
+    future::ready,
+    sync::{Arc, Mutex},
+};
 use crate::rpc_health::RpcHealthStatus;
 
-const GAS_PRICE: u64 = 3;
+mod cache;
+pub use cache::{EvmRpcCache, BLOCK_HASH_CACHE_SIZE};
+
+/// Default maximum number of blocks in eth_getLogs range.
+pub const MAX_NUM_BLOCKS: u64 = 2000;
+/// Maximum number of slots in velas_getStorageAtMulti request.
+const MAX_STORAGE_SLOTS: usize = 1024;
+/// Maximum number of hashes in velas_getBlocksByHashes request.
+const MAX_BLOCK_HASHES: usize = 256;
+/// Default maximum number of transactions in block, returned with `full` flag.
+pub const MAX_FULL_BLOCK_TRANSACTIONS: usize = 10_000;
+/// Maximum number of executions in binary search of eth_estimateGas.
+const MAX_ESTIMATE_GAS_ITERATIONS: usize = 20;
+/// Default percent of gas, added on top of minimal gas limit found by eth_estimateGas.
+pub const ESTIMATE_GAS_BUFFER_PERCENT: u64 = 5;
+/// Default maximum size in bytes of web3_sha3 input.
+pub const MAX_INPUT_SIZE: usize = 1024 * 1024;
 
 use tracing_attributes::instrument;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StateRootWithBank {
     pub state_root: Option<H256>,
     pub bank: Option<Arc<Bank>>,
@@ -95,6 +120,45 @@ impl StateRootWithBank {
             .get_storage_at(root, address, idx)
             .unwrap_or_default())
     }
+
+    /// Reads storage values of `address` at `indexes` from the same state, unset values are zero.
+    pub fn get_storages_at(
+        &self,
+        meta: &JsonRpcRequestProcessor,
+        address: H160,
+        indexes: &[H256],
+    ) -> Result<Vec<H256>, Error> {
+        ensure!(
+            self.state_root.is_some(),
+            BlockNotFound { block: self.block }
+        );
+
+        let root = *self.state_root.as_ref().unwrap();
+        if let Some(bank) = &self.bank {
+            let evm = bank.evm_state.read().unwrap();
+
+            assert!(evm.last_root() == root, "we store bank with invalid root");
+            return Ok(indexes
+                .iter()
+                .map(|idx| evm.get_storage(address, *idx).unwrap_or_default())
+                .collect());
+        }
+        let archive_evm_state = meta
+            .evm_state_archive(self.block_timestamp)
+            .ok_or(Error::ArchiveNotSupported)?;
+        ensure!(
+            archive_evm_state.kvs().check_root_exist(root),
+            StateNotFoundForBlock { block: self.block }
+        );
+        Ok(indexes
+            .iter()
+            .map(|idx| {
+                archive_evm_state
+                    .get_storage_at(root, address, *idx)
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
 }
 
 #[instrument(skip(meta))]
@@ -122,6 +186,14 @@ async fn block_to_state_root(
         BlockId::RelativeId(BlockRelId::Earliest) | BlockId::Num(Hex(0)) => {
             meta.get_first_available_evm_block().await
         }
+        BlockId::RelativeId(BlockRelId::Finalized) => {
+            let block_num = meta.get_finalized_evm_block();
+            return tagged_block_to_state_root(block_num, block_id, meta).await;
+        }
+        BlockId::RelativeId(BlockRelId::Safe) => {
+            let block_num = meta.get_safe_evm_block();
+            return tagged_block_to_state_root(block_num, block_id, meta).await;
+        }
         BlockId::Num(num) => num.0,
         BlockId::BlockHash { block_hash } => {
             found_block_hash = Some(block_hash.0);
@@ -137,24 +209,56 @@ async fn block_to_state_root(
             }
         }
     };
+    let header = meta
+        .get_evm_block_header_by_id(block_num)
+        .await
+        .map(|(header, _)| header);
     StateRootWithBank {
-        state_root: meta
-            .get_evm_block_by_id(block_num) // TODO: don't request full block.
-            .await
-            .filter(|(b, _)| {
+        state_root: header
+            .as_ref()
+            .filter(|header| {
                 // if requested specific block hash, check that block with this hash is not in reorged fork
                 found_block_hash
-                    .map(|block_hash| b.header.hash() == block_hash)
+                    .map(|block_hash| header.hash() == block_hash)
                     .unwrap_or(true)
             })
-            .map(|(b, _)| b.header.state_root),
+            .map(|header| header.state_root),
 
         bank: None,
         block: block_id,
-        block_timestamp: meta
-            .get_evm_block_by_id(block_num)
-            .await
-            .map(|(block, _)| block.header.timestamp),
+        block_timestamp: header.map(|header| header.timestamp),
+    }
+}
+
+/// State of block, resolved from `finalized` or `safe` tag.
+async fn tagged_block_to_state_root(
+    block_num: Option<u64>,
+    block_id: BlockId,
+    meta: &JsonRpcRequestProcessor,
+) -> StateRootWithBank {
+    let header = match block_num {
+        Some(block_num) => meta.get_evm_block_header_by_id(block_num).await,
+        None => None,
+    };
+    let header = match header {
+        Some((header, _)) => header,
+        None => {
+            return StateRootWithBank {
+                state_root: None,
+                bank: None,
+                block: block_id,
+                block_timestamp: None,
+            }
+        }
+    };
+    // tagged block is usually the last one, so its state can be read from bank without archive
+    let bank = meta.bank(Some(CommitmentConfig::processed()));
+    let last_root = bank.evm_state.read().unwrap().last_root();
+    StateRootWithBank {
+        state_root: Some(header.state_root),
+        bank: (last_root == header.state_root).then(|| bank),
+        block: block_id,
+        block_timestamp: Some(header.timestamp),
     }
 }
 
@@ -176,6 +280,8 @@ async fn block_parse_confirmed_num(
                 evm.block_number().saturating_sub(1)
             }))
         }
+        BlockId::RelativeId(BlockRelId::Finalized) => meta.get_finalized_evm_block(),
+        BlockId::RelativeId(BlockRelId::Safe) => meta.get_safe_evm_block(),
 
         BlockId::Num(num) => Some(num.0),
     }
@@ -189,7 +295,15 @@ impl GeneralERPC for GeneralErpcImpl {
         Ok(String::from("velas-chain/v0.5.0"))
     }
 
-    fn sha3(&self, _meta: Self::Metadata, bytes: Bytes) -> Result<Hex<H256>, Error> {
+    fn sha3(&self, meta: Self::Metadata, bytes: Bytes) -> Result<Hex<H256>, Error> {
+        let max_size = meta.evm_max_input_size();
+        ensure!(
+            bytes.0.len() <= max_size,
+            InputTooLarge {
+                size: bytes.0.len(),
+                max_size
+            }
+        );
         Ok(Hex(H256::from_slice(
             Keccak256::digest(bytes.0.as_slice()).as_slice(),
         )))
@@ -237,10 +351,12 @@ impl GeneralERPC for GeneralErpcImpl {
         Ok(Hex(0.into()))
     }
 
-    fn gas_price(&self, _meta: Self::Metadata) -> Result<Hex<Gas>, Error> {
-        Ok(Hex(
-            solana_evm_loader_program::scope::evm::lamports_to_gwei(GAS_PRICE),
-        ))
+    fn gas_price(&self, meta: Self::Metadata) -> Result<Hex<Gas>, Error> {
+        Ok(Hex(oracle_gas_price(&meta)))
+    }
+
+    fn max_priority_fee_per_gas(&self, meta: Self::Metadata) -> Result<Hex<Gas>, Error> {
+        Ok(Hex(meta.gas_price_config().max_priority_fee_per_gas()))
     }
 }
 
@@ -314,14 +430,13 @@ impl ChainERPC for ChainErpcImpl {
         &self,
         meta: Self::Metadata,
         block: BlockId,
-    ) -> BoxFuture<Result<Hex<usize>, Error>> {
+    ) -> BoxFuture<Result<Option<Hex<usize>>, Error>> {
         Box::pin(async move {
-            let (evm_block, _) = match block_parse_confirmed_num(Some(block), &meta).await {
+            let evm_block = match block_parse_confirmed_num(Some(block), &meta).await {
                 Some(num) => meta.get_evm_block_by_id(num).await,
                 None => None,
-            }
-                .ok_or(Error::BlockNotFound { block })?;
-            Ok(Hex(evm_block.transactions.len()))
+            };
+            Ok(evm_block.map(|(evm_block, _)| Hex(evm_block.transactions.len())))
         })
     }
 
@@ -330,14 +445,10 @@ impl ChainERPC for ChainErpcImpl {
         &self,
         meta: Self::Metadata,
         block_hash: Hex<H256>,
-    ) -> BoxFuture<Result<Hex<usize>, Error>> {
+    ) -> BoxFuture<Result<Option<Hex<usize>>, Error>> {
         Box::pin(async move {
-            let (evm_block, _) = match meta.get_evm_block_id_by_hash(block_hash.0).await {
-                Some(num) => meta.get_evm_block_by_id(num).await,
-                None => None,
-            }
-                .ok_or(Error::BlockNotFound { block: BlockId::BlockHash { block_hash } })?;
-            Ok(Hex(evm_block.transactions.len()))
+            let evm_block = meta.get_evm_block_by_hash(block_hash.0).await;
+            Ok(evm_block.map(|(evm_block, _)| Hex(evm_block.transactions.len())))
         })
     }
 
@@ -367,20 +478,25 @@ impl ChainERPC for ChainErpcImpl {
     ) -> BoxFuture<Result<Option<RPCBlock>, Error>> {
         debug!("Requested hash = {:?}", block_hash.0);
         Box::pin(async move {
-            let block = match meta.get_evm_block_id_by_hash(block_hash.0).await {
+            let (block, confirmed) = match meta.get_evm_block_by_hash(block_hash.0).await {
                 None => {
                     error!("Not found block for hash:{}", block_hash);
                     return Ok(None);
                 }
-                Some(b) => match meta.get_evm_block_by_id(b).await {
-                    // check that found block only in valid fork.
-                    Some(block) if block.0.header.hash() == block_hash.0 => b,
-                    _ => return Ok(None),
-                },
+                Some(block) => block,
             };
-            debug!("Found block = {:?}", block);
-
-            block_by_number(meta, block.into(), full).await
+            debug!("Found block = {:?}", block.header.block_number);
+
+            let max_full_transactions = meta.evm_max_full_block_transactions();
+            let chain_id = meta.bank(None).evm_chain_id;
+            rpc_block(
+                block,
+                confirmed,
+                full,
+                chain_id,
+                Some(max_full_transactions),
+            )
+            .map(Some)
         })
     }
 
@@ -391,7 +507,13 @@ impl ChainERPC for ChainErpcImpl {
         block: BlockId,
         full: bool,
     ) -> BoxFuture<Result<Option<RPCBlock>, Error>> {
-        Box::pin(block_by_number(meta, block, full))
+        let max_full_transactions = meta.evm_max_full_block_transactions();
+        Box::pin(block_by_number(
+            meta,
+            block,
+            full,
+            Some(max_full_transactions),
+        ))
     }
 
     #[instrument(skip(self, meta))]
@@ -413,11 +535,10 @@ impl ChainERPC for ChainErpcImpl {
         let bank = meta.bank(None);
         let chain_id = bank.evm_chain_id;
         Box::pin(async move {
-            let (evm_block, _) = match meta.get_evm_block_id_by_hash(block_hash.0).await {
-                Some(num) => meta.get_evm_block_by_id(num).await,
-                None => None,
-            }
-                .ok_or(Error::BlockNotFound { block: BlockId::BlockHash { block_hash } })?;
+            let evm_block = match meta.get_evm_block_by_hash(block_hash.0).await {
+                Some((evm_block, _)) => evm_block,
+                None => return Ok(None),
+            };
             match evm_block.transactions.get(tx_id.0) {
                 Some((hash, receipt)) => Ok(Some(RPCTransaction::new_from_receipt(
                     receipt.clone(),
@@ -440,11 +561,14 @@ impl ChainERPC for ChainErpcImpl {
         let bank = meta.bank(None);
         let chain_id = bank.evm_chain_id;
         Box::pin(async move {
-            let (evm_block, _) = match block_parse_confirmed_num(Some(block), &meta).await {
+            let evm_block = match block_parse_confirmed_num(Some(block), &meta).await {
                 Some(num) => meta.get_evm_block_by_id(num).await,
                 None => None,
-            }
-                .ok_or(Error::BlockNotFound { block })?;
+            };
+            let evm_block = match evm_block {
+                Some((evm_block, _)) => evm_block,
+                None => return Ok(None),
+            };
             match evm_block.transactions.get(tx_id.0) {
                 Some((hash, receipt)) => Ok(Some(RPCTransaction::new_from_receipt(
                     receipt.clone(),
@@ -491,6 +615,7 @@ impl ChainERPC for ChainErpcImpl {
         tx: RPCTransaction,
         block: Option<BlockId>,
         meta_keys: Option<Vec<String>>,
+        state_overrides: Option<StateOverrides>,
     ) -> BoxFuture<Result<Bytes, Error>> {
         let meta_keys = match meta_keys
             .into_iter()
@@ -505,7 +630,13 @@ impl ChainERPC for ChainErpcImpl {
         Box::pin(async move {
             let saved_state = block_to_state_root(block, &meta).await;
 
-            let result = call(meta, tx, saved_state, meta_keys)?;
+            let result = call(
+                meta,
+                tx,
+                saved_state,
+                meta_keys,
+                state_overrides.as_ref(),
+            )?;
             Ok(Bytes(result.exit_data))
         })
     }
@@ -527,8 +658,9 @@ impl ChainERPC for ChainErpcImpl {
                 .collect::<Result<Vec<_>, _>>()
                 .map_err(|e| into_native_error(e, false))?;
             let saved_state = block_to_state_root(block, &meta).await;
-            let result = call(meta, tx, saved_state, meta_keys)?;
-            Ok(Hex(result.used_gas.into()))
+            let buffer_percent = meta.evm_estimate_gas_buffer_percent();
+            let gas = estimate_gas(meta, tx, saved_state, meta_keys, buffer_percent)?;
+            Ok(Hex(gas))
         })
     }
 
@@ -539,7 +671,6 @@ impl ChainERPC for ChainErpcImpl {
         log_filter: RPCLogFilter,
     ) -> BoxFuture<Result<Vec<RPCLog>, Error>> {
         Box::pin(async move {
-            const MAX_NUM_BLOCKS: u64 = 2000;
             let block_num = meta
                 .get_last_available_evm_block()
                 .ok_or(Error::ArchiveNotSupported)?;
@@ -549,7 +680,8 @@ impl ChainERPC for ChainErpcImpl {
             let from = block_parse_confirmed_num(log_filter.from_block, &meta)
                 .await
                 .unwrap_or(block_num);
-            if to > from + MAX_NUM_BLOCKS {
+            let max_blocks = meta.evm_max_logs_blocks();
+            if to > from + max_blocks {
                 warn!(
                     "Log filter, block range is too big, reducing, to={}, from={}",
                     to, from
@@ -557,33 +689,11 @@ impl ChainERPC for ChainErpcImpl {
                 return Err(Error::InvalidBlocksRange {
                     starting: from,
                     ending: to,
-                    batch_size: Some(MAX_NUM_BLOCKS),
+                    batch_size: Some(max_blocks),
                 });
             }
 
-            let filter = LogFilter {
-                address: log_filter
-                    .address
-                    .map(|k| match k {
-                        Either::Left(v) => v.into_iter().map(|k| k.0).collect(),
-                        Either::Right(k) => vec![k.0],
-                    })
-                    .unwrap_or_default(),
-                topics: log_filter
-                    .topics
-                    .into_iter()
-                    .flatten()
-                    .map(RPCTopicFilter::into_topics)
-                    .collect(),
-                from_block: from,
-                to_block: to,
-            };
-            debug!("filter = {:?}", filter);
-
-            let logs = meta.filter_logs(filter).await.map_err(|e| {
-                debug!("filter_logs error = {:?}", e);
-                into_native_error(e, false)
-            })?;
+            let logs = filter_logs(&meta, log_filter, from, to).await?;
             Ok(logs.into_iter().map(|l| l.into()).collect())
         })
     }
@@ -623,7 +733,26 @@ impl ChainERPC for ChainErpcImpl {
     }
 }
 
-pub struct TraceErpcImpl;
+/// Maximum number of replayed transaction traces kept in memory
+const TRACE_CACHE_SIZE: usize = 1024;
+
+/// Transaction hash, requested trace types and meta keys, that affect replay result
+type TraceCacheKey = (H256, Vec<String>, Option<Vec<String>>);
+
+#[derive(Clone)]
+pub struct TraceErpcImpl {
+    /// Traces of transactions in rooted blocks are deterministic, so they are never invalidated
+    replay_cache: Arc<Mutex<LruCache<TraceCacheKey, TraceResultsWithTransactionHash>>>,
+}
+
+impl Default for TraceErpcImpl {
+    fn default() -> Self {
+        Self {
+            replay_cache: Arc::new(Mutex::new(LruCache::new(TRACE_CACHE_SIZE))),
+        }
+    }
+}
+
 impl TraceERPC for TraceErpcImpl {
     type Metadata = JsonRpcRequestProcessor;
 
@@ -664,6 +793,12 @@ impl TraceERPC for TraceErpcImpl {
         meta_info: Option<TraceMeta>,
     ) -> BoxFuture<Result<Option<evm_rpc::trace::TraceResultsWithTransactionHash>, Error>> {
         let meta_info = meta_info.unwrap_or_default();
+        let replay_cache = self.replay_cache.clone();
+        let cache_key = (tx_hash.0, traces.clone(), meta_info.meta_keys.clone());
+        if let Some(cached) = replay_cache.lock().unwrap().get(&cache_key) {
+            debug!("Trace of transaction {:?} found in cache", tx_hash);
+            return Box::pin(ready(Ok(Some(cached.clone()))));
+        }
         Box::pin(async move {
             match transaction_by_hash(meta.clone(), tx_hash).await {
                 Ok(Some(tx)) => {
@@ -697,8 +832,16 @@ impl TraceERPC for TraceErpcImpl {
                         None => return Ok(None),
                     };
 
+                    // block that isn't rooted yet can be replaced by other fork
+                    let rooted = meta
+                        .get_last_confirmed_evm_block()
+                        .map_or(false, |confirmed| tx_block <= confirmed);
                     let traces = trace_call_many(meta, tx_traces, Some(base_block)).await?;
-                    Ok(traces.get(tx_index - 1).cloned())
+                    let trace = traces.get(tx_index - 1).cloned();
+                    if let Some(trace) = trace.as_ref().filter(|_| rooted) {
+                        replay_cache.lock().unwrap().put(cache_key, trace.clone());
+                    }
+                    Ok(trace)
                 },
                 Ok(None) => Ok(None),
                 Err(e) => Err(e),
@@ -715,11 +858,12 @@ impl TraceERPC for TraceErpcImpl {
         meta_info: Option<TraceMeta>,
     ) -> BoxFuture<Result<Vec<evm_rpc::trace::TraceResultsWithTransactionHash>, Error>> {
         Box::pin(async move {
-            let block = if let Some(block) = block_by_number(meta.clone(), block_num, true).await? {
-                block
-            } else {
-                return Err(Error::StateNotFoundForBlock { block: block_num });
-            };
+            let block =
+                if let Some(block) = block_by_number(meta.clone(), block_num, true, None).await? {
+                    block
+                } else {
+                    return Err(Error::StateNotFoundForBlock { block: block_num });
+                };
             let txs = match block.transactions {
                 Either::Right(txs) => txs,
                 _ => return Err(Error::Unimplemented {}),
@@ -746,329 +890,2837 @@ impl TraceERPC for TraceErpcImpl {
     }
 }
 
-struct TxOutput {
-    exit_reason: evm_state::ExitReason,
-    exit_data: Vec<u8>,
-    used_gas: u64,
-    traces: Vec<evm_state::executor::Trace>,
+#[derive(Default)]
+pub struct VelasErpcImpl {
+    /// Shares replay cache with trace rpc, to serve receipts with traces.
+    trace: TraceErpcImpl,
 }
 
-#[instrument(skip(meta))]
-fn call(
-    meta: JsonRpcRequestProcessor,
-    tx: RPCTransaction,
-    saved_state: StateRootWithBank,
-    meta_keys: Vec<solana_sdk::pubkey::Pubkey>,
-) -> Result<TxOutput, Error> {
-    let outputs = call_many(meta, &[(tx, meta_keys)], saved_state)?;
+impl VelasErpcImpl {
+    pub fn new(trace: TraceErpcImpl) -> Self {
+        Self { trace }
+    }
+}
 
-    let TxOutput {
-        exit_reason,
-        exit_data,
-        used_gas,
-        traces,
-    } = outputs
-        .into_iter()
-        .next()
-        .expect("Should contain result for tx.");
+impl VelasERPC for VelasErpcImpl {
+    type Metadata = JsonRpcRequestProcessor;
 
-    let (_, exit_data) = evm_rpc::handle_evm_exit_reason(exit_reason.clone(), exit_data)?;
+    #[instrument(skip(self, meta))]
+    fn logs_count(
+        &self,
+        meta: Self::Metadata,
+        log_filter: RPCLogFilter,
+    ) -> BoxFuture<Result<RPCLogsCount, Error>> {
+        Box::pin(async move {
+            let block_num = meta
+                .get_last_available_evm_block()
+                .ok_or(Error::ArchiveNotSupported)?;
+            let to = block_parse_confirmed_num(log_filter.to_block, &meta)
+                .await
+                .unwrap_or(block_num);
+            let from = block_parse_confirmed_num(log_filter.from_block, &meta)
+                .await
+                .unwrap_or(block_num);
+            // count only allowed part of range
+            let max_to = from.saturating_add(meta.evm_max_logs_blocks());
+            let capped = to > max_to;
+            let filter = log_filter.into_log_filter(from, to.min(max_to));
 
-    Ok(TxOutput {
-        exit_reason,
-        exit_data,
-        used_gas,
-        traces,
-    })
-}
+            let count = meta.count_logs(filter).await.map_err(|e| {
+                debug!("count_logs error = {:?}", e);
+                into_native_error(e, false)
+            })?;
+            Ok(RPCLogsCount {
+                count: Hex(count),
+                capped,
+            })
+        })
+    }
 
-#[instrument(skip(meta))]
-fn call_many(
-    meta: JsonRpcRequestProcessor,
-    txs: &[(RPCTransaction, Vec<solana_sdk::pubkey::Pubkey>)],
-    saved_state: StateRootWithBank,
-) -> Result<Vec<TxOutput>, Error> {
-    // if we already found bank with some root, or we just cannot find state_root - use latest.
-    let use_latest_state = saved_state.bank.is_some() || saved_state.state_root.is_none();
-    let bank = saved_state
-        .bank
-        .unwrap_or_else(|| meta.bank(Some(CommitmentConfig::processed())));
+    #[instrument(skip(self, meta))]
+    fn block_range(&self, meta: Self::Metadata) -> BoxFuture<Result<RPCBlockRange, Error>> {
+        Box::pin(async move {
+            let latest = meta
+                .get_last_available_evm_block()
+                .ok_or(Error::ArchiveNotSupported)?;
+            let earliest = meta.get_first_available_evm_block().await;
+            Ok(RPCBlockRange {
+                earliest: Hex(earliest),
+                latest: Hex(latest),
+            })
+        })
+    }
 
-    let evm_state = if use_latest_state {
-        // keep current bank to allow simulating on latest state without archive
-        match bank.evm_state.read().unwrap().clone() {
-            evm_state::EvmState::Incomming(i) => i,
-            evm_state::EvmState::Committed(c) => {
-                c.next_incomming(bank.clock().unix_timestamp as u64)
+    #[instrument(skip(self, meta))]
+    fn transactions_compact(
+        &self,
+        meta: Self::Metadata,
+        tx_hashes: Vec<Hex<H256>>,
+        fields: Vec<String>,
+    ) -> BoxFuture<Result<Vec<Option<serde_json::Value>>, Error>> {
+        Box::pin(async move {
+            RPCTransaction::validate_compact_request(tx_hashes.len(), &fields)?;
+            let mut result = Vec::with_capacity(tx_hashes.len());
+            for tx_hash in tx_hashes {
+                let tx = transaction_by_hash(meta.clone(), tx_hash).await?;
+                result.push(tx.map(|tx| tx.to_compact(&fields)));
             }
-        }
-    } else {
-        let root = saved_state.state_root.unwrap();
-        meta.evm_state_archive(saved_state.block_timestamp)
-            .ok_or(Error::ArchiveNotSupported)?
-            .new_incomming_for_root(root)
-            .ok_or(Error::StateNotFoundForBlock {
-                block: saved_state.block,
-            })?
-    };
+            Ok(result)
+        })
+    }
 
-    let estimate_config = evm_state::EvmConfig {
-        estimate: true,
-        chain_id: bank.evm_chain_id,
-        ..Default::default()
-    };
+    #[instrument(skip(self, meta))]
+    fn next_nonce(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+    ) -> BoxFuture<Result<Hex<U256>, Error>> {
+        // node has no mempool, so next nonce is always the state nonce
+        ChainErpcImpl.transaction_count(meta, address, None)
+    }
 
-    //TODO: Hashes actual to saved root
-    let last_hashes = bank.evm_hashes();
-    let mut executor = evm_state::Executor::with_config(
-        evm_state,
-        evm_state::ChainContext::new(last_hashes),
-        estimate_config,
-        evm_state::executor::FeatureSet::new(
-            bank.feature_set.is_active(
-                &solana_sdk::feature_set::velas::unsigned_tx_fix::id(),
-            ),
-            bank.feature_set.is_active(
-                &solana_sdk::feature_set::velas::clear_logs_on_error::id(),
-            ),
-        ),
-    );
+    #[instrument(skip(self, meta))]
+    fn block_by_timestamp(
+        &self,
+        meta: Self::Metadata,
+        timestamp: Hex<u64>,
+        round_up: bool,
+    ) -> BoxFuture<Result<Option<RPCBlock>, Error>> {
+        Box::pin(async move {
+            match block_number_by_timestamp(&meta, timestamp.0, round_up).await? {
+                Some(num) => block_by_number(meta, num.into(), false, None).await,
+                None => Ok(None),
+            }
+        })
+    }
 
-    debug!("running evm executor = {:?}", executor);
-    let mut result = Vec::new();
-    for (tx, meta_keys) in txs {
-        result.push(call_inner(
-            &mut executor,
-            tx.clone(),
-            meta_keys.clone(),
-            &*bank,
-        )?)
+    #[instrument(skip(self, meta))]
+    fn logs_for_contracts(
+        &self,
+        meta: Self::Metadata,
+        addresses: Vec<Hex<Address>>,
+        topics: Option<Vec<Option<RPCTopicFilter>>>,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+    ) -> BoxFuture<Result<Vec<RPCLog>, Error>> {
+        // empty address list in log filter matches any contract
+        if addresses.is_empty() {
+            return Box::pin(ready(Err(Error::InvalidParams {
+                details: "Expected at least one contract address".to_string(),
+            })));
+        }
+        let log_filter = RPCLogFilter {
+            from_block,
+            to_block,
+            address: Some(Either::Left(addresses)),
+            topics,
+        };
+        // range cap is checked by eth_getLogs
+        let logs = ChainErpcImpl.logs(meta, log_filter);
+        Box::pin(async move {
+            let mut logs = logs.await?;
+            logs.sort_by_key(|log| (log.block_number, log.transaction_index, log.log_index));
+            Ok(logs)
+        })
     }
-    Ok(result)
-}
 
-#[instrument(skip(executor, bank))]
-fn call_inner(
-    executor: &mut evm_state::Executor,
-    tx: RPCTransaction,
-    meta_keys: Vec<solana_sdk::pubkey::Pubkey>,
-    bank: &Bank,
-) -> Result<TxOutput, Error> {
-    use solana_evm_loader_program::precompiles::*;
-    let caller = tx.from.map(|a| a.0).unwrap_or_default();
+    #[instrument(skip(self, meta))]
+    fn find_transaction(
+        &self,
+        meta: Self::Metadata,
+        raw_tx: Bytes,
+    ) -> BoxFuture<Result<RPCTransactionLookup, Error>> {
+        let tx: evm_state::Transaction = match rlp::decode(&raw_tx.0).with_context(|| RlpError {
+            struct_name: "RawTransaction".to_string(),
+            input_data: hex::encode(&raw_tx.0),
+        }) {
+            Ok(tx) => tx,
+            Err(e) => return Box::pin(ready(Err(e))),
+        };
+        let hash = Hex(tx.tx_id_hash());
+        Box::pin(async move {
+            // node has no mempool, so transaction is either mined or unknown
+            let receipt = ChainErpcImpl.transaction_receipt(meta, hash).await?;
+            let status = if receipt.is_some() {
+                RPCTransactionStatus::Mined
+            } else {
+                RPCTransactionStatus::Unknown
+            };
+            Ok(RPCTransactionLookup {
+                hash,
+                status,
+                receipt,
+            })
+        })
+    }
 
-    let value = tx.value.map(|a| a.0).unwrap_or_else(|| 0.into());
-    let input = tx.input.map(|a| a.0).unwrap_or_else(Vec::new);
-    let gas_limit = tx.gas.map(|a| a.0).unwrap_or_else(|| u64::MAX.into());
-    // On estimate set gas price to zero, to avoid out of funds errors.
-    let gas_price = u64::MIN.into();
+    #[instrument(skip(self, _meta))]
+    fn reorg_stats(&self, _meta: Self::Metadata) -> BoxFuture<Result<RPCReorgStats, Error>> {
+        // reorgs are tracked by bridge, that polls node
+        Box::pin(ready(Err(Error::Unimplemented {})))
+    }
 
-    let nonce = tx
-        .nonce
+    fn mempool_stats(&self, _meta: Self::Metadata) -> BoxFuture<Result<RPCMempoolStats, Error>> {
+        // node has no mempool
+        Box::pin(ready(Err(Error::Unimplemented {})))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn contract_creation(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+    ) -> BoxFuture<Result<Option<RPCContractCreation>, Error>> {
+        let creation =
+            meta.get_evm_contract_creation(address.0)
+                .map(|creation| RPCContractCreation {
+                    tx_hash: Hex(creation.tx_hash),
+                    block_number: Hex(creation.block_num),
+                    creator: Hex(creation.creator),
+                });
+        Box::pin(ready(Ok(creation)))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn storage_at_multi(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+        slots: Vec<Hex<U256>>,
+        block: Option<BlockId>,
+    ) -> BoxFuture<Result<Vec<Hex<H256>>, Error>> {
+        Box::pin(async move {
+            if slots.len() > MAX_STORAGE_SLOTS {
+                return Err(Error::InvalidParams {
+                    details: format!("Too many storage slots, maximum is {}", MAX_STORAGE_SLOTS),
+                });
+            }
+            let indexes: Vec<H256> = slots
+                .iter()
+                .map(|slot| {
+                    let mut bytes = [0u8; 32];
+                    slot.0.to_big_endian(&mut bytes);
+                    H256::from_slice(&bytes)
+                })
+                .collect();
+            let state = block_to_state_root(block, &meta).await;
+            let storages = state.get_storages_at(&meta, address.0, &indexes)?;
+            Ok(storages.into_iter().map(Hex).collect())
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn receipt_with_trace(
+        &self,
+        meta: Self::Metadata,
+        tx_hash: Hex<H256>,
+        traces: Vec<String>,
+    ) -> BoxFuture<Result<Option<RPCReceiptWithTrace>, Error>> {
+        let trace = self.trace.clone();
+        Box::pin(async move {
+            let receipt = ChainErpcImpl
+                .transaction_receipt(meta.clone(), tx_hash)
+                .await?;
+            let receipt = match receipt {
+                Some(receipt) => receipt,
+                None => return Ok(None),
+            };
+            let trace = trace
+                .trace_replay_transaction(meta, tx_hash, traces, None)
+                .await?;
+            Ok(trace.map(|trace| RPCReceiptWithTrace { receipt, trace }))
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn blocks_by_hashes(
+        &self,
+        meta: Self::Metadata,
+        block_hashes: Vec<Hex<H256>>,
+        full: bool,
+    ) -> BoxFuture<Result<Vec<Option<RPCBlock>>, Error>> {
+        Box::pin(async move {
+            if block_hashes.len() > MAX_BLOCK_HASHES {
+                return Err(Error::InvalidParams {
+                    details: format!("Too many block hashes, maximum is {}", MAX_BLOCK_HASHES),
+                });
+            }
+            let max_full_transactions = meta.evm_max_full_block_transactions();
+            let chain_id = meta.bank(None).evm_chain_id;
+
+            let mut blocks = Vec::with_capacity(block_hashes.len());
+            for block_hash in block_hashes {
+                let block = match meta.get_evm_block_by_hash(block_hash.0).await {
+                    Some((block, confirmed)) => Some(rpc_block(
+                        block,
+                        confirmed,
+                        full,
+                        chain_id,
+                        Some(max_full_transactions),
+                    )?),
+                    None => None,
+                };
+                blocks.push(block);
+            }
+            Ok(blocks)
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn estimate_gas_range(
+        &self,
+        meta: Self::Metadata,
+        tx: RPCTransaction,
+        block: Option<BlockId>,
+    ) -> BoxFuture<Result<RPCGasRange, Error>> {
+        Box::pin(async move {
+            let saved_state = block_to_state_root(block, &meta).await;
+            let buffer_percent = meta.evm_estimate_gas_buffer_percent();
+            estimate_gas_range(meta, tx, saved_state, vec![], buffer_percent)
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn transaction_position(
+        &self,
+        meta: Self::Metadata,
+        tx_hash: Hex<H256>,
+    ) -> BoxFuture<Result<Option<RPCTransactionPosition>, Error>> {
+        Box::pin(async move {
+            let receipt = match meta.get_evm_receipt_by_hash(tx_hash.0).await {
+                Some(receipt) => receipt,
+                None => return Ok(None),
+            };
+            let (block, _) = meta.get_evm_block_by_id(receipt.block_number).await.ok_or(
+                Error::BlockNotFound {
+                    block: receipt.block_number.into(),
+                },
+            )?;
+            Ok(Some(RPCTransactionPosition {
+                block_hash: Hex(block.header.hash()),
+                block_number: Hex(receipt.block_number.into()),
+                transaction_index: Hex(receipt.index as usize),
+            }))
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn effective_gas_price(
+        &self,
+        meta: Self::Metadata,
+        tx_hash: Hex<H256>,
+    ) -> BoxFuture<Result<Option<Hex<Gas>>, Error>> {
+        Box::pin(async move {
+            Ok(meta
+                .get_evm_receipt_by_hash(tx_hash.0)
+                .await
+                .map(|receipt| Hex(receipt.transaction.gas_price())))
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn block_hashes_by_range(
+        &self,
+        meta: Self::Metadata,
+        from: BlockId,
+        to: BlockId,
+    ) -> BoxFuture<Result<Vec<Hex<H256>>, Error>> {
+        Box::pin(async move {
+            let from = block_parse_confirmed_num(Some(from), &meta)
+                .await
+                .ok_or(Error::BlockNotFound { block: from })?;
+            let to = block_parse_confirmed_num(Some(to), &meta)
+                .await
+                .ok_or(Error::BlockNotFound { block: to })?;
+            let max_blocks = meta.evm_max_logs_blocks();
+            if to < from || to > from.saturating_add(max_blocks) {
+                return Err(Error::InvalidBlocksRange {
+                    starting: from,
+                    ending: to,
+                    batch_size: Some(max_blocks),
+                });
+            }
+
+            let mut hashes = Vec::with_capacity((to - from + 1) as usize);
+            for num in from..=to {
+                let block_id = BlockId::Num(Hex(num));
+                let (header, _) = meta
+                    .get_evm_block_header_by_id(num)
+                    .await
+                    .ok_or(Error::BlockNotFound { block: block_id })?;
+                hashes.push(Hex(header.hash()));
+            }
+            Ok(hashes)
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn replay_block_transactions_with_results(
+        &self,
+        meta: Self::Metadata,
+        block_num: BlockId,
+    ) -> BoxFuture<Result<Vec<RPCTransactionReplay>, Error>> {
+        Box::pin(async move {
+            let max_transactions = meta.evm_max_full_block_transactions();
+            let block = block_by_number(meta.clone(), block_num, true, Some(max_transactions))
+                .await?
+                .ok_or(Error::BlockNotFound { block: block_num })?;
+            let (block_hash, block_number) = (block.hash, block.number);
+            let txs = match block.transactions {
+                Either::Right(txs) => txs,
+                _ => return Err(Error::Unimplemented {}),
+            };
+
+            // execute on previous block, all transactions share executor
+            let base_block = block_number.as_u64().saturating_sub(1).into();
+            let saved_state = block_to_state_root(Some(base_block), &meta).await;
+            let calls: Vec<_> = txs.iter().map(|tx| (tx.clone(), vec![])).collect();
+            let outputs = call_many(meta, &calls, saved_state, None)?;
+
+            Ok(txs
+                .into_iter()
+                .zip(outputs)
+                .map(|(tx, output)| {
+                    let hash = tx.hash.unwrap_or_default();
+                    let transaction_index = tx.transaction_index.unwrap_or_default();
+                    let output_data = Bytes(output.exit_data);
+                    let revert_reason = match output.exit_reason {
+                        evm_state::ExitReason::Revert(_) => {
+                            evm_rpc::error::revert_reason(&output_data)
+                        }
+                        _ => None,
+                    };
+                    let logs = output
+                        .logs
+                        .into_iter()
+                        .enumerate()
+                        .map(|(id, log)| RPCLog {
+                            removed: false,
+                            log_index: Hex(id),
+                            transaction_index,
+                            transaction_hash: hash,
+                            block_hash,
+                            block_number,
+                            address: Hex(log.address),
+                            data: log.data.into(),
+                            topics: log.topics.into_iter().map(Hex).collect(),
+                        })
+                        .collect();
+                    RPCTransactionReplay {
+                        hash,
+                        success: matches!(output.exit_reason, evm_state::ExitReason::Succeed(_)),
+                        gas_used: Hex(output.used_gas.into()),
+                        output: output_data,
+                        revert_reason,
+                        logs,
+                    }
+                })
+                .collect())
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn logs_delta(
+        &self,
+        meta: Self::Metadata,
+        log_filter: RPCLogFilter,
+        since_block: Hex<u64>,
+    ) -> BoxFuture<Result<RPCLogsDelta, Error>> {
+        Box::pin(async move {
+            let latest = meta
+                .get_last_available_evm_block()
+                .ok_or(Error::ArchiveNotSupported)?;
+            if latest <= since_block.0 {
+                return Ok(RPCLogsDelta {
+                    logs: vec![],
+                    latest_block: since_block,
+                });
+            }
+            let from = since_block.0 + 1;
+            let max_blocks = meta.evm_max_logs_blocks();
+            if latest > from + max_blocks {
+                return Err(Error::InvalidBlocksRange {
+                    starting: from,
+                    ending: latest,
+                    batch_size: Some(max_blocks),
+                });
+            }
+
+            let logs = filter_logs(&meta, log_filter, from, latest).await?;
+            Ok(RPCLogsDelta {
+                logs: logs.into_iter().map(|l| l.into()).collect(),
+                latest_block: Hex(latest),
+            })
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn code_size(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+        block: Option<BlockId>,
+    ) -> BoxFuture<Result<Hex<usize>, Error>> {
+        Box::pin(async move {
+            let state = block_to_state_root(block, &meta).await;
+
+            let account = state
+                .get_account_state_at(&meta, address.0)?
+                .unwrap_or_default();
+            Ok(Hex(account.code.size()))
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn non_empty_blocks(
+        &self,
+        meta: Self::Metadata,
+        from: BlockId,
+        to: BlockId,
+    ) -> BoxFuture<Result<RPCNonEmptyBlocks, Error>> {
+        Box::pin(async move {
+            let from = block_parse_confirmed_num(Some(from), &meta)
+                .await
+                .ok_or(Error::BlockNotFound { block: from })?;
+            let to = block_parse_confirmed_num(Some(to), &meta)
+                .await
+                .ok_or(Error::BlockNotFound { block: to })?;
+            let max_blocks = meta.evm_max_logs_blocks();
+            if to < from || to > from.saturating_add(max_blocks) {
+                return Err(Error::InvalidBlocksRange {
+                    starting: from,
+                    ending: to,
+                    batch_size: Some(max_blocks),
+                });
+            }
+
+            let mut blocks = vec![];
+            let mut skipped = 0;
+            for num in from..=to {
+                let block_id = BlockId::Num(Hex(num));
+                // transaction hashes are in header, receipts are not needed
+                let (header, confirmed) = meta
+                    .get_evm_block_header_by_id(num)
+                    .await
+                    .ok_or(Error::BlockNotFound { block: block_id })?;
+                if header.transactions.is_empty() {
+                    skipped += 1;
+                    continue;
+                }
+                let transactions = header.transactions.iter().copied().map(Hex).collect();
+                blocks.push(RPCBlock::new_from_head(
+                    header,
+                    confirmed,
+                    Either::Left(transactions),
+                ));
+            }
+            Ok(RPCNonEmptyBlocks {
+                blocks,
+                skipped: Hex(skipped),
+            })
+        })
+    }
+}
+
+struct TxOutput {
+    exit_reason: evm_state::ExitReason,
+    exit_data: Vec<u8>,
+    used_gas: u64,
+    traces: Vec<evm_state::executor::Trace>,
+    logs: Vec<evm_state::Log>,
+}
+
+#[instrument(skip(meta))]
+fn call(
+    meta: JsonRpcRequestProcessor,
+    tx: RPCTransaction,
+    saved_state: StateRootWithBank,
+    meta_keys: Vec<solana_sdk::pubkey::Pubkey>,
+    state_overrides: Option<&StateOverrides>,
+) -> Result<TxOutput, Error> {
+    let outputs = call_many(meta, &[(tx, meta_keys)], saved_state, state_overrides)?;
+
+    let TxOutput {
+        exit_reason,
+        exit_data,
+        used_gas,
+        traces,
+        logs,
+    } = outputs
+        .into_iter()
+        .next()
+        .expect("Should contain result for tx.");
+
+    let (_, exit_data) = evm_rpc::handle_evm_exit_reason(exit_reason.clone(), exit_data)?;
+
+    Ok(TxOutput {
+        exit_reason,
+        exit_data,
+        used_gas,
+        traces,
+        logs,
+    })
+}
+
+/// Finds minimal gas limit, with which transaction succeeds, and adds `buffer_percent` to it.
+#[instrument(skip(meta))]
+fn estimate_gas(
+    meta: JsonRpcRequestProcessor,
+    tx: RPCTransaction,
+    saved_state: StateRootWithBank,
+    meta_keys: Vec<solana_sdk::pubkey::Pubkey>,
+    buffer_percent: u64,
+) -> Result<Gas, Error> {
+    let range = estimate_gas_range(meta, tx, saved_state, meta_keys, buffer_percent)?;
+    Ok(range.max.0)
+}
+
+/// Finds minimal gas limit, with which transaction succeeds, and the same limit with `buffer_percent`.
+/// Execution can require more gas than it uses (e.g. 63/64 of gas passed to inner calls),
+/// so limit is doubled from used gas until transaction succeeds, and then binary searched.
+fn estimate_gas_range(
+    meta: JsonRpcRequestProcessor,
+    mut tx: RPCTransaction,
+    saved_state: StateRootWithBank,
+    meta_keys: Vec<solana_sdk::pubkey::Pubkey>,
+    buffer_percent: u64,
+) -> Result<RPCGasRange, Error> {
+    let max_gas = tx_gas_limit(&tx)?.unwrap_or(evm_state::DEFAULT_GAS_LIMIT);
+    let mut execute = |gas_limit: u64| {
+        tx.gas = Some(Hex(gas_limit.into()));
+        call(
+            meta.clone(),
+            tx.clone(),
+            saved_state.clone(),
+            meta_keys.clone(),
+            None,
+        )
+    };
+    // failure with maximal limit is not caused by lack of gas
+    let used_gas = execute(max_gas)?.used_gas;
+
+    // most transactions need only gas they use
+    let (mut low, mut high) = (used_gas.saturating_sub(1), used_gas);
+    while high < max_gas && execute(high).is_err() {
+        low = high;
+        high = high.saturating_mul(2).min(max_gas);
+    }
+    let mut iterations = 0;
+    while low + 1 < high && iterations < MAX_ESTIMATE_GAS_ITERATIONS {
+        let middle = low + (high - low) / 2;
+        if execute(middle).is_ok() {
+            high = middle;
+        } else {
+            low = middle;
+        }
+        iterations += 1;
+    }
+    // buffer is computed in U256, to not overflow near u64::MAX
+    let gas = Gas::from(high);
+    Ok(RPCGasRange {
+        min: Hex(gas),
+        max: Hex((gas + gas * buffer_percent / 100).min(max_gas.into())),
+    })
+}
+
+/// Returns gas limit of simulated transaction.
+/// Executor accepts only limits that fit into u64, so bigger values are rejected instead of truncated.
+fn tx_gas_limit(tx: &RPCTransaction) -> Result<Option<u64>, Error> {
+    match tx.gas {
+        Some(Hex(gas)) if gas > Gas::from(u64::MAX) => Err(Error::InvalidParams {
+            details: format!("Gas limit {} exceeds maximum of {}", gas, u64::MAX),
+        }),
+        Some(Hex(gas)) => Ok(Some(gas.as_u64())),
+        None => Ok(None),
+    }
+}
+
+/// Pending nonce of sender can include transactions, that are not in state yet (e.g. pooled by bridge).
+/// To simulate transaction after them, nonce of sender is advanced to nonce of its first transaction.
+fn advance_to_pending_nonces(
+    evm_state: &mut evm_state::EvmBackend<evm_state::Incomming>,
+    txs: &[(RPCTransaction, Vec<solana_sdk::pubkey::Pubkey>)],
+) {
+    let mut senders = std::collections::HashSet::new();
+    for (tx, _) in txs {
+        let (caller, nonce) = match (tx.from, tx.nonce) {
+            (Some(caller), Some(nonce)) => (caller.0, nonce.0),
+            _ => continue,
+        };
+        if !senders.insert(caller) {
+            continue;
+        }
+        let mut account = evm_state.get_account_state(caller).unwrap_or_default();
+        if account.nonce < nonce {
+            account.nonce = nonce;
+            evm_state.set_account_state(caller, account);
+        }
+    }
+}
+
+/// Replaces fields and storage slots of accounts in simulated state.
+fn apply_state_overrides(
+    evm_state: &mut evm_state::EvmBackend<evm_state::Incomming>,
+    state_overrides: &StateOverrides,
+) {
+    for (address, account_override) in state_overrides {
+        let address = address.0;
+        let mut account = evm_state.get_account_state(address).unwrap_or_default();
+        if let Some(balance) = account_override.balance {
+            account.balance = balance.0;
+        }
+        if let Some(nonce) = account_override.nonce {
+            account.nonce = nonce.0;
+        }
+        if let Some(code) = &account_override.code {
+            account.code = code.0.clone().into();
+        }
+        evm_state.set_account_state(address, account);
+        if let Some(state_diff) = &account_override.state_diff {
+            evm_state.ext_storage(
+                address,
+                state_diff.iter().map(|(index, value)| (index.0, value.0)),
+            );
+        }
+    }
+}
+
+#[instrument(skip(meta))]
+fn call_many(
+    meta: JsonRpcRequestProcessor,
+    txs: &[(RPCTransaction, Vec<solana_sdk::pubkey::Pubkey>)],
+    saved_state: StateRootWithBank,
+    state_overrides: Option<&StateOverrides>,
+) -> Result<Vec<TxOutput>, Error> {
+    // if we already found bank with some root, or we just cannot find state_root - use latest.
+    let use_latest_state = saved_state.bank.is_some() || saved_state.state_root.is_none();
+    let bank = saved_state
+        .bank
+        .unwrap_or_else(|| meta.bank(Some(CommitmentConfig::processed())));
+
+    let mut evm_state = if use_latest_state {
+        // keep current bank to allow simulating on latest state without archive
+        match bank.evm_state.read().unwrap().clone() {
+            evm_state::EvmState::Incomming(mut i) => {
+                // tagged block is committed, so changes of current block are not part of its state
+                if matches!(
+                    saved_state.block,
+                    BlockId::RelativeId(BlockRelId::Finalized | BlockRelId::Safe)
+                ) {
+                    i.discard_changes();
+                }
+                i
+            }
+            evm_state::EvmState::Committed(c) => {
+                c.next_incomming(bank.clock().unix_timestamp as u64)
+            }
+        }
+    } else {
+        let root = saved_state.state_root.unwrap();
+        meta.evm_state_archive(saved_state.block_timestamp)
+            .ok_or(Error::ArchiveNotSupported)?
+            .new_incomming_for_root(root)
+            .ok_or(Error::StateNotFoundForBlock {
+                block: saved_state.block,
+            })?
+    };
+
+    if matches!(saved_state.block, BlockId::RelativeId(BlockRelId::Pending)) {
+        advance_to_pending_nonces(&mut evm_state, txs);
+    }
+    if let Some(state_overrides) = state_overrides {
+        apply_state_overrides(&mut evm_state, state_overrides);
+    }
+
+    let estimate_config = evm_state::EvmConfig {
+        estimate: true,
+        chain_id: bank.evm_chain_id,
+        ..Default::default()
+    };
+
+    //TODO: Hashes actual to saved root
+    let last_hashes = bank.evm_hashes();
+    let mut executor = evm_state::Executor::with_config(
+        evm_state,
+        evm_state::ChainContext::new(last_hashes),
+        estimate_config,
+        evm_state::executor::FeatureSet::new(
+            bank.feature_set.is_active(
+                &solana_sdk::feature_set::velas::unsigned_tx_fix::id(),
+            ),
+            bank.feature_set.is_active(
+                &solana_sdk::feature_set::velas::clear_logs_on_error::id(),
+            ),
+        ),
+    );
+
+    debug!("running evm executor = {:?}", executor);
+    let mut result = Vec::new();
+    for (tx, meta_keys) in txs {
+        result.push(call_inner(
+            &mut executor,
+            tx.clone(),
+            meta_keys.clone(),
+            &*bank,
+        )?)
+    }
+    Ok(result)
+}
+
+#[instrument(skip(executor, bank))]
+fn call_inner(
+    executor: &mut evm_state::Executor,
+    tx: RPCTransaction,
+    meta_keys: Vec<solana_sdk::pubkey::Pubkey>,
+    bank: &Bank,
+) -> Result<TxOutput, Error> {
+    use solana_evm_loader_program::precompiles::*;
+    let caller = tx.from.map(|a| a.0).unwrap_or_default();
+
+    let value = tx.value.map(|a| a.0).unwrap_or_else(|| 0.into());
+    let input = tx.input.map(|a| a.0).unwrap_or_else(Vec::new);
+    let gas_limit = Gas::from(tx_gas_limit(&tx)?.unwrap_or(u64::MAX));
+    // On estimate set gas price to zero, to avoid out of funds errors.
+    let gas_price = u64::MIN.into();
+
+    let nonce = tx
+        .nonce
         .map(|a| a.0)
         .unwrap_or_else(|| executor.nonce(caller));
     let tx_chain_id = executor.chain_id();
     let tx_hash = tx.hash.map(|a| a.0).unwrap_or_else(H256::random);
 
-    let evm_state_balance = bank
-        .get_account(&solana_sdk::evm_state::id())
+    let evm_state_balance = bank
+        .get_account(&solana_sdk::evm_state::id())
+        .unwrap_or_default()
+        .lamports;
+
+    let (user_accounts, action) = if let Some(address) = tx.to {
+        let address = address.0;
+        debug!(
+            "Trying to execute tx = {:?}",
+            (caller, address, value, &input, gas_limit)
+        );
+
+        let mut meta_keys: Vec<_> = meta_keys
+            .into_iter()
+            .map(|pk| {
+                let user_account = RefCell::new(bank.get_account(&pk).unwrap_or_default());
+                (user_account, pk)
+            })
+            .collect();
+
+        // Shortcut for swap tokens to native, will add solana account to transaction.
+        if address == *ETH_TO_VLX_ADDR {
+            debug!("Found transferToNative transaction");
+            match ETH_TO_VLX_CODE.parse_abi(&input) {
+                Ok(pk) => {
+                    info!("Adding account to meta = {}", pk);
+
+                    let user_account = RefCell::new(bank.get_account(&pk).unwrap_or_default());
+                    meta_keys.push((user_account, pk))
+                }
+                Err(e) => {
+                    return Err(Error::InvalidPrecompileCall {
+                        address,
+                        details: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        (meta_keys, TransactionAction::Call(address))
+    } else {
+        (vec![], TransactionAction::Create)
+    };
+
+    // system transfers always set s = 0x1
+    if Some(Hex(U256::from(0x1))) == tx.s {
+        // check if it native swap, then predeposit, amount, to pass transaction
+        if caller == *ETH_TO_VLX_ADDR {
+            let amount = value + gas_limit * gas_price;
+            executor.deposit(caller, amount)
+        }
+    }
+
+    let user_accounts: Vec<_> = user_accounts
+        .iter()
+        .map(|(user_account, pk)| KeyedAccount::new(pk, false, user_account))
+        .collect();
+
+    let evm_state::executor::ExecutionResult {
+        exit_reason,
+        exit_data,
+        used_gas,
+        traces,
+        tx_logs,
+        ..
+    } = executor
+        .transaction_execute_raw(
+            caller,
+            nonce,
+            gas_price,
+            gas_limit,
+            action,
+            input,
+            value,
+            Some(tx_chain_id),
+            tx_hash,
+            true,
+            solana_evm_loader_program::precompiles::simulation_entrypoint(
+                executor.support_precompile(),
+                evm_state_balance,
+                &user_accounts,
+            ),
+        )
+        .with_context(|| EvmStateError)?;
+
+    Ok(TxOutput {
+        exit_reason,
+        exit_data,
+        used_gas,
+        traces,
+        logs: tx_logs,
+    })
+}
+
+/// Returns block, with full transactions if `full` is set.
+/// Fails if block has more than `max_full_transactions` transactions, to avoid building huge response.
+#[instrument(skip(meta))]
+async fn block_by_number(
+    meta: JsonRpcRequestProcessor,
+    block: BlockId,
+    full: bool,
+    max_full_transactions: Option<usize>,
+) -> Result<Option<RPCBlock>, Error> {
+    let num = block_parse_confirmed_num(Some(block), &meta).await;
+    let evm_block = match num {
+        Some(block_num) => meta.get_evm_block_by_id(block_num).await,
+        None => None,
+    };
+    // TODO: Inline evm_state lookups, and request only solana headers.
+    let (block, confirmed) = match evm_block {
+        None => {
+            error!("Error requesting block:{:?} ({:?}) not found", block, num);
+            return Ok(None);
+        }
+        Some(b) => b,
+    };
+
+    let bank = meta.bank(None);
+    rpc_block(block, confirmed, full, bank.evm_chain_id, max_full_transactions).map(Some)
+}
+
+/// Converts block to its RPC representation, with full transactions if `full` is set.
+fn rpc_block(
+    block: evm_state::Block,
+    confirmed: bool,
+    full: bool,
+    chain_id: u64,
+    max_full_transactions: Option<usize>,
+) -> Result<RPCBlock, Error> {
+    match max_full_transactions {
+        Some(max) if full && block.transactions.len() > max => {
+            return Err(Error::InvalidParams {
+                details: format!(
+                    "Block has {} transactions, at most {} can be returned with full=true, \
+                     request block with full=false, and fetch transactions by hash or index",
+                    block.transactions.len(),
+                    max
+                ),
+            })
+        }
+        _ => (),
+    }
+
+    let block_hash = block.header.hash();
+    let transactions = if full {
+        let txs = block
+            .transactions
+            .into_iter()
+            .filter_map(|(hash, receipt)| {
+                RPCTransaction::new_from_receipt(receipt, hash, block_hash, chain_id).ok()
+            })
+            .collect();
+        Either::Right(txs)
+    } else {
+        let txs = block
+            .transactions
+            .into_iter()
+            .map(|(k, _v)| Hex(k))
+            .collect();
+        Either::Left(txs)
+    };
+
+    Ok(RPCBlock::new_from_head(
+        block.header,
+        confirmed,
+        transactions,
+    ))
+}
+
+async fn block_timestamp(meta: &JsonRpcRequestProcessor, num: u64) -> Result<u64, Error> {
+    let cached = meta.evm_rpc_cache().block_timestamp(num);
+    if let Some(timestamp) = cached {
+        return Ok(timestamp);
+    }
+    let (block, confirmed) = meta
+        .get_evm_block_by_id(num)
+        .await
+        .ok_or(Error::BlockNotFound { block: num.into() })?;
+    // unconfirmed block can be replaced
+    if confirmed {
+        meta.evm_rpc_cache()
+            .set_block_timestamp(num, block.header.timestamp);
+    }
+    Ok(block.header.timestamp)
+}
+
+/// Gas price at configured percentile of transactions in latest blocks, but not less than default one.
+/// Result is cached until next block is recorded.
+fn oracle_gas_price(meta: &JsonRpcRequestProcessor) -> Gas {
+    let config = meta.gas_price_config();
+    let latest = match meta.get_last_available_evm_block() {
+        Some(latest) if config.oracle_blocks > 0 => latest,
+        _ => return config.default_gas_price,
+    };
+    if let Some(gas_price) = meta.evm_rpc_cache().gas_price() {
+        return gas_price;
+    }
+
+    let oldest = (latest + 1).saturating_sub(config.oracle_blocks);
+    let mut gas_prices: Vec<Gas> = (oldest..=latest)
+        .filter_map(|block_num| meta.blockstore.get_evm_block(block_num).ok())
+        .flat_map(|(block, _)| {
+            block
+                .transactions
+                .into_iter()
+                .map(|(_, receipt)| receipt.transaction.gas_price())
+        })
+        .collect();
+    gas_prices.sort();
+    let gas_price = evm_rpc::percentile(&gas_prices, config.oracle_percentile)
         .unwrap_or_default()
-        .lamports;
+        .max(config.default_gas_price);
+    meta.evm_rpc_cache().set_gas_price(latest, gas_price);
+    gas_price
+}
 
-    let (user_accounts, action) = if let Some(address) = tx.to {
-        let address = address.0;
-        debug!(
-            "Trying to execute tx = {:?}",
-            (caller, address, value, &input, gas_limit)
+/// Binary search of block by timestamp, between earliest and latest available blocks.
+/// Returns last block at or before `timestamp`, or first block at or after it, if `round_up` is set.
+async fn block_number_by_timestamp(
+    meta: &JsonRpcRequestProcessor,
+    timestamp: u64,
+    round_up: bool,
+) -> Result<Option<u64>, Error> {
+    let latest = meta
+        .get_last_available_evm_block()
+        .ok_or(Error::ArchiveNotSupported)?;
+    let cached = meta.evm_rpc_cache().timestamp_lookup(timestamp, round_up);
+    if let Some(block) = cached {
+        return Ok(block);
+    }
+    let block = search_block_by_timestamp(meta, latest, timestamp, round_up).await?;
+    meta.evm_rpc_cache()
+        .set_timestamp_lookup(latest, timestamp, round_up, block);
+    Ok(block)
+}
+
+async fn search_block_by_timestamp(
+    meta: &JsonRpcRequestProcessor,
+    latest: u64,
+    timestamp: u64,
+    round_up: bool,
+) -> Result<Option<u64>, Error> {
+    let earliest = meta.get_first_available_evm_block().await;
+
+    if timestamp < block_timestamp(meta, earliest).await? {
+        return Ok(if round_up { Some(earliest) } else { None });
+    }
+    if timestamp > block_timestamp(meta, latest).await? {
+        return Ok(if round_up { None } else { Some(latest) });
+    }
+
+    // invariant: timestamp(lo) <= timestamp <= timestamp(hi)
+    let (mut lo, mut hi) = (earliest, latest);
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if block_timestamp(meta, mid).await? <= timestamp {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    if block_timestamp(meta, hi).await? == timestamp {
+        return Ok(Some(hi));
+    }
+    if block_timestamp(meta, lo).await? == timestamp || !round_up {
+        Ok(Some(lo))
+    } else {
+        Ok(Some(hi))
+    }
+}
+
+#[instrument(skip(meta))]
+async fn filter_logs(
+    meta: &JsonRpcRequestProcessor,
+    log_filter: RPCLogFilter,
+    from: u64,
+    to: u64,
+) -> Result<Vec<evm_state::LogWithLocation>, Error> {
+    let filter = log_filter.into_log_filter(from, to);
+    debug!("filter = {:?}", filter);
+
+    meta.filter_logs(filter).await.map_err(|e| {
+        debug!("filter_logs error = {:?}", e);
+        into_native_error(e, false)
+    })
+}
+
+#[instrument(skip(meta))]
+async fn transaction_by_hash(
+    meta: JsonRpcRequestProcessor,
+    tx_hash: Hex<H256>,
+) -> Result<Option<RPCTransaction>, Error> {
+    let bank = meta.bank(None);
+    let chain_id = bank.evm_chain_id;
+    Ok(match meta.get_evm_receipt_by_hash(tx_hash.0).await {
+        Some(receipt) => {
+            let (block, _) = meta.get_evm_block_by_id(receipt.block_number)
+                .await
+                .ok_or({
+                    Error::BlockNotFound { block: receipt.block_number.into() }
+                })?;
+            let block_hash = block.header.hash();
+            Some(RPCTransaction::new_from_receipt(
+                receipt, tx_hash.0, block_hash, chain_id,
+            )?)
+        }
+        None => None,
+    })
+}
+
+#[instrument(skip(meta))]
+async fn trace_call_many(
+    meta: JsonRpcRequestProcessor,
+    tx_traces: Vec<(RPCTransaction, Vec<String>, Option<TraceMeta>)>,
+    block: Option<BlockId>,
+) -> Result<Vec<evm_rpc::trace::TraceResultsWithTransactionHash>, Error> {
+    let saved_state = block_to_state_root(block, &meta).await;
+
+    let mut txs = Vec::new();
+    let mut txs_meta = Vec::new();
+
+    // TODO: Handle Vec<String> - traces array, check that it contain "trace" string.
+    for (t, _, meta) in tx_traces {
+        let meta = meta.unwrap_or_default();
+        let meta_keys = meta
+            .meta_keys
+            .iter()
+            .flatten()
+            .map(|s| solana_sdk::pubkey::Pubkey::from_str(s))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| into_native_error(e, false))?;
+
+        txs.push((t, meta_keys));
+        txs_meta.push(meta);
+    }
+
+    let traces = call_many(meta, &txs, saved_state, None)?.into_iter();
+
+    let mut result = Vec::new();
+    for (output, meta_tx) in traces.zip(txs_meta) {
+        result.push(evm_rpc::trace::TraceResultsWithTransactionHash {
+            trace: output.traces.into_iter().map(From::from).collect(),
+            output: output.exit_data.into(),
+            transaction_hash: meta_tx.transaction_hash.map(Hex),
+            transaction_index: meta_tx.transaction_index.map(Hex),
+            block_hash: meta_tx.block_hash.map(Hex),
+            block_number: meta_tx.block_number.map(Hex),
+        })
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_ledger::genesis_utils::create_genesis_config;
+
+    fn test_meta() -> JsonRpcRequestProcessor {
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        JsonRpcRequestProcessor::new_from_bank(&bank)
+    }
+
+    fn write_rooted_block(
+        meta: &JsonRpcRequestProcessor,
+        block_number: u64,
+        slot: u64,
+        timestamp: u64,
+    ) {
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            H256::zero(),
+            block_number,
+            0,
+            timestamp,
+            slot,
+            H256::zero(),
+            std::iter::empty(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore.write_evm_block_header(&header).unwrap();
+        meta.blockstore.set_roots(&[slot]).unwrap();
+    }
+
+    #[test]
+    fn test_sha3_input_size() {
+        let meta = test_meta();
+
+        let hash = GeneralErpcImpl
+            .sha3(meta.clone(), Bytes(vec![0; MAX_INPUT_SIZE]))
+            .unwrap();
+        assert_eq!(
+            hash.0,
+            H256::from_slice(Keccak256::digest(&vec![0; MAX_INPUT_SIZE]).as_slice())
+        );
+        assert!(matches!(
+            GeneralErpcImpl.sha3(meta, Bytes(vec![0; MAX_INPUT_SIZE + 1])),
+            Err(Error::InputTooLarge { size, max_size })
+                if size == MAX_INPUT_SIZE + 1 && max_size == MAX_INPUT_SIZE
+        ));
+    }
+
+    #[test]
+    fn test_gas_price_from_config() {
+        let meta = test_meta();
+        assert_eq!(
+            GeneralErpcImpl.gas_price(meta).unwrap().0,
+            evm_rpc::GasPriceConfig::default().default_gas_price
+        );
+    }
+
+    /// Writes rooted block with transactions of given gas prices.
+    fn write_block_with_gas_prices(
+        meta: &JsonRpcRequestProcessor,
+        block_number: u64,
+        slot: u64,
+        gas_prices: &[u64],
+    ) {
+        let secret_key = evm_state::SecretKey::from_slice(&[4u8; 32]).unwrap();
+        let transactions: Vec<_> = gas_prices
+            .iter()
+            .zip(0..)
+            .map(|(gas_price, nonce)| {
+                let tx = evm_state::UnsignedTransaction {
+                    nonce: nonce.into(),
+                    gas_price: (*gas_price).into(),
+                    gas_limit: 21_000.into(),
+                    action: TransactionAction::Call(H160::repeat_byte(0x11)),
+                    value: 0.into(),
+                    input: vec![],
+                }
+                .sign(&secret_key, Some(meta.bank(None).evm_chain_id));
+                let hash = tx.tx_id_hash();
+                let receipt = evm_state::TransactionReceipt::new(
+                    tx.into(),
+                    21_000,
+                    block_number,
+                    nonce,
+                    vec![],
+                    (evm_state::ExitSucceed::Stopped.into(), vec![]),
+                );
+                (hash, receipt)
+            })
+            .collect();
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            H256::zero(),
+            block_number,
+            0,
+            0,
+            slot,
+            H256::zero(),
+            transactions.iter(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore.write_evm_block_header(&header).unwrap();
+        for (hash, receipt) in transactions {
+            meta.blockstore
+                .write_evm_transaction(block_number, slot, hash, receipt)
+                .unwrap();
+        }
+        meta.blockstore.set_roots(&[slot]).unwrap();
+    }
+
+    #[test]
+    fn test_gas_price_oracle() {
+        let meta = test_meta();
+        let gwei = 1_000_000_000u64;
+        let gas_price = || GeneralErpcImpl.gas_price(meta.clone()).unwrap().0;
+
+        // transactions cheaper than default gas price don't lower it
+        write_block_with_transactions(&meta, 1, 10);
+        assert_eq!(
+            gas_price(),
+            evm_rpc::GasPriceConfig::default().default_gas_price
+        );
+
+        // 60th percentile of 1 wei, 1 wei, 4, 6, 8 and 10 gwei
+        write_block_with_gas_prices(&meta, 2, 11, &[4 * gwei, 6 * gwei]);
+        write_block_with_gas_prices(&meta, 3, 12, &[8 * gwei, 10 * gwei]);
+        assert_eq!(gas_price(), U256::from(6 * gwei));
+
+        // computed price is reused until next block is recorded
+        meta.evm_rpc_cache().on_evm_block_advanced(3);
+        assert_eq!(gas_price(), U256::from(6 * gwei));
+        write_block_with_gas_prices(&meta, 4, 13, &[20 * gwei; 4]);
+        assert_eq!(gas_price(), U256::from(6 * gwei));
+
+        meta.evm_rpc_cache().on_evm_block_advanced(4);
+        assert_eq!(gas_price(), U256::from(10 * gwei));
+    }
+
+    #[test]
+    fn test_max_priority_fee_per_gas() {
+        let meta = test_meta();
+        let gas_price = GeneralErpcImpl.gas_price(meta.clone()).unwrap();
+        let tip = GeneralErpcImpl.max_priority_fee_per_gas(meta).unwrap();
+        assert!(tip.0 <= gas_price.0);
+    }
+
+    #[test]
+    fn test_replay_transaction_cached() {
+        let meta = test_meta();
+        let trace_impl = TraceErpcImpl::default();
+        let tx_hash = H256::repeat_byte(0x11);
+        let traces = vec!["trace".to_string()];
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        // transaction is unknown to ledger, so replay has nothing to execute
+        let replayed = rt
+            .block_on(trace_impl.trace_replay_transaction(
+                meta.clone(),
+                Hex(tx_hash),
+                traces.clone(),
+                None,
+            ))
+            .unwrap();
+        assert!(replayed.is_none());
+        assert_eq!(trace_impl.replay_cache.lock().unwrap().len(), 0);
+
+        let cached = TraceResultsWithTransactionHash {
+            output: Bytes(vec![1, 2, 3]),
+            trace: vec![],
+            block_hash: None,
+            block_number: None,
+            transaction_hash: Some(Hex(tx_hash)),
+            transaction_index: None,
+        };
+        trace_impl
+            .replay_cache
+            .lock()
+            .unwrap()
+            .put((tx_hash, traces.clone(), None), cached);
+
+        let replayed = rt
+            .block_on(trace_impl.trace_replay_transaction(meta, Hex(tx_hash), traces, None))
+            .unwrap()
+            .unwrap();
+        assert_eq!(replayed.output.0, vec![1, 2, 3]);
+        assert_eq!(replayed.transaction_hash, Some(Hex(tx_hash)));
+    }
+
+    #[test]
+    fn test_replay_transaction_cached_only_when_rooted() {
+        let meta = test_meta();
+        let trace_impl = TraceErpcImpl::default();
+        let (_, transactions) = write_unrooted_block_with_logs(&meta, 4, 12, vec![]);
+        let tx_hash = transactions[1].0;
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let replay = || {
+            rt.block_on(trace_impl.trace_replay_transaction(
+                meta.clone(),
+                Hex(tx_hash),
+                vec!["trace".to_string()],
+                None,
+            ))
+            .unwrap()
+        };
+        assert!(replay().is_some());
+        assert_eq!(trace_impl.replay_cache.lock().unwrap().len(), 0);
+
+        meta.blockstore.set_roots(&[12]).unwrap();
+        assert!(replay().is_some());
+        assert_eq!(trace_impl.replay_cache.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_block_by_timestamp() {
+        let meta = test_meta();
+        // blocks 3..=9 with timestamps 100, 110, .., 160
+        for block_number in 3..=9 {
+            let timestamp = 100 + (block_number - 3) * 10;
+            write_rooted_block(&meta, block_number, block_number + 10, timestamp);
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let find = |timestamp: u64, round_up: bool| {
+            rt.block_on(VelasErpcImpl::default().block_by_timestamp(
+                meta.clone(),
+                Hex(timestamp),
+                round_up,
+            ))
+            .unwrap()
+            .map(|block| block.number.0.as_u64())
+        };
+
+        assert_eq!(find(130, false), Some(6));
+        assert_eq!(find(130, true), Some(6));
+        assert_eq!(find(135, false), Some(6));
+        assert_eq!(find(135, true), Some(7));
+        // before earliest block
+        assert_eq!(find(50, false), None);
+        assert_eq!(find(50, true), Some(3));
+        // after latest block
+        assert_eq!(find(200, false), Some(9));
+        assert_eq!(find(200, true), None);
+        // bounds
+        assert_eq!(find(100, false), Some(3));
+        assert_eq!(find(100, true), Some(3));
+        assert_eq!(find(160, false), Some(9));
+        assert_eq!(find(160, true), Some(9));
+    }
+
+    #[test]
+    fn test_caches_advance_with_new_block() {
+        let meta = test_meta();
+        for block_number in 3..=5 {
+            write_rooted_block(&meta, block_number, block_number + 10, block_number * 10);
+        }
+        meta.evm_rpc_cache().on_evm_block_advanced(5);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let find = |timestamp: u64| {
+            rt.block_on(VelasErpcImpl::default().block_by_timestamp(
+                meta.clone(),
+                Hex(timestamp),
+                true,
+            ))
+            .unwrap()
+            .map(|block| block.number.0.as_u64())
+        };
+
+        assert_eq!(find(40), Some(4));
+        assert_eq!(find(60), None);
+        assert_eq!(meta.evm_rpc_cache().timestamp_lookup(60, true), Some(None));
+        assert_eq!(meta.evm_rpc_cache().block_timestamp(4), Some(40));
+
+        write_rooted_block(&meta, 6, 16, 60);
+        // reading latest block has no side effects, caches are invalidated by block recorder
+        assert_eq!(meta.get_last_available_evm_block(), Some(6));
+        assert_eq!(meta.evm_rpc_cache().latest_block(), Some(5));
+        meta.evm_rpc_cache().on_evm_block_advanced(6);
+        // latest-dependent lookup is dropped, historical timestamps are kept
+        assert_eq!(meta.evm_rpc_cache().latest_block(), Some(6));
+        assert_eq!(meta.evm_rpc_cache().timestamp_lookup(60, true), None);
+        assert_eq!(meta.evm_rpc_cache().block_timestamp(4), Some(40));
+        assert_eq!(find(60), Some(6));
+    }
+
+    #[test]
+    fn test_finalized_and_safe_tags() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        // block committed by rooted bank, and newer block, that is rooted only in blockstore
+        let finalized_header = {
+            let mut evm_state = bank.evm_state.write().unwrap();
+            let incomming = match evm_state.clone() {
+                evm_state::EvmState::Incomming(i) => i,
+                _ => panic!("Not expected state"),
+            };
+            let committed = incomming.commit_block(bank.slot(), H256::zero());
+            let header = committed.state.block.clone();
+            *evm_state = evm_state::EvmState::Committed(committed);
+            header
+        };
+        let latest_header = evm_state::BlockHeader::new(
+            finalized_header.hash(),
+            0,
+            H256::repeat_byte(1),
+            finalized_header.block_number + 1,
+            0,
+            10,
+            bank.slot() + 1,
+            H256::zero(),
+            std::iter::empty(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore
+            .write_evm_block_header(&finalized_header)
+            .unwrap();
+        meta.blockstore
+            .write_evm_block_header(&latest_header)
+            .unwrap();
+        meta.blockstore
+            .set_roots(&[bank.slot(), bank.slot() + 1])
+            .unwrap();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let finalized = BlockId::RelativeId(BlockRelId::Finalized);
+        let balance = rt
+            .block_on(ChainErpcImpl.balance(
+                meta.clone(),
+                Hex(H160::repeat_byte(1)),
+                Some(finalized),
+            ))
+            .unwrap();
+        assert_eq!(balance, Hex(U256::zero()));
+
+        let block_number = |block| {
+            rt.block_on(ChainErpcImpl.block_by_number(meta.clone(), block, false))
+                .unwrap()
+                .map(|block| block.number.0.as_u64())
+        };
+        assert_eq!(
+            meta.get_finalized_evm_block(),
+            Some(finalized_header.block_number)
+        );
+        assert_eq!(block_number(finalized), Some(finalized_header.block_number));
+        assert_eq!(
+            block_number(BlockId::RelativeId(BlockRelId::Latest)),
+            Some(latest_header.block_number)
+        );
+        // safe block depth is 0 by default
+        assert_eq!(
+            block_number(BlockId::RelativeId(BlockRelId::Safe)),
+            Some(latest_header.block_number)
+        );
+    }
+
+    #[test]
+    fn test_storage_at_multi_matches_storage_at() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let address = H160::repeat_byte(0x11);
+        let storage = (1..=3u64)
+            .map(|i| (H256::from_low_u64_be(i), H256::from_low_u64_be(i * 100)))
+            .collect();
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![(
+                address,
+                evm_state::MemoryAccount {
+                    storage,
+                    ..Default::default()
+                },
+            )]),
+            _ => panic!("Not expected state"),
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        // unset slot 7 in the middle, and repeated slot
+        let slots: Vec<Hex<U256>> = [3u64, 7, 1, 2, 1]
+            .iter()
+            .map(|i| Hex((*i).into()))
+            .collect();
+        let values = rt
+            .block_on(VelasErpcImpl::default().storage_at_multi(
+                meta.clone(),
+                Hex(address),
+                slots.clone(),
+                None,
+            ))
+            .unwrap();
+        let expected: Vec<_> = slots
+            .iter()
+            .map(|slot| {
+                rt.block_on(ChainErpcImpl.storage_at(meta.clone(), Hex(address), *slot, None))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(values, expected);
+        assert_eq!(values[0], Hex(H256::from_low_u64_be(300)));
+        assert_eq!(values[1], Hex(H256::zero()));
+
+        let too_many = vec![Hex(U256::zero()); MAX_STORAGE_SLOTS + 1];
+        let err = rt
+            .block_on(VelasErpcImpl::default().storage_at_multi(meta, Hex(address), too_many, None))
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidParams { .. }));
+    }
+
+    #[test]
+    fn test_find_unknown_transaction() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let secret_key = evm_state::SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let tx = evm_state::UnsignedTransaction {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas_limit: 21000.into(),
+            action: TransactionAction::Call(H160::repeat_byte(0x11)),
+            value: 0.into(),
+            input: vec![],
+        }
+        .sign(&secret_key, Some(bank.evm_chain_id));
+
+        let raw_tx = Bytes(rlp::encode(&tx).to_vec());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let lookup = rt
+            .block_on(VelasErpcImpl::default().find_transaction(meta.clone(), raw_tx))
+            .unwrap();
+        assert_eq!(lookup.hash.0, tx.tx_id_hash());
+        assert_eq!(lookup.status, RPCTransactionStatus::Unknown);
+        assert!(lookup.receipt.is_none());
+
+        let err = rt
+            .block_on(VelasErpcImpl::default().find_transaction(meta, Bytes(vec![1, 2])))
+            .unwrap_err();
+        assert!(matches!(err, Error::RlpError { .. }));
+    }
+
+    #[test]
+    fn test_contract_creation_lookup() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let secret_key = evm_state::SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let deploy = |nonce: u64, result: evm_state::ExitReason| {
+            let tx = evm_state::UnsignedTransaction {
+                nonce: nonce.into(),
+                gas_price: 0.into(),
+                gas_limit: 300_000.into(),
+                action: TransactionAction::Create,
+                value: 0.into(),
+                input: vec![0x60, 0x00],
+            }
+            .sign(&secret_key, Some(bank.evm_chain_id));
+            let hash = tx.tx_id_hash();
+            let creator = tx.caller().unwrap();
+            let receipt = evm_state::TransactionReceipt::new(
+                tx.into(),
+                53_000,
+                7,
+                nonce,
+                vec![],
+                (result, vec![]),
+            );
+            meta.blockstore
+                .write_evm_transaction(7, 20, hash, receipt)
+                .unwrap();
+            (
+                hash,
+                creator,
+                TransactionAction::Create.address(creator, nonce.into()),
+            )
+        };
+        let (deployed_hash, creator, deployed) = deploy(0, evm_state::ExitSucceed::Returned.into());
+        let (_, _, reverted) = deploy(1, evm_state::ExitRevert::Reverted.into());
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let creation = |address| {
+            rt.block_on(VelasErpcImpl::default().contract_creation(meta.clone(), Hex(address)))
+                .unwrap()
+        };
+        assert_eq!(
+            creation(deployed),
+            Some(RPCContractCreation {
+                tx_hash: Hex(deployed_hash),
+                block_number: Hex(7),
+                creator: Hex(creator),
+            })
+        );
+        // failed deployment and plain accounts have no creation record
+        assert_eq!(creation(reverted), None);
+        assert_eq!(creation(creator), None);
+    }
+
+    /// Writes rooted block with two transactions.
+    fn write_block_with_transactions(
+        meta: &JsonRpcRequestProcessor,
+        block_number: u64,
+        slot: u64,
+    ) -> (
+        evm_state::BlockHeader,
+        Vec<(H256, evm_state::TransactionReceipt)>,
+    ) {
+        write_block_with_logs(meta, block_number, slot, vec![])
+    }
+
+    /// Writes rooted block without transactions.
+    fn write_empty_block(meta: &JsonRpcRequestProcessor, block_number: u64, slot: u64) {
+        let transactions: Vec<(H256, evm_state::TransactionReceipt)> = vec![];
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            H256::zero(),
+            block_number,
+            0,
+            0,
+            slot,
+            H256::zero(),
+            transactions.iter(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore.write_evm_block_header(&header).unwrap();
+        meta.blockstore.set_roots(&[slot]).unwrap();
+    }
+
+    /// Writes rooted block with two transactions, each of them emitting `logs`.
+    fn write_block_with_logs(
+        meta: &JsonRpcRequestProcessor,
+        block_number: u64,
+        slot: u64,
+        logs: Vec<evm_state::Log>,
+    ) -> (
+        evm_state::BlockHeader,
+        Vec<(H256, evm_state::TransactionReceipt)>,
+    ) {
+        let block = write_unrooted_block_with_logs(meta, block_number, slot, logs);
+        meta.blockstore.set_roots(&[slot]).unwrap();
+        block
+    }
+
+    /// Writes block with two transactions, each of them emitting `logs`, without rooting its slot.
+    fn write_unrooted_block_with_logs(
+        meta: &JsonRpcRequestProcessor,
+        block_number: u64,
+        slot: u64,
+        logs: Vec<evm_state::Log>,
+    ) -> (
+        evm_state::BlockHeader,
+        Vec<(H256, evm_state::TransactionReceipt)>,
+    ) {
+        let secret_key = evm_state::SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let transactions: Vec<_> = (0..2u64)
+            .map(|nonce| {
+                let tx = evm_state::UnsignedTransaction {
+                    nonce: nonce.into(),
+                    gas_price: 1.into(),
+                    gas_limit: 21_000.into(),
+                    action: TransactionAction::Call(H160::repeat_byte(0x11)),
+                    value: 0.into(),
+                    input: vec![],
+                }
+                .sign(&secret_key, Some(meta.bank(None).evm_chain_id));
+                let hash = tx.tx_id_hash();
+                let receipt = evm_state::TransactionReceipt::new(
+                    tx.into(),
+                    21_000,
+                    block_number,
+                    nonce + 1,
+                    logs.clone(),
+                    (evm_state::ExitSucceed::Stopped.into(), vec![]),
+                );
+                (hash, receipt)
+            })
+            .collect();
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            H256::zero(),
+            block_number,
+            0,
+            0,
+            slot,
+            H256::zero(),
+            transactions.iter(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore.write_evm_block_header(&header).unwrap();
+        for (hash, receipt) in &transactions {
+            meta.blockstore
+                .write_evm_transaction(block_number, slot, *hash, receipt.clone())
+                .unwrap();
+        }
+        (header, transactions)
+    }
+
+    #[test]
+    fn test_transaction_by_block_hash_and_index() {
+        let meta = test_meta();
+        let (header, transactions) = write_block_with_transactions(&meta, 4, 12);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let by_index = |block_hash, index| {
+            rt.block_on(ChainErpcImpl.transaction_by_block_hash_and_index(
+                meta.clone(),
+                Hex(block_hash),
+                Hex(index),
+            ))
+            .unwrap()
+        };
+        for (index, (hash, _)) in transactions.iter().enumerate() {
+            let tx = by_index(header.hash(), index).unwrap();
+            assert_eq!(tx.hash, Some(Hex(*hash)));
+            assert_eq!(tx.block_hash, Some(Hex(header.hash())));
+            assert_eq!(tx.transaction_index, Some(Hex(index + 1)));
+        }
+        // out of range index and unknown block are not errors
+        assert!(by_index(header.hash(), 2).is_none());
+        assert!(by_index(H256::repeat_byte(0xff), 0).is_none());
+    }
+
+    #[test]
+    fn test_transaction_by_block_number_and_index() {
+        let meta = test_meta();
+        let (header, transactions) = write_block_with_transactions(&meta, 4, 12);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let by_index = |block, index| {
+            rt.block_on(ChainErpcImpl.transaction_by_block_number_and_index(
+                meta.clone(),
+                block,
+                Hex(index),
+            ))
+            .unwrap()
+        };
+        let expected = rt
+            .block_on(transaction_by_hash(meta.clone(), Hex(transactions[1].0)))
+            .unwrap()
+            .unwrap();
+        for block in [
+            BlockId::Num(Hex(4)),
+            BlockId::RelativeId(BlockRelId::Latest),
+        ] {
+            let tx = by_index(block, 1).unwrap();
+            assert_eq!(tx.hash, expected.hash);
+            assert_eq!(tx.block_hash, Some(Hex(header.hash())));
+            assert_eq!(tx.block_number, expected.block_number);
+            assert_eq!(tx.transaction_index, Some(Hex(2)));
+        }
+        // out of range index and missing block are not errors
+        assert!(by_index(BlockId::Num(Hex(4)), 2).is_none());
+        assert!(by_index(BlockId::Num(Hex(5)), 0).is_none());
+    }
+
+    #[test]
+    fn test_block_transaction_count() {
+        let meta = test_meta();
+        write_rooted_block(&meta, 3, 10, 0);
+        let (header, _) = write_block_with_transactions(&meta, 4, 12);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (empty_block, _) = rt.block_on(meta.get_evm_block_by_id(3)).unwrap();
+        for (block, count) in [
+            (BlockId::Num(Hex(3)), Some(Hex(0))),
+            (BlockId::Num(Hex(4)), Some(Hex(2))),
+            (BlockId::RelativeId(BlockRelId::Earliest), Some(Hex(0))),
+            (BlockId::RelativeId(BlockRelId::Latest), Some(Hex(2))),
+            (BlockId::RelativeId(BlockRelId::Pending), Some(Hex(2))),
+            (BlockId::Num(Hex(5)), None),
+        ] {
+            let result = rt
+                .block_on(ChainErpcImpl.block_transaction_count_by_number(meta.clone(), block))
+                .unwrap();
+            assert_eq!(result, count, "{:?}", block);
+        }
+        for (block_hash, count) in [
+            (empty_block.header.hash(), Some(Hex(0))),
+            (header.hash(), Some(Hex(2))),
+            (H256::repeat_byte(0xff), None),
+        ] {
+            let result = rt
+                .block_on(
+                    ChainErpcImpl.block_transaction_count_by_hash(meta.clone(), Hex(block_hash)),
+                )
+                .unwrap();
+            assert_eq!(result, count, "{:?}", block_hash);
+        }
+    }
+
+    #[test]
+    fn test_estimate_gas_above_used_gas() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        // reverts if less than 100000 gas is left on entry
+        let code = vec![
+            0x5a, // GAS
+            0x62, 0x01, 0x86, 0xa0, // PUSH3 100000
+            0x11, // GT
+            0x60, 0x0a, // PUSH1 0x0a
+            0x57, // JUMPI
+            0x00, // STOP
+            0x5b, // JUMPDEST
+            0x60, 0x00, // PUSH1 0
+            0x80, // DUP1
+            0xfd, // REVERT
+        ];
+        let contract = H160::repeat_byte(0x22);
+        let plain = H160::repeat_byte(0x33);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![(
+                contract,
+                evm_state::MemoryAccount {
+                    code,
+                    ..Default::default()
+                },
+            )]),
+            _ => panic!("Not expected state"),
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let tx = |to: H160, gas: Option<u64>| -> RPCTransaction {
+            serde_json::from_value(serde_json::json!({
+                "from": Hex(H160::repeat_byte(0x11)),
+                "to": Hex(to),
+                "gas": gas.map(|gas| Hex(Gas::from(gas))),
+            }))
+            .unwrap()
+        };
+        let estimate = |tx, buffer_percent| {
+            let state = rt.block_on(block_to_state_root(None, &meta));
+            estimate_gas(meta.clone(), tx, state, vec![], buffer_percent).unwrap()
+        };
+        let execute = |tx| {
+            let state = rt.block_on(block_to_state_root(None, &meta));
+            call(meta.clone(), tx, state, vec![], None)
+        };
+
+        let used_gas = execute(tx(contract, None)).unwrap().used_gas;
+        assert!(execute(tx(contract, Some(used_gas))).is_err());
+        let estimated = estimate(tx(contract, None), 0);
+        assert!(estimated > Gas::from(used_gas));
+        assert!(execute(tx(contract, Some(estimated.as_u64()))).is_ok());
+        assert!(execute(tx(contract, Some(estimated.as_u64() - 1))).is_err());
+        assert_eq!(
+            estimate(tx(contract, None), 5),
+            estimated + estimated * 5 / 100
         );
 
-        let mut meta_keys: Vec<_> = meta_keys
+        // transfer needs only gas it uses, and buffer is limited by gas of transaction
+        assert_eq!(estimate(tx(plain, None), 0), Gas::from(21_000));
+        assert_eq!(estimate(tx(plain, Some(21_000)), 5), Gas::from(21_000));
+        assert_eq!(
+            meta.evm_estimate_gas_buffer_percent(),
+            ESTIMATE_GAS_BUFFER_PERCENT
+        );
+    }
+
+    #[test]
+    fn test_estimate_gas_range() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        // reverts if less than 100000 gas is left on entry
+        let branching = vec![
+            0x5a, // GAS
+            0x62, 0x01, 0x86, 0xa0, // PUSH3 100000
+            0x11, // GT
+            0x60, 0x0a, // PUSH1 0x0a
+            0x57, // JUMPI
+            0x00, // STOP
+            0x5b, // JUMPDEST
+            0x60, 0x00, // PUSH1 0
+            0x80, // DUP1
+            0xfd, // REVERT
+        ];
+        let reverting = vec![
+            0x60, 0x00, // PUSH1 0
+            0x80, // DUP1
+            0xfd, // REVERT
+        ];
+        let contract = H160::repeat_byte(0x22);
+        let always_reverts = H160::repeat_byte(0x44);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![
+                (
+                    contract,
+                    evm_state::MemoryAccount {
+                        code: branching,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    always_reverts,
+                    evm_state::MemoryAccount {
+                        code: reverting,
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            _ => panic!("Not expected state"),
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let estimate_gas_range = |to: H160| {
+            let tx: RPCTransaction = serde_json::from_value(serde_json::json!({
+                "from": Hex(H160::repeat_byte(0x11)),
+                "to": Hex(to),
+            }))
+            .unwrap();
+            rt.block_on(VelasErpcImpl::default().estimate_gas_range(meta.clone(), tx, None))
+        };
+
+        let range = estimate_gas_range(contract).unwrap();
+        assert!(range.min.0 <= range.max.0);
+        let buffer_percent = meta.evm_estimate_gas_buffer_percent();
+        assert_eq!(
+            range.max.0,
+            range.min.0 + range.min.0 * buffer_percent / 100
+        );
+
+        assert!(estimate_gas_range(always_reverts).is_err());
+    }
+
+    #[test]
+    fn test_transaction_position() {
+        let meta = test_meta();
+        let (header, _) = write_block_with_transactions(&meta, 4, 12);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (block, _) = rt.block_on(meta.get_evm_block_by_id(4)).unwrap();
+        assert_eq!(block.transactions.len(), 2);
+        for (position, (hash, _)) in block.transactions.iter().enumerate() {
+            let found = rt
+                .block_on(VelasErpcImpl::default().transaction_position(meta.clone(), Hex(*hash)))
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.block_hash, Hex(header.hash()));
+            assert_eq!(found.block_number, Hex(U256::from(4)));
+            // receipt indexes are counted from 1, as in eth_getTransactionReceipt
+            assert_eq!(found.transaction_index, Hex(position + 1));
+        }
+        assert!(rt
+            .block_on(
+                VelasErpcImpl::default().transaction_position(meta, Hex(H256::repeat_byte(0xff)))
+            )
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_effective_gas_price() {
+        let meta = test_meta();
+        let (_, transactions) = write_block_with_transactions(&meta, 4, 12);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let effective_gas_price = |hash| {
+            rt.block_on(VelasErpcImpl::default().effective_gas_price(meta.clone(), Hex(hash)))
+                .unwrap()
+        };
+        // legacy transactions pay their gas price
+        for (hash, receipt) in &transactions {
+            assert_eq!(
+                effective_gas_price(*hash),
+                Some(Hex(receipt.transaction.gas_price()))
+            );
+            assert_eq!(effective_gas_price(*hash), Some(Hex(Gas::from(1))));
+        }
+        assert!(effective_gas_price(H256::repeat_byte(0xff)).is_none());
+    }
+
+    #[test]
+    fn test_block_hashes_by_range() {
+        let meta = test_meta();
+        for (block_number, slot) in (4..=6).zip(12..) {
+            write_block_with_transactions(&meta, block_number, slot);
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let hashes = |from: u64, to: u64| {
+            rt.block_on(VelasErpcImpl::default().block_hashes_by_range(
+                meta.clone(),
+                BlockId::from(from),
+                BlockId::from(to),
+            ))
+        };
+        let range = hashes(4, 6).unwrap();
+        assert_eq!(range.len(), 3);
+        for (block_number, hash) in (4..=6).zip(&range) {
+            let block = rt
+                .block_on(ChainErpcImpl.block_by_number(
+                    meta.clone(),
+                    BlockId::from(block_number),
+                    false,
+                ))
+                .unwrap()
+                .unwrap();
+            assert_eq!(block.hash, *hash);
+        }
+        assert_eq!(hashes(5, 5).unwrap(), vec![range[1]]);
+
+        assert!(matches!(
+            hashes(6, 4),
+            Err(Error::InvalidBlocksRange { .. })
+        ));
+        let max_blocks = meta.evm_max_logs_blocks();
+        assert!(matches!(
+            hashes(4, 5 + max_blocks),
+            Err(Error::InvalidBlocksRange { .. })
+        ));
+        // missing block in range
+        assert!(matches!(hashes(4, 7), Err(Error::BlockNotFound { .. })));
+        // range end doesn't overflow
+        assert!(matches!(
+            hashes(u64::MAX, u64::MAX),
+            Err(Error::BlockNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_non_empty_blocks() {
+        let meta = test_meta();
+        let (first, _) = write_block_with_transactions(&meta, 4, 12);
+        write_empty_block(&meta, 5, 13);
+        write_empty_block(&meta, 6, 14);
+        let (second, _) = write_block_with_transactions(&meta, 7, 15);
+        write_empty_block(&meta, 8, 16);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let non_empty = |from: u64, to: u64| {
+            rt.block_on(VelasErpcImpl::default().non_empty_blocks(
+                meta.clone(),
+                BlockId::from(from),
+                BlockId::from(to),
+            ))
+        };
+        let range = non_empty(4, 8).unwrap();
+        assert_eq!(range.skipped, Hex(3));
+        let hashes: Vec<_> = range.blocks.iter().map(|block| block.hash).collect();
+        assert_eq!(hashes, vec![Hex(first.hash()), Hex(second.hash())]);
+        // blocks are returned with transaction hashes only, in order of header
+        match &range.blocks[1].transactions {
+            Either::Left(hashes) => {
+                let expected: Vec<_> = second.transactions.iter().copied().map(Hex).collect();
+                assert_eq!(*hashes, expected);
+            }
+            Either::Right(_) => panic!("expected transaction hashes"),
+        }
+
+        let range = non_empty(5, 6).unwrap();
+        assert!(range.blocks.is_empty());
+        assert_eq!(range.skipped, Hex(2));
+
+        let max_blocks = meta.evm_max_logs_blocks();
+        assert!(matches!(
+            non_empty(4, 5 + max_blocks),
+            Err(Error::InvalidBlocksRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_replay_block_transactions_with_results() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let logging = H160::repeat_byte(0x21);
+        let reverting = H160::repeat_byte(0x22);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![
+                (
+                    // LOG0 with empty data, STOP
+                    logging,
+                    evm_state::MemoryAccount {
+                        code: vec![0x60, 0x00, 0x60, 0x00, 0xa0, 0x00],
+                        ..Default::default()
+                    },
+                ),
+                (
+                    // REVERT with empty data
+                    reverting,
+                    evm_state::MemoryAccount {
+                        code: vec![0x60, 0x00, 0x80, 0xfd],
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            _ => panic!("Not expected state"),
+        }
+
+        let (block_number, slot) = (4, 12);
+        let secret_key = evm_state::SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let transactions: Vec<_> = vec![logging, reverting]
             .into_iter()
-            .map(|pk| {
-                let user_account = RefCell::new(bank.get_account(&pk).unwrap_or_default());
-                (user_account, pk)
+            .zip(0..)
+            .map(|(address, nonce)| {
+                let tx = evm_state::UnsignedTransaction {
+                    nonce: nonce.into(),
+                    gas_price: 1.into(),
+                    gas_limit: 100_000.into(),
+                    action: TransactionAction::Call(address),
+                    value: 0.into(),
+                    input: vec![],
+                }
+                .sign(&secret_key, Some(meta.bank(None).evm_chain_id));
+                let hash = tx.tx_id_hash();
+                let receipt = evm_state::TransactionReceipt::new(
+                    tx.into(),
+                    21_000,
+                    block_number,
+                    nonce + 1,
+                    vec![],
+                    (evm_state::ExitSucceed::Stopped.into(), vec![]),
+                );
+                (hash, receipt)
             })
             .collect();
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            H256::zero(),
+            block_number,
+            0,
+            0,
+            slot,
+            H256::zero(),
+            transactions.iter(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore.write_evm_block_header(&header).unwrap();
+        for (hash, receipt) in &transactions {
+            meta.blockstore
+                .write_evm_transaction(block_number, slot, *hash, receipt.clone())
+                .unwrap();
+        }
+        meta.blockstore.set_roots(&[slot]).unwrap();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let replay = |block: u64| {
+            rt.block_on(
+                VelasErpcImpl::default()
+                    .replay_block_transactions_with_results(meta.clone(), BlockId::from(block)),
+            )
+        };
+        let results = replay(block_number).unwrap();
+        let hashes: Vec<_> = results.iter().map(|result| result.hash.0).collect();
+        let expected: Vec<_> = transactions.iter().map(|(hash, _)| *hash).collect();
+        assert_eq!(hashes, expected);
+
+        let (succeeded, reverted) = (&results[0], &results[1]);
+        assert!(succeeded.success);
+        assert_eq!(succeeded.logs.len(), 1);
+        assert_eq!(succeeded.logs[0].address, Hex(logging));
+        assert_eq!(succeeded.logs[0].transaction_hash, succeeded.hash);
+        assert!(!reverted.success);
+        assert!(reverted.logs.is_empty());
+        assert!(reverted.revert_reason.is_none());
+        for result in &results {
+            assert!(result.gas_used.0 > Gas::zero());
+        }
 
-        // Shortcut for swap tokens to native, will add solana account to transaction.
-        if address == *ETH_TO_VLX_ADDR {
-            debug!("Found transferToNative transaction");
-            match ETH_TO_VLX_CODE.parse_abi(&input) {
-                Ok(pk) => {
-                    info!("Adding account to meta = {}", pk);
+        assert!(matches!(
+            replay(block_number + 1),
+            Err(Error::BlockNotFound { .. })
+        ));
+    }
 
-                    let user_account = RefCell::new(bank.get_account(&pk).unwrap_or_default());
-                    meta_keys.push((user_account, pk))
-                }
-                Err(e) => {
-                    error!("Error in parsing abi = {}", e);
-                }
+    #[test]
+    fn test_logs_delta() {
+        let meta = test_meta();
+        let emitter = H160::repeat_byte(0x33);
+        let write_block = |block_number: u64| {
+            let log = evm_state::Log {
+                address: emitter,
+                topics: vec![H256::from_low_u64_be(block_number)],
+                data: vec![],
+            };
+            write_block_with_logs(&meta, block_number, block_number + 8, vec![log]);
+        };
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let filter = RPCLogFilter {
+            from_block: None,
+            to_block: None,
+            address: Some(Either::Right(Hex(emitter))),
+            topics: None,
+        };
+        let delta = |since_block: u64| {
+            rt.block_on(VelasErpcImpl::default().logs_delta(
+                meta.clone(),
+                filter.clone(),
+                Hex(since_block),
+            ))
+        };
+
+        let mut since_block = 3;
+        let mut seen = std::collections::HashSet::new();
+        let mut blocks = vec![];
+        for new_blocks in vec![4..=5, 6..=8] {
+            let latest = *new_blocks.end();
+            new_blocks.for_each(&write_block);
+            let result = delta(since_block).unwrap();
+            assert_eq!(result.latest_block, Hex(latest));
+            for log in result.logs {
+                let block = log.block_number.0.as_u64();
+                assert!(seen.insert((block, log.transaction_index, log.log_index)));
+                blocks.push(block);
             }
+            since_block = result.latest_block.0;
         }
+        // each block has two transactions emitting log
+        assert_eq!(blocks, vec![4, 4, 5, 5, 6, 6, 7, 7, 8, 8]);
+
+        // nothing new
+        let result = delta(since_block).unwrap();
+        assert!(result.logs.is_empty());
+        assert_eq!(result.latest_block, Hex(since_block));
+
+        // gap is too big, client should resync
+        write_block(since_block + meta.evm_max_logs_blocks() + 2);
+        assert!(matches!(
+            delta(since_block),
+            Err(Error::InvalidBlocksRange { .. })
+        ));
+    }
 
-        (meta_keys, TransactionAction::Call(address))
-    } else {
-        (vec![], TransactionAction::Create)
-    };
+    #[test]
+    fn test_gas_limit_above_u64() {
+        let meta = test_meta();
 
-    // system transfers always set s = 0x1
-    if Some(Hex(U256::from(0x1))) == tx.s {
-        // check if it native swap, then predeposit, amount, to pass transaction
-        if caller == *ETH_TO_VLX_ADDR {
-            let amount = value + gas_limit * gas_price;
-            executor.deposit(caller, amount)
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let tx = |gas: Gas| -> RPCTransaction {
+            serde_json::from_value(serde_json::json!({
+                "from": Hex(H160::repeat_byte(0x11)),
+                "to": Hex(H160::repeat_byte(0x33)),
+                "gas": Hex(gas),
+            }))
+            .unwrap()
+        };
+        let estimate = |tx| {
+            let state = rt.block_on(block_to_state_root(None, &meta));
+            estimate_gas(meta.clone(), tx, state, vec![], 0)
+        };
+        let execute = |tx| {
+            let state = rt.block_on(block_to_state_root(None, &meta));
+            call(meta.clone(), tx, state, vec![], None)
+        };
+
+        let max = Gas::from(u64::MAX);
+        assert_eq!(estimate(tx(max)).unwrap(), Gas::from(21_000));
+        assert_eq!(execute(tx(max)).unwrap().used_gas, 21_000);
+
+        // not truncated to lower 64 bits, which would be enough for transfer
+        let too_big = max + Gas::from(21_001);
+        assert_eq!(too_big.low_u64(), 21_000);
+        assert!(matches!(
+            estimate(tx(too_big)),
+            Err(Error::InvalidParams { .. })
+        ));
+        assert!(matches!(
+            execute(tx(too_big)),
+            Err(Error::InvalidParams { .. })
+        ));
+    }
+
+    #[test]
+    fn test_call_transfer_to_native() {
+        use solana_evm_loader_program::precompiles::ETH_TO_VLX_ADDR;
+
+        let meta = test_meta();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let execute = |input: Vec<u8>| {
+            let tx = serde_json::from_value(serde_json::json!({
+                "from": Hex(H160::repeat_byte(0x11)),
+                "to": Hex(*ETH_TO_VLX_ADDR),
+                "input": Bytes(input),
+            }))
+            .unwrap();
+            let state = rt.block_on(block_to_state_root(None, &meta));
+            call(meta.clone(), tx, state, vec![], None)
+        };
+
+        // transferToNative(bytes32) selector, followed by recipient
+        let mut input = vec![0xb1, 0xd6, 0x92, 0x7a];
+        input.extend_from_slice(&solana_sdk::pubkey::Pubkey::new_unique().to_bytes());
+        let output = execute(input.clone()).unwrap();
+        assert!(matches!(
+            output.exit_reason,
+            evm_state::ExitReason::Succeed(_)
+        ));
+
+        let mut wrong_selector = input.clone();
+        wrong_selector[0] = 0;
+        for malformed in vec![vec![], input[..20].to_vec(), wrong_selector] {
+            assert!(matches!(
+                execute(malformed),
+                Err(Error::InvalidPrecompileCall { address, .. }) if address == *ETH_TO_VLX_ADDR
+            ));
         }
     }
 
-    let user_accounts: Vec<_> = user_accounts
-        .iter()
-        .map(|(user_account, pk)| KeyedAccount::new(pk, false, user_account))
-        .collect();
+    #[test]
+    fn test_call_with_pending_nonce() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let caller = H160::repeat_byte(0x11);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![(
+                caller,
+                evm_state::MemoryAccount {
+                    nonce: 2.into(),
+                    ..Default::default()
+                },
+            )]),
+            _ => panic!("Not expected state"),
+        }
 
-    let evm_state::executor::ExecutionResult {
-        exit_reason,
-        exit_data,
-        used_gas,
-        traces,
-        ..
-    } = executor
-        .transaction_execute_raw(
-            caller,
-            nonce,
-            gas_price,
-            gas_limit,
-            action,
-            input,
-            value,
-            Some(tx_chain_id),
-            tx_hash,
-            true,
-            solana_evm_loader_program::precompiles::simulation_entrypoint(
-                executor.support_precompile(),
-                evm_state_balance,
-                &user_accounts,
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let call = |nonce: Option<u64>, block| {
+            let tx: RPCTransaction = serde_json::from_value(serde_json::json!({
+                "from": Hex(caller),
+                "to": Hex(H160::repeat_byte(0x22)),
+                "nonce": nonce.map(|nonce| Hex(U256::from(nonce))),
+            }))
+            .unwrap();
+            rt.block_on(ChainErpcImpl.call(meta.clone(), tx, Some(block), None, None))
+        };
+        let pending = BlockId::RelativeId(BlockRelId::Pending);
+        let latest = BlockId::RelativeId(BlockRelId::Latest);
+
+        // nonce of transactions, that are not in state yet, is accepted only on pending block
+        assert!(call(Some(4), pending).is_ok());
+        assert!(call(Some(4), latest).is_err());
+        assert!(call(None, pending).is_ok());
+        assert!(call(Some(2), latest).is_ok());
+        // already used nonce is still rejected
+        assert!(call(Some(1), pending).is_err());
+    }
+
+    #[test]
+    fn test_call_with_state_overrides() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        // returns storage slot 0
+        let code = vec![
+            0x60, 0x00, // PUSH1 0
+            0x54, // SLOAD
+            0x60, 0x00, // PUSH1 0
+            0x52, // MSTORE
+            0x60, 0x20, // PUSH1 32
+            0x60, 0x00, // PUSH1 0
+            0xf3, // RETURN
+        ];
+        let contract = H160::repeat_byte(0x22);
+        let slot = |value: u64| H256::from_low_u64_be(value);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![(
+                contract,
+                evm_state::MemoryAccount {
+                    code: code.clone(),
+                    storage: vec![(slot(0), slot(1)), (slot(1), slot(2))]
+                        .into_iter()
+                        .collect(),
+                    ..Default::default()
+                },
+            )]),
+            _ => panic!("Not expected state"),
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let call = |to: H160, overrides: Vec<(H160, evm_rpc::AccountOverride)>| {
+            let tx: RPCTransaction = serde_json::from_value(serde_json::json!({
+                "from": Hex(H160::repeat_byte(0x11)),
+                "to": Hex(to),
+            }))
+            .unwrap();
+            let overrides = overrides
+                .into_iter()
+                .map(|(address, account)| (Hex(address), account))
+                .collect();
+            let output = rt
+                .block_on(ChainErpcImpl.call(meta.clone(), tx, None, None, Some(overrides)))
+                .unwrap();
+            H256::from_slice(&output.0)
+        };
+        let storage_override = |index, value| evm_rpc::AccountOverride {
+            state_diff: Some(
+                vec![(Hex(slot(index)), Hex(slot(value)))]
+                    .into_iter()
+                    .collect(),
             ),
-        )
-        .with_context(|| EvmStateError)?;
+            ..Default::default()
+        };
 
-    Ok(TxOutput {
-        exit_reason,
-        exit_data,
-        used_gas,
-        traces,
-    })
-}
+        assert_eq!(call(contract, vec![]), slot(1));
+        assert_eq!(
+            call(contract, vec![(contract, storage_override(0, 7))]),
+            slot(7)
+        );
+        // other slots are kept, and overrides are not persisted
+        assert_eq!(
+            call(contract, vec![(contract, storage_override(1, 7))]),
+            slot(1)
+        );
+        assert_eq!(call(contract, vec![]), slot(1));
 
-#[instrument(skip(meta))]
-async fn block_by_number(
-    meta: JsonRpcRequestProcessor,
-    block: BlockId,
-    full: bool,
-) -> Result<Option<RPCBlock>, Error> {
-    let num = block_parse_confirmed_num(Some(block), &meta).await;
-    let evm_block = match num {
-        Some(block_num) => meta.get_evm_block_by_id(block_num).await,
-        None => None,
-    };
-    // TODO: Inline evm_state lookups, and request only solana headers.
-    let (block, confirmed) = match evm_block {
-        None => {
-            error!("Error requesting block:{:?} ({:?}) not found", block, num);
-            return Ok(None);
+        // code and storage of account without code
+        let empty = H160::repeat_byte(0x33);
+        let code_override = evm_rpc::AccountOverride {
+            code: Some(Bytes(code)),
+            ..storage_override(0, 9)
+        };
+        assert_eq!(call(empty, vec![(empty, code_override)]), slot(9));
+    }
+
+    #[test]
+    fn test_revert_data_in_error() {
+        use serde_json::{json, Value};
+
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        // Error("nope"), as emitted by `revert("nope")`
+        let mut revert_data = vec![0x08, 0xc3, 0x79, 0xa0];
+        revert_data.extend_from_slice(&H256::from_low_u64_be(0x20).0);
+        revert_data.extend_from_slice(&H256::from_low_u64_be(4).0);
+        revert_data.extend_from_slice(&H256::from_slice(&[b"nope".as_ref(), &[0; 28]].concat()).0);
+        // copies revert data from the end of code, and reverts with it
+        let mut code = vec![
+            0x60, 0x64, // PUSH1 100
+            0x60, 0x0c, // PUSH1 12
+            0x60, 0x00, // PUSH1 0
+            0x39, // CODECOPY
+            0x60, 0x64, // PUSH1 100
+            0x60, 0x00, // PUSH1 0
+            0xfd, // REVERT
+        ];
+        code.extend_from_slice(&revert_data);
+        let contract = H160::repeat_byte(0x22);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![(
+                contract,
+                evm_state::MemoryAccount {
+                    code,
+                    ..Default::default()
+                },
+            )]),
+            _ => panic!("Not expected state"),
         }
-        Some(b) => b,
-    };
+        let mut io = jsonrpc_core::MetaIoHandler::default();
+        io.extend_with(ChainErpcImpl.to_delegate());
+
+        let tx = json!({"from": Hex(H160::repeat_byte(0x11)), "to": Hex(contract)});
+        for method in ["eth_call", "eth_estimateGas"] {
+            let request =
+                json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": [tx, "latest"]});
+            let response = io
+                .handle_request_sync(&request.to_string(), meta.clone())
+                .unwrap();
+            let response: Value = serde_json::from_str(&response).unwrap();
+            assert_eq!(response["error"]["code"], 3, "{}: {}", method, response);
+            assert_eq!(response["error"]["message"], "execution reverted: nope");
+            assert_eq!(
+                response["error"]["data"],
+                json!(Bytes(revert_data.clone()).to_string())
+            );
+        }
+    }
 
-    let bank = meta.bank(None);
-    let chain_id = bank.evm_chain_id;
+    #[test]
+    fn test_code_size() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let contract = H160::repeat_byte(0x22);
+        match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => i.set_initial(vec![(
+                contract,
+                evm_state::MemoryAccount {
+                    code: vec![0x60, 0x00, 0x60, 0x00, 0xf3],
+                    ..Default::default()
+                },
+            )]),
+            _ => panic!("Not expected state"),
+        }
 
-    let block_hash = block.header.hash();
-    let transactions = if full {
-        let txs = block
-            .transactions
-            .into_iter()
-            .filter_map(|(hash, receipt)| {
-                RPCTransaction::new_from_receipt(receipt, hash, block_hash, chain_id).ok()
-            })
-            .collect();
-        Either::Right(txs)
-    } else {
-        let txs = block
-            .transactions
-            .into_iter()
-            .map(|(k, _v)| Hex(k))
-            .collect();
-        Either::Left(txs)
-    };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let sizes = |address: H160| {
+            let code = rt
+                .block_on(ChainErpcImpl.code(meta.clone(), Hex(address), None))
+                .unwrap();
+            let size = rt
+                .block_on(VelasErpcImpl::default().code_size(meta.clone(), Hex(address), None))
+                .unwrap();
+            (code.0.len(), size.0)
+        };
 
-    Ok(Some(RPCBlock::new_from_head(
-        block.header,
-        confirmed,
-        transactions,
-    )))
-}
+        assert_eq!(sizes(contract), (5, 5));
+        // account without code
+        assert_eq!(sizes(H160::repeat_byte(0x33)), (0, 0));
+    }
 
-#[instrument(skip(meta))]
-async fn transaction_by_hash(
-    meta: JsonRpcRequestProcessor,
-    tx_hash: Hex<H256>,
-) -> Result<Option<RPCTransaction>, Error> {
-    let bank = meta.bank(None);
-    let chain_id = bank.evm_chain_id;
-    Ok(match meta.get_evm_receipt_by_hash(tx_hash.0).await {
-        Some(receipt) => {
-            let (block, _) = meta.get_evm_block_by_id(receipt.block_number)
-                .await
-                .ok_or({
-                    Error::BlockNotFound { block: receipt.block_number.into() }
-                })?;
-            let block_hash = block.header.hash();
-            Some(RPCTransaction::new_from_receipt(
-                receipt, tx_hash.0, block_hash, chain_id,
-            )?)
+    #[test]
+    fn test_call_on_committed_state() {
+        let meta = test_meta();
+        let bank = meta.bank(None);
+        let contract = H160::repeat_byte(0x22);
+        {
+            let mut evm_state = bank.evm_state.write().unwrap();
+            let mut incomming = match evm_state.clone() {
+                evm_state::EvmState::Incomming(i) => i,
+                _ => panic!("Not expected state"),
+            };
+            incomming.set_initial(vec![(
+                contract,
+                evm_state::MemoryAccount {
+                    // PUSH1 0x2a, PUSH1 0, MSTORE, PUSH1 0x20, PUSH1 0, RETURN
+                    code: vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3],
+                    ..Default::default()
+                },
+            )]);
+            // bank state between blocks, calls should be run on the next block
+            *evm_state =
+                evm_state::EvmState::Committed(incomming.commit_block(bank.slot(), H256::zero()));
         }
-        None => None,
-    })
-}
 
-#[instrument(skip(meta))]
-async fn trace_call_many(
-    meta: JsonRpcRequestProcessor,
-    tx_traces: Vec<(RPCTransaction, Vec<String>, Option<TraceMeta>)>,
-    block: Option<BlockId>,
-) -> Result<Vec<evm_rpc::trace::TraceResultsWithTransactionHash>, Error> {
-    let saved_state = block_to_state_root(block, &meta).await;
+        let tx: RPCTransaction = serde_json::from_value(serde_json::json!({
+            "from": Hex(H160::repeat_byte(0x11)),
+            "to": Hex(contract),
+        }))
+        .unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let output = rt
+            .block_on(ChainErpcImpl.call(meta, tx, None, None, None))
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&output.0), U256::from(0x2a));
+    }
 
-    let mut txs = Vec::new();
-    let mut txs_meta = Vec::new();
+    #[test]
+    fn test_full_block_transactions_limit() {
+        let meta = test_meta();
+        write_block_with_transactions(&meta, 4, 12);
+        assert_eq!(
+            meta.evm_max_full_block_transactions(),
+            MAX_FULL_BLOCK_TRANSACTIONS
+        );
 
-    // TODO: Handle Vec<String> - traces array, check that it contain "trace" string.
-    for (t, _, meta) in tx_traces {
-        let meta = meta.unwrap_or_default();
-        let meta_keys = meta
-            .meta_keys
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let block = |full, max_full_transactions| {
+            rt.block_on(block_by_number(
+                meta.clone(),
+                BlockId::Num(Hex(4)),
+                full,
+                max_full_transactions,
+            ))
+        };
+        assert!(matches!(
+            block(true, Some(1)),
+            Err(Error::InvalidParams { .. })
+        ));
+        // hashes and blocks within limit are still returned
+        for (full, max_full_transactions) in [(false, Some(1)), (true, Some(2)), (true, None)] {
+            let block = block(full, max_full_transactions).unwrap().unwrap();
+            let transactions = match block.transactions {
+                Either::Left(hashes) => hashes.len(),
+                Either::Right(txs) => txs.len(),
+            };
+            assert_eq!(transactions, 2);
+        }
+    }
+
+    #[test]
+    fn test_blocks_by_hashes() {
+        let meta = test_meta();
+        let (first, _) = write_block_with_transactions(&meta, 4, 12);
+        let (second, _) = write_block_with_transactions(&meta, 5, 13);
+        let unknown = H256::repeat_byte(0x11);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let blocks_by_hashes = |hashes: Vec<H256>, full| {
+            rt.block_on(VelasErpcImpl::default().blocks_by_hashes(
+                meta.clone(),
+                hashes.into_iter().map(Hex).collect(),
+                full,
+            ))
+        };
+        let blocks = blocks_by_hashes(
+            vec![second.hash(), unknown, first.hash(), second.hash()],
+            true,
+        )
+        .unwrap();
+        let hashes: Vec<_> = blocks
             .iter()
-            .flatten()
-            .map(|s| solana_sdk::pubkey::Pubkey::from_str(s))
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| into_native_error(e, false))?;
+            .map(|b| b.as_ref().map(|b| b.hash.0))
+            .collect();
+        assert_eq!(
+            hashes,
+            vec![
+                Some(second.hash()),
+                None,
+                Some(first.hash()),
+                Some(second.hash())
+            ]
+        );
+        for block in blocks.into_iter().flatten() {
+            assert!(matches!(block.transactions, Either::Right(txs) if txs.len() == 2));
+        }
 
-        txs.push((t, meta_keys));
-        txs_meta.push(meta);
+        assert!(blocks_by_hashes(vec![], false).unwrap().is_empty());
+        assert!(matches!(
+            blocks_by_hashes(vec![unknown; MAX_BLOCK_HASHES + 1], false),
+            Err(Error::InvalidParams { .. })
+        ));
     }
 
-    let traces = call_many(meta, &txs, saved_state)?.into_iter();
+    #[test]
+    fn test_block_id_by_hash_cached() {
+        let meta = test_meta();
+        let (header, _) = write_block_with_transactions(&meta, 4, 12);
+        let unknown = H256::repeat_byte(0x11);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        assert_eq!(meta.evm_rpc_cache().block_id_by_hash(&header.hash()), None);
+        assert_eq!(
+            rt.block_on(meta.get_evm_block_id_by_hash(header.hash())),
+            Some(4)
+        );
+        assert_eq!(
+            meta.evm_rpc_cache().block_id_by_hash(&header.hash()),
+            Some(4)
+        );
+        // unknown hashes are not cached
+        assert_eq!(rt.block_on(meta.get_evm_block_id_by_hash(unknown)), None);
+        assert_eq!(meta.evm_rpc_cache().block_id_by_hash(&unknown), None);
+
+        // second lookup is served from cache, even for hash missing in blockstore
+        meta.evm_rpc_cache().set_block_id_by_hash(unknown, 4);
+        assert_eq!(rt.block_on(meta.get_evm_block_id_by_hash(unknown)), Some(4));
+        // but block is returned only if its hash matches, as for reorged block
+        assert!(rt.block_on(meta.get_evm_block_by_hash(unknown)).is_none());
+        let (block, _) = rt
+            .block_on(meta.get_evm_block_by_hash(header.hash()))
+            .unwrap();
+        assert_eq!(block.header.hash(), header.hash());
+    }
 
-    let mut result = Vec::new();
-    for (output, meta_tx) in traces.zip(txs_meta) {
-        result.push(evm_rpc::trace::TraceResultsWithTransactionHash {
-            trace: output.traces.into_iter().map(From::from).collect(),
-            output: output.exit_data.into(),
-            transaction_hash: meta_tx.transaction_hash.map(Hex),
-            transaction_index: meta_tx.transaction_index.map(Hex),
-            block_hash: meta_tx.block_hash.map(Hex),
-            block_number: meta_tx.block_number.map(Hex),
-        })
+    #[test]
+    fn test_uncles_always_empty() {
+        use serde_json::{json, Value};
+
+        let meta = test_meta();
+        let (header, _) = write_block_with_transactions(&meta, 4, 12);
+        let mut io = jsonrpc_core::MetaIoHandler::default();
+        io.extend_with(ChainErpcImpl.to_delegate());
+
+        let call = |method: &str, params: Value| -> Value {
+            let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            let response = io
+                .handle_request_sync(&request.to_string(), meta.clone())
+                .unwrap();
+            let mut response: Value = serde_json::from_str(&response).unwrap();
+            assert!(response.get("error").is_none(), "{}: {}", method, response);
+            response["result"].take()
+        };
+
+        // known and unknown blocks are answered the same way
+        for hash in [Hex(header.hash()), Hex(H256::repeat_byte(0x11))] {
+            for params in [json!([hash, "0x0"]), json!([hash, "0x1"])] {
+                assert_eq!(call("eth_getUncleByBlockHashAndIndex", params), json!(null));
+            }
+            assert_eq!(
+                call("eth_getUncleCountByBlockHash", json!([hash])),
+                json!("0x0")
+            );
+        }
+        for block in ["latest", "0x4", "0x10"] {
+            for params in [json!([block, "0x0"]), json!([block, "0x1"])] {
+                assert_eq!(
+                    call("eth_getUncleByBlockNumberAndIndex", params),
+                    json!(null)
+                );
+            }
+            assert_eq!(
+                call("eth_getUncleCountByBlockNumber", json!([block])),
+                json!("0x0")
+            );
+        }
+
+        // blocks don't reference uncles either
+        let blocks = [
+            call("eth_getBlockByNumber", json!(["0x4", false])),
+            call("eth_getBlockByHash", json!([Hex(header.hash()), true])),
+        ];
+        for block in blocks {
+            assert_eq!(block["uncles"], json!([]));
+            assert_eq!(
+                block["sha3Uncles"],
+                json!(Hex(evm_state::empty_ommers_hash()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_root_from_block_header() {
+        let meta = test_meta();
+        let (header, _) = write_block_with_transactions(&meta, 4, 12);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let state_root = |block: BlockId| rt.block_on(block_to_state_root(Some(block), &meta));
+        let expected = (Some(header.state_root), Some(header.timestamp));
+
+        let state = state_root(BlockId::from(4));
+        assert_eq!((state.state_root, state.block_timestamp), expected);
+        assert!(state.bank.is_none());
+
+        let state = state_root(BlockId::BlockHash {
+            block_hash: Hex(header.hash()),
+        });
+        assert_eq!((state.state_root, state.block_timestamp), expected);
+
+        let state = state_root(BlockId::BlockHash {
+            block_hash: Hex(H256::repeat_byte(0xff)),
+        });
+        assert_eq!(state.state_root, None);
+
+        let (header_by_id, confirmed) = rt.block_on(meta.get_evm_block_header_by_id(4)).unwrap();
+        assert_eq!(header_by_id.hash(), header.hash());
+        assert!(confirmed);
+        assert!(rt.block_on(meta.get_evm_block_header_by_id(5)).is_none());
+    }
+
+    #[test]
+    fn test_receipt_with_trace() {
+        let meta = test_meta();
+        let (header, transactions) = write_block_with_transactions(&meta, 4, 12);
+        let tx_hash = transactions[1].0;
+
+        let trace_impl = TraceErpcImpl::default();
+        let velas_impl = VelasErpcImpl::new(trace_impl.clone());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let receipt_with_trace = |tx_hash| {
+            rt.block_on(velas_impl.receipt_with_trace(
+                meta.clone(),
+                Hex(tx_hash),
+                vec!["trace".to_string()],
+            ))
+            .unwrap()
+        };
+        let RPCReceiptWithTrace { receipt, trace } = receipt_with_trace(tx_hash).unwrap();
+        assert_eq!(receipt.transaction_hash, Hex(tx_hash));
+        assert_eq!(receipt.block_hash, Hex(header.hash()));
+        assert_eq!(receipt.transaction_index, Hex(2));
+        assert_eq!(trace.transaction_hash, Some(Hex(tx_hash)));
+        assert_eq!(trace.transaction_index, Some(Hex(2)));
+        // replay is cached for later trace requests
+        assert_eq!(trace_impl.replay_cache.lock().unwrap().len(), 1);
+
+        assert!(receipt_with_trace(H256::repeat_byte(0xff)).is_none());
+    }
+
+    #[test]
+    fn test_block_range_matches_tags() {
+        let meta = test_meta();
+        for (block_number, slot) in [(3, 10), (4, 11), (5, 12)].iter() {
+            write_rooted_block(&meta, *block_number, *slot, 0);
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let range = rt
+            .block_on(VelasErpcImpl::default().block_range(meta.clone()))
+            .unwrap();
+        let earliest = rt.block_on(block_parse_confirmed_num(
+            Some(BlockId::RelativeId(BlockRelId::Earliest)),
+            &meta,
+        ));
+        let latest = rt.block_on(block_parse_confirmed_num(
+            Some(BlockId::RelativeId(BlockRelId::Latest)),
+            &meta,
+        ));
+
+        assert_eq!(range.earliest, Hex(3));
+        assert_eq!(range.latest, Hex(5));
+        assert_eq!(Some(range.earliest.0), earliest);
+        assert_eq!(Some(range.latest.0), latest);
     }
-    Ok(result)
 }
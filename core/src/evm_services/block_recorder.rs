@@ -3,7 +3,7 @@ use solana_ledger::blockstore::Blockstore;
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     thread::{self, Builder, JoinHandle},
     time::Duration,
@@ -11,6 +11,8 @@ use std::{
 
 use evm_state::Block;
 
+use crate::evm_rpc_impl::EvmRpcCache;
+
 pub type EvmRecorderReceiver = Receiver<Block>;
 pub type EvmRecorderSender = Sender<Block>;
 
@@ -23,6 +25,7 @@ impl EvmRecorderService {
     pub fn new(
         evm_recorder_receiver: EvmRecorderReceiver,
         blockstore: Arc<Blockstore>,
+        evm_rpc_cache: Arc<Mutex<EvmRpcCache>>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         let exit = exit.clone();
@@ -33,7 +36,7 @@ impl EvmRecorderService {
                     break;
                 }
                 if let Err(RecvTimeoutError::Disconnected) =
-                    Self::write_evm_record(&evm_recorder_receiver, &blockstore)
+                    Self::write_evm_record(&evm_recorder_receiver, &blockstore, &evm_rpc_cache)
                 {
                     break;
                 }
@@ -45,8 +48,10 @@ impl EvmRecorderService {
     fn write_evm_record(
         evm_records_receiver: &EvmRecorderReceiver,
         blockstore: &Arc<Blockstore>,
+        evm_rpc_cache: &Mutex<EvmRpcCache>,
     ) -> Result<(), RecvTimeoutError> {
         let block = evm_records_receiver.recv_timeout(Duration::from_secs(1))?;
+        evm_rpc_cache.lock().unwrap().index_block_topic0(&block);
         let block_header = block.header;
         debug!("Writing evm block num = {}", block_header.block_number);
         blockstore
@@ -62,6 +67,10 @@ impl EvmRecorderService {
                 )
                 .expect("Expected database write to succed");
         }
+        evm_rpc_cache
+            .lock()
+            .unwrap()
+            .on_evm_block_advanced(block_header.block_number);
         Ok(())
     }
 
@@ -3,6 +3,7 @@
 use crate::{
     cluster_info::ClusterInfo,
     contact_info::ContactInfo,
+    evm_rpc_impl::EvmRpcCache,
     max_slots::MaxSlots,
     non_circulating_supply::calculate_non_circulating_supply,
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
@@ -38,8 +39,8 @@ use solana_client::{
 };
 use solana_faucet::faucet::request_airdrop_transaction;
 use solana_ledger::{
-    blockstore::Blockstore, blockstore_db::BlockstoreError, get_tmp_ledger_path,
-    leader_schedule_cache::LeaderScheduleCache,
+    blockstore::Blockstore, blockstore_db::BlockstoreError, blockstore_meta::EvmContractCreation,
+    get_tmp_ledger_path, leader_schedule_cache::LeaderScheduleCache,
 };
 use solana_metrics::inc_new_counter_info;
 use solana_perf::packet::PACKET_DATA_SIZE;
@@ -88,7 +89,7 @@ use std::{
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
         mpsc::{channel, Receiver, Sender},
-        Arc, Mutex, RwLock,
+        Arc, Mutex, MutexGuard, RwLock,
     },
     time::{Duration, Instant},
 };
@@ -144,6 +145,20 @@ pub struct JsonRpcConfig {
     pub rpc_bigtable_timeout: Option<Duration>,
     pub minimal_api: bool,
     pub rpc_scan_and_fix_roots: bool,
+    pub evm_topic0_index: bool,
+    /// How many blocks below the latest one is considered "safe" block.
+    pub evm_safe_block_depth: u64,
+    /// Maximum number of transactions in evm block, returned with `full` flag.
+    pub evm_max_full_block_transactions: Option<usize>,
+    /// Percent of gas, added to minimal gas limit found by eth_estimateGas.
+    pub evm_estimate_gas_buffer_percent: Option<u64>,
+    /// Number of block hash lookups, cached by evm rpc.
+    pub evm_block_hash_cache_size: Option<usize>,
+    /// Maximum number of blocks in eth_getLogs range.
+    pub evm_max_logs_blocks: Option<u64>,
+    /// Maximum size in bytes of web3_sha3 input.
+    pub evm_max_input_size: Option<usize>,
+    pub gas_price_config: evm_rpc::GasPriceConfig,
 }
 
 #[derive(Clone)]
@@ -165,6 +180,7 @@ pub struct JsonRpcRequestProcessor {
     leader_schedule_cache: Arc<LeaderScheduleCache>,
     max_complete_transaction_status_slot: Arc<AtomicU64>,
     evm_state_archive: Option<evm_state::Storage>,
+    evm_rpc_cache: Arc<Mutex<EvmRpcCache>>,
 }
 
 impl Metadata for JsonRpcRequestProcessor {}
@@ -253,6 +269,7 @@ impl JsonRpcRequestProcessor {
         leader_schedule_cache: Arc<LeaderScheduleCache>,
         max_complete_transaction_status_slot: Arc<AtomicU64>,
         evm_state_archive: Option<evm_state::Storage>,
+        evm_rpc_cache: Arc<Mutex<EvmRpcCache>>,
     ) -> (Self, Receiver<TransactionInfo>) {
         let (sender, receiver) = channel();
         (
@@ -274,6 +291,7 @@ impl JsonRpcRequestProcessor {
                 leader_schedule_cache,
                 max_complete_transaction_status_slot,
                 evm_state_archive,
+                evm_rpc_cache,
             },
             receiver,
         )
@@ -317,6 +335,7 @@ impl JsonRpcRequestProcessor {
             leader_schedule_cache: Arc::new(LeaderScheduleCache::new_from_bank(bank)),
             max_complete_transaction_status_slot: Arc::new(AtomicU64::default()),
             evm_state_archive: None,
+            evm_rpc_cache: Arc::default(),
         }
     }
 
@@ -324,6 +343,34 @@ impl JsonRpcRequestProcessor {
         self.health.check()
     }
 
+    pub fn gas_price_config(&self) -> &evm_rpc::GasPriceConfig {
+        &self.config.gas_price_config
+    }
+
+    pub fn evm_max_full_block_transactions(&self) -> usize {
+        self.config
+            .evm_max_full_block_transactions
+            .unwrap_or(crate::evm_rpc_impl::MAX_FULL_BLOCK_TRANSACTIONS)
+    }
+
+    pub fn evm_max_logs_blocks(&self) -> u64 {
+        self.config
+            .evm_max_logs_blocks
+            .unwrap_or(crate::evm_rpc_impl::MAX_NUM_BLOCKS)
+    }
+
+    pub fn evm_max_input_size(&self) -> usize {
+        self.config
+            .evm_max_input_size
+            .unwrap_or(crate::evm_rpc_impl::MAX_INPUT_SIZE)
+    }
+
+    pub fn evm_estimate_gas_buffer_percent(&self) -> u64 {
+        self.config
+            .evm_estimate_gas_buffer_percent
+            .unwrap_or(crate::evm_rpc_impl::ESTIMATE_GAS_BUFFER_PERCENT)
+    }
+
     pub fn evm_state_archive_storage(&self) -> &Option<evm_state::Storage> {
         &self.evm_state_archive
     }
@@ -2028,6 +2075,17 @@ impl JsonRpcRequestProcessor {
 
         filter_request_time += filter_request.elapsed();
 
+        let topic0 = self
+            .config
+            .evm_topic0_index
+            .then(|| Self::topic0_only(&filter))
+            .flatten();
+        if let Some(topic0) = topic0 {
+            if let Some(logs) = self.filter_logs_indexed(topic0, &filter, &masks).await? {
+                return Ok(logs);
+            }
+        }
+
         let mut logs = Vec::new();
         for block in self
             .get_evm_blocks_by_ids(filter.from_block, filter.to_block)
@@ -2042,6 +2100,73 @@ impl JsonRpcRequestProcessor {
         Ok(logs)
     }
 
+    /// Counts logs matching filter, without collecting them.
+    #[instrument(skip(self))]
+    pub async fn count_logs(
+        &self,
+        filter: evm_state::LogFilter,
+    ) -> solana_ledger::blockstore_db::Result<usize> {
+        let masks = filter.bloom_possibilities();
+        let mut count = 0;
+        for block in self
+            .get_evm_blocks_by_ids(filter.from_block, filter.to_block)
+            .await?
+        {
+            count += Blockstore::count_block_logs(&block, &masks, &filter);
+        }
+        Ok(count)
+    }
+
+    /// Returns event signature, if filter is suitable for topic0 index (has no addresses and pins only topic0).
+    fn topic0_only(filter: &evm_state::LogFilter) -> Option<evm_state::H256> {
+        if !filter.address.is_empty() {
+            return None;
+        }
+        let (first, rest) = filter.topics.split_first()?;
+        match first {
+            evm_state::LogFilterTopicEntry::One(topic)
+                if rest
+                    .iter()
+                    .all(|t| matches!(t, evm_state::LogFilterTopicEntry::Any)) =>
+            {
+                Some(*topic)
+            }
+            _ => None,
+        }
+    }
+
+    /// Filter logs using topic0 index, returns None if range is not indexed.
+    /// Index is filled by block recorder and can contain blocks of other forks,
+    /// so logs are filtered from block that is currently stored under indexed number.
+    async fn filter_logs_indexed(
+        &self,
+        topic0: evm_state::H256,
+        filter: &evm_state::LogFilter,
+        masks: &[evm_state::Bloom],
+    ) -> solana_ledger::blockstore_db::Result<Option<Vec<evm_state::LogWithLocation>>> {
+        let blocks =
+            match self
+                .evm_rpc_cache()
+                .topic0_blocks(&topic0, filter.from_block, filter.to_block)
+            {
+                Some(blocks) => blocks,
+                None => return Ok(None),
+            };
+        let mut logs = Vec::new();
+        for block_num in blocks {
+            let block = match self.get_evm_block_by_id(block_num).await {
+                Some((block, _)) => block,
+                None => {
+                    warn!(target: "evm", "Indexed block {} not found, fallback to scan", block_num);
+                    return Ok(None);
+                }
+            };
+            logs.extend(Blockstore::filter_block_logs(&block, masks, filter)?);
+        }
+        debug!(target: "evm", "Filter evm logs using topic0 index, topic = {:?}", topic0);
+        Ok(Some(logs))
+    }
+
     #[instrument(skip(self))]
     pub async fn get_first_available_evm_block(&self) -> u64 {
         let block = self
@@ -2070,6 +2195,10 @@ impl JsonRpcRequestProcessor {
             .unwrap_or(None)
     }
 
+    pub(crate) fn evm_rpc_cache(&self) -> MutexGuard<'_, EvmRpcCache> {
+        self.evm_rpc_cache.lock().unwrap()
+    }
+
     ///
     /// Get last evm block which has respective native chain block rooted
 
@@ -2082,6 +2211,26 @@ impl JsonRpcRequestProcessor {
             .map(|((block_num, _), _)| block_num)
     }
 
+    /// Get last evm block, committed by rooted bank or its ancestors.
+    pub fn get_finalized_evm_block(&self) -> Option<u64> {
+        let bank = self.bank(Some(CommitmentConfig::finalized()));
+        let evm = bank.evm_state.read().unwrap();
+        match &*evm {
+            evm_state::EvmState::Committed(_) => Some(evm.block_number()),
+            // incomming block of bank without evm transactions is not committed
+            evm_state::EvmState::Incomming(_) => evm.block_number().checked_sub(1),
+        }
+    }
+
+    /// Get evm block `evm_safe_block_depth` below the last one, but not older than finalized.
+    pub fn get_safe_evm_block(&self) -> Option<u64> {
+        let finalized = self.get_finalized_evm_block();
+        let safe = self
+            .get_last_available_evm_block()
+            .map(|block| block.saturating_sub(self.config.evm_safe_block_depth));
+        finalized.max(safe)
+    }
+
     #[instrument(skip(self))]
     pub async fn get_evm_receipt_by_hash(
         &self,
@@ -2109,6 +2258,38 @@ impl JsonRpcRequestProcessor {
         receipt
     }
 
+    /// Returns header of block, without loading its transactions.
+    #[instrument(skip(self))]
+    pub async fn get_evm_block_header_by_id(
+        &self,
+        id: evm_state::BlockNum,
+    ) -> Option<(evm_state::BlockHeader, bool)> {
+        let header = self.blockstore.get_evm_block_header(id).ok();
+        if header.is_some() {
+            return header;
+        }
+
+        let last_evm_block = self.get_last_available_evm_block();
+        if last_evm_block.is_none()
+            || matches!(last_evm_block, Some(last_evm_block) if id <= last_evm_block)
+        {
+            if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
+                let bigtable_header = bigtable_ledger_storage
+                    .get_evm_confirmed_block_header(id)
+                    .await
+                    .ok();
+
+                // bigtable store only confirmed slots
+                return bigtable_header.map(|header| {
+                    let above_our_chain = self.blockstore.last_root() < header.native_chain_slot;
+                    // return confirmed if we have seen it before.
+                    (header, !above_our_chain)
+                });
+            }
+        }
+        None
+    }
+
     #[instrument(skip(self))]
     pub async fn get_evm_block_by_id(
         &self,
@@ -2140,25 +2321,53 @@ impl JsonRpcRequestProcessor {
         None
     }
 
+    /// Returns number of block with hash, block can be in reorged fork.
     #[instrument(skip(self))]
     pub async fn get_evm_block_id_by_hash(&self, hash: evm_state::H256) -> Option<u64> {
-        let block = self
+        if let Some(block) = self.evm_rpc_cache().block_id_by_hash(&hash) {
+            return Some(block);
+        }
+        let mut block = self
             .blockstore
             .read_evm_block_id_by_hash(hash)
             .unwrap_or_default();
         if block.is_none() {
             if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                let bigtable_block = bigtable_ledger_storage
+                block = bigtable_ledger_storage
                     .get_evm_block_by_hash(hash)
                     .await
                     .ok();
-
-                return bigtable_block;
             }
         }
+        if let Some(block) = block {
+            self.evm_rpc_cache().set_block_id_by_hash(hash, block);
+        }
         block
     }
 
+    /// Returns block with hash, if it is in canonical chain.
+    pub async fn get_evm_block_by_hash(
+        &self,
+        hash: evm_state::H256,
+    ) -> Option<(evm_state::Block, bool)> {
+        let block = self.get_evm_block_id_by_hash(hash).await?;
+        self.get_evm_block_by_id(block)
+            .await
+            // check that found block only in valid fork.
+            .filter(|(block, _)| block.header.hash() == hash)
+    }
+
+    /// Looks up create transaction of contract, in index of local blockstore.
+    pub fn get_evm_contract_creation(
+        &self,
+        address: evm_state::Address,
+    ) -> Option<EvmContractCreation> {
+        self.blockstore
+            .read_evm_contract_creation(address)
+            .map_err(|e| warn!("Unable to read creation of contract {:?}: {:?}", address, e))
+            .unwrap_or_default()
+    }
+
     fn get_velas_accounts_by_storage_key(
         &self,
         bank: &Arc<Bank>,
@@ -4442,6 +4651,7 @@ pub mod tests {
             Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
             max_complete_transaction_status_slot,
             None,
+            Arc::default(),
         );
         SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
 
@@ -6022,6 +6232,7 @@ pub mod tests {
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
             None,
+            Arc::default(),
         );
         SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
 
@@ -6299,6 +6510,7 @@ pub mod tests {
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
             None,
+            Arc::default(),
         );
         SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
         assert_eq!(
@@ -7725,6 +7937,7 @@ pub mod tests {
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
             None,
+            Arc::default(),
         );
 
         let mut io = MetaIoHandler::default();
@@ -7866,4 +8079,300 @@ pub mod tests {
             expect58
         );
     }
+
+    fn write_evm_block_with_logs(
+        blockstore: &Blockstore,
+        block_number: u64,
+        topics: &[evm_state::H256],
+    ) {
+        let logs = topics
+            .iter()
+            .map(|topic| evm_state::Log {
+                address: evm_state::H160::repeat_byte(block_number as u8),
+                topics: vec![*topic, evm_state::H256::zero()],
+                data: vec![],
+            })
+            .collect();
+        let transaction = evm_state::UnsignedTransaction {
+            nonce: block_number.into(),
+            gas_price: 0.into(),
+            gas_limit: 0.into(),
+            action: evm_state::TransactionAction::Create,
+            value: 0.into(),
+            input: vec![],
+        };
+        let receipt = evm_state::TransactionReceipt::new(
+            evm_state::TransactionInReceipt::Unsigned(evm_state::UnsignedTransactionWithCaller {
+                unsigned_tx: transaction,
+                caller: evm_state::H160::zero(),
+                chain_id: 0,
+                signed_compatible: true,
+            }),
+            0,
+            block_number,
+            0,
+            logs,
+            (evm_state::ExitSucceed::Stopped.into(), vec![]),
+        );
+        let txs = vec![(evm_state::H256::repeat_byte(block_number as u8), receipt)];
+        let header = evm_state::BlockHeader::new(
+            evm_state::H256::zero(),
+            0,
+            evm_state::H256::zero(),
+            block_number,
+            0,
+            0,
+            block_number,
+            evm_state::H256::zero(),
+            txs.iter(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        blockstore.write_evm_block_header(&header).unwrap();
+        for (hash, receipt) in txs {
+            blockstore
+                .write_evm_transaction(block_number, block_number, hash, receipt)
+                .unwrap();
+        }
+        blockstore.set_roots(&[block_number]).unwrap();
+    }
+
+    #[test]
+    fn test_evm_topic0_index_matches_scan() {
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+        meta.config.evm_topic0_index = true;
+        meta.evm_rpc_cache = Arc::new(Mutex::new(EvmRpcCache::new(
+            crate::evm_rpc_impl::BLOCK_HASH_CACHE_SIZE,
+            true,
+        )));
+
+        let topic = evm_state::H256::repeat_byte(0xaa);
+        let other_topic = evm_state::H256::repeat_byte(0xbb);
+        for block_number in 1..=6 {
+            let topics = match block_number {
+                2 | 5 => vec![topic, other_topic],
+                3 => vec![other_topic],
+                _ => vec![],
+            };
+            write_evm_block_with_logs(&meta.blockstore, block_number, &topics);
+        }
+        let record_blocks = |from_block, to_block| {
+            for block_number in from_block..=to_block {
+                let (block, _) = meta.blockstore.get_evm_block(block_number).unwrap();
+                meta.evm_rpc_cache().index_block_topic0(&block);
+            }
+        };
+
+        let filter = |from_block, to_block| evm_state::LogFilter {
+            from_block,
+            to_block,
+            address: vec![],
+            topics: vec![evm_state::LogFilterTopicEntry::One(topic)],
+        };
+        let summary = |logs: Vec<evm_state::LogWithLocation>| {
+            logs.into_iter()
+                .map(|log| {
+                    (
+                        log.block_num,
+                        log.transaction_hash,
+                        log.log_index,
+                        log.topics,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let scanned = summary(runtime.block_on(meta.filter_logs(filter(1, 6))).unwrap());
+
+        // index is filled only by recorded blocks, not by scans
+        assert_eq!(meta.evm_rpc_cache().topic0_blocks(&topic, 1, 6), None);
+        record_blocks(1, 6);
+        assert_eq!(
+            meta.evm_rpc_cache().topic0_blocks(&topic, 1, 6),
+            Some(vec![2, 5])
+        );
+        let indexed = summary(runtime.block_on(meta.filter_logs(filter(1, 6))).unwrap());
+        assert_eq!(scanned.len(), 2);
+        assert_eq!(scanned, indexed);
+
+        // subrange is served from index too
+        let indexed = summary(runtime.block_on(meta.filter_logs(filter(3, 6))).unwrap());
+        assert_eq!(indexed, scanned[1..].to_vec());
+    }
+
+    #[test]
+    fn test_evm_state_around_selfdestruct() {
+        use crate::evm_rpc_impl::ChainErpcImpl;
+        use evm_rpc::{chain::ChainERPC, BlockId, BlockRelId, Hex, RPCTransaction};
+        use evm_state::{AccountProvider, AccountState, H160, H256, U256};
+
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+        meta.evm_state_archive = match &*bank.evm_state.read().unwrap() {
+            evm_state::EvmState::Incomming(i) => Some(i.kvs().clone()),
+            evm_state::EvmState::Committed(c) => Some(c.kvs().clone()),
+        };
+
+        // returns value of storage slot 0
+        let code = vec![
+            0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+        ];
+        let contract = H160::repeat_byte(0x22);
+        let account = AccountState {
+            nonce: 1.into(),
+            code: code.clone().into(),
+            ..Default::default()
+        };
+
+        // deploy contract, change its storage, and selfdestruct it in consequent blocks
+        let slot_values = vec![Some(0x2a), Some(0x2b), None];
+        let mut state = meta.evm_state_archive(None).unwrap();
+        let mut headers = vec![];
+        for (slot, value) in (1..).zip(&slot_values) {
+            match value {
+                Some(value) => {
+                    state.set_account_state(contract, account.clone());
+                    state.ext_storage(
+                        contract,
+                        Some((H256::zero(), H256::from_low_u64_be(*value))),
+                    );
+                }
+                None => state.remove_account(contract),
+            }
+            let committed = state.commit_block(slot, H256::zero());
+            meta.blockstore
+                .write_evm_block_header(&committed.state.block)
+                .unwrap();
+            meta.blockstore.set_roots(&[slot]).unwrap();
+            headers.push(committed.state.block.clone());
+            state = committed.next_incomming(0);
+        }
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let tx: RPCTransaction = serde_json::from_value(json!({ "to": Hex(contract) })).unwrap();
+        let state_at = |block: BlockId| {
+            let code = runtime
+                .block_on(ChainErpcImpl.code(meta.clone(), Hex(contract), Some(block.clone())))
+                .unwrap();
+            let storage = runtime
+                .block_on(ChainErpcImpl.storage_at(
+                    meta.clone(),
+                    Hex(contract),
+                    Hex(U256::zero()),
+                    Some(block.clone()),
+                ))
+                .unwrap();
+            let output = runtime
+                .block_on(ChainErpcImpl.call(meta.clone(), tx.clone(), Some(block), None, None))
+                .unwrap();
+            (code.0, storage.0, output.0)
+        };
+        for (header, value) in headers.iter().zip(&slot_values) {
+            let (block_code, storage, output) = state_at(BlockId::Num(Hex(header.block_number)));
+            match value {
+                Some(value) => {
+                    let value = H256::from_low_u64_be(*value);
+                    assert_eq!(block_code, code);
+                    assert_eq!(storage, value);
+                    assert_eq!(output, value.as_bytes());
+                }
+                None => {
+                    assert!(block_code.is_empty());
+                    assert_eq!(storage, H256::zero());
+                    assert!(output.is_empty());
+                }
+            }
+        }
+
+        // contract is selfdestructed in current block of bank, but finalized block still has it
+        let finalized_block = headers.last().unwrap().block_number + 1;
+        let finalized_root = match &mut *bank.evm_state.write().unwrap() {
+            evm_state::EvmState::Incomming(i) => {
+                i.state.block_number = finalized_block + 1;
+                i.set_initial(vec![(
+                    contract,
+                    evm_state::MemoryAccount {
+                        nonce: 1.into(),
+                        code: code.clone(),
+                        storage: vec![(H256::zero(), H256::from_low_u64_be(0x2c))]
+                            .into_iter()
+                            .collect(),
+                        ..Default::default()
+                    },
+                )]);
+                let root = i.last_root();
+                i.remove_account(contract);
+                root
+            }
+            _ => panic!("Not expected state"),
+        };
+        let header = evm_state::BlockHeader::new(
+            H256::zero(),
+            0,
+            finalized_root,
+            finalized_block,
+            0,
+            0,
+            4,
+            H256::zero(),
+            std::iter::empty(),
+            evm_state::BlockVersion::VersionConsistentHashes,
+        );
+        meta.blockstore.write_evm_block_header(&header).unwrap();
+        meta.blockstore.set_roots(&[4]).unwrap();
+
+        let (block_code, storage, output) = state_at(BlockId::RelativeId(BlockRelId::Finalized));
+        let value = H256::from_low_u64_be(0x2c);
+        assert_eq!(block_code, code);
+        assert_eq!(storage, value);
+        assert_eq!(output, value.as_bytes());
+    }
+
+    #[test]
+    fn test_evm_max_logs_blocks() {
+        use crate::evm_rpc_impl::{ChainErpcImpl, VelasErpcImpl};
+        use evm_rpc::{chain::ChainERPC, velas::VelasERPC, RPCLogFilter};
+
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+        assert_eq!(
+            meta.evm_max_logs_blocks(),
+            crate::evm_rpc_impl::MAX_NUM_BLOCKS
+        );
+        meta.config.evm_max_logs_blocks = Some(3);
+        let topic = evm_state::H256::repeat_byte(0xaa);
+        for block_number in 1..=6 {
+            write_evm_block_with_logs(&meta.blockstore, block_number, &[topic]);
+        }
+
+        let filter = |from_block: u64, to_block: u64| -> RPCLogFilter {
+            serde_json::from_value(json!({
+                "fromBlock": format!("{:#x}", from_block),
+                "toBlock": format!("{:#x}", to_block),
+            }))
+            .unwrap()
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let logs = runtime
+            .block_on(ChainErpcImpl.logs(meta.clone(), filter(1, 4)))
+            .unwrap();
+        assert_eq!(logs.len(), 4);
+        match runtime.block_on(ChainErpcImpl.logs(meta.clone(), filter(1, 5))) {
+            Err(evm_rpc::Error::InvalidBlocksRange {
+                starting,
+                ending,
+                batch_size,
+            }) => assert_eq!((starting, ending, batch_size), (1, 5, Some(3))),
+            result => panic!("Expected InvalidBlocksRange, got {:?}", result),
+        }
+
+        let count = runtime
+            .block_on(VelasErpcImpl::default().logs_count(meta, filter(1, 6)))
+            .unwrap();
+        assert_eq!(count.count.0, 4);
+        assert!(count.capped);
+    }
 }
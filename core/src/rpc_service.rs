@@ -3,6 +3,7 @@
 use crate::{
     bigtable_upload_service::BigTableUploadService,
     cluster_info::ClusterInfo,
+    evm_rpc_impl::EvmRpcCache,
     max_slots::MaxSlots,
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
     poh_recorder::PohRecorder,
@@ -281,6 +282,7 @@ impl JsonRpcService {
         leader_schedule_cache: Arc<LeaderScheduleCache>,
         current_transaction_status_slot: Arc<AtomicU64>,
         evm_state_archive: Option<evm_state::Storage>,
+        evm_rpc_cache: Arc<Mutex<EvmRpcCache>>,
         jaeger_collector_url: Option<String>,
     ) -> Self {
         info!("rpc bound to {:?}", rpc_addr);
@@ -371,6 +373,7 @@ impl JsonRpcService {
             leader_schedule_cache,
             current_transaction_status_slot,
             evm_state_archive,
+            evm_rpc_cache,
         );
 
         let leader_info =
@@ -429,7 +432,9 @@ impl JsonRpcService {
 
                 io.extend_with(super::evm_rpc_impl::ChainErpcImpl.to_delegate());
                 io.extend_with(super::evm_rpc_impl::GeneralErpcImpl.to_delegate());
-                io.extend_with(super::evm_rpc_impl::TraceErpcImpl.to_delegate());
+                let trace_impl = super::evm_rpc_impl::TraceErpcImpl::default();
+                io.extend_with(trace_impl.clone().to_delegate());
+                io.extend_with(super::evm_rpc_impl::VelasErpcImpl::new(trace_impl).to_delegate());
 
                 let request_middleware = RpcRequestMiddleware::new(
                     ledger_path,
@@ -552,6 +557,7 @@ mod tests {
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
             None,
+            Arc::default(),
             None,
         );
         let thread = rpc_service.thread_hdl.thread();
@@ -11,6 +11,7 @@ use crate::{
     completed_data_sets_service::CompletedDataSetsService,
     consensus::{reconcile_blockstore_roots_with_tower, Tower},
     contact_info::ContactInfo,
+    evm_rpc_impl::{EvmRpcCache, BLOCK_HASH_CACHE_SIZE},
     evm_services::{
         EvmRecorderSender, EvmRecorderService, EvmStateRecorderSender, EvmStateRecorderService,
     },
@@ -268,6 +269,7 @@ struct TransactionHistoryServices {
     evm_block_recorder_service: Option<EvmRecorderService>,
     evm_state_recorder_sender: Option<EvmStateRecorderSender>,
     evm_state_recorder_service: Option<EvmStateRecorderService>,
+    evm_rpc_cache: Arc<Mutex<EvmRpcCache>>,
 }
 
 pub struct Validator {
@@ -413,6 +415,7 @@ impl Validator {
                 evm_block_recorder_service,
                 evm_state_recorder_sender,
                 evm_state_recorder_service,
+                evm_rpc_cache,
             },
             tower,
         ) = new_banks_from_ledger(
@@ -575,6 +578,7 @@ impl Validator {
                     leader_schedule_cache.clone(),
                     max_complete_transaction_status_slot,
                     evm_state_archive,
+                    evm_rpc_cache,
                     config.jaeger_collector_url.clone(),
                 )),
                 if config.rpc_config.minimal_api {
@@ -1159,6 +1163,13 @@ fn new_banks_from_ledger(
         ..blockstore_processor::ProcessOptions::default()
     };
 
+    let evm_rpc_cache = Arc::new(Mutex::new(EvmRpcCache::new(
+        config
+            .rpc_config
+            .evm_block_hash_cache_size
+            .unwrap_or(BLOCK_HASH_CACHE_SIZE),
+        config.rpc_config.evm_topic0_index,
+    )));
     let transaction_history_services =
         if config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history {
             initialize_rpc_transaction_history_services(
@@ -1166,9 +1177,13 @@ fn new_banks_from_ledger(
                 exit,
                 config.rpc_config.enable_cpi_and_log_storage,
                 evm_archive.clone(),
+                evm_rpc_cache,
             )
         } else {
-            TransactionHistoryServices::default()
+            TransactionHistoryServices {
+                evm_rpc_cache,
+                ..TransactionHistoryServices::default()
+            }
         };
 
     // TODO: Add evm-state to config.
@@ -1372,6 +1387,7 @@ fn initialize_rpc_transaction_history_services(
     exit: &Arc<AtomicBool>,
     enable_cpi_and_log_storage: bool,
     archive_evm_state: Option<evm_state::Storage>,
+    evm_rpc_cache: Arc<Mutex<EvmRpcCache>>,
 ) -> TransactionHistoryServices {
     let max_complete_transaction_status_slot = Arc::new(AtomicU64::new(blockstore.max_root()));
     let (transaction_status_sender, transaction_status_receiver) = unbounded();
@@ -1407,6 +1423,7 @@ fn initialize_rpc_transaction_history_services(
     let evm_block_recorder_service = Some(EvmRecorderService::new(
         evm_block_recorder_receiver,
         blockstore,
+        evm_rpc_cache.clone(),
         exit,
     ));
 
@@ -1438,6 +1455,7 @@ fn initialize_rpc_transaction_history_services(
         evm_block_recorder_service,
         evm_state_recorder_sender,
         evm_state_recorder_service,
+        evm_rpc_cache,
     }
 }
 
@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use jsonrpc_core::{
+    futures::future::{Either, FutureExt, Shared},
+    middleware, BoxFuture, Call, Id, Metadata, Middleware, Output, Version,
+};
+use solana_metrics::datapoint_info;
+
+/// Idempotent methods, identical concurrent calls of which share one upstream request.
+/// Methods that change state (like `eth_sendRawTransaction`) are never coalesced.
+const COALESCED_METHODS: &[&str] = &[
+    "net_version",
+    "eth_chainId",
+    "eth_blockNumber",
+    "eth_gasPrice",
+    "eth_getBalance",
+    "eth_getStorageAt",
+    "eth_getTransactionCount",
+    "eth_getCode",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getTransactionByHash",
+    "eth_getTransactionReceipt",
+    "eth_getLogs",
+    "eth_call",
+    "eth_estimateGas",
+];
+
+type SharedResponse = Shared<BoxFuture<Option<Output>>>;
+
+/// Lets identical in-flight calls of idempotent methods wait for response of the first one,
+/// instead of sending the same request to upstream node again.
+#[derive(Clone, Default)]
+pub struct CoalescedReads {
+    enabled: bool,
+    in_flight: Arc<Mutex<HashMap<(String, String), SharedResponse>>>,
+}
+
+impl CoalescedReads {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            in_flight: Default::default(),
+        }
+    }
+}
+
+/// Addresses shared response to the caller.
+fn readdress(output: Option<Output>, jsonrpc: Option<Version>, id: Id) -> Option<Output> {
+    output.map(|output| match output {
+        Output::Success(mut success) => {
+            success.jsonrpc = jsonrpc;
+            success.id = id;
+            Output::Success(success)
+        }
+        Output::Failure(mut failure) => {
+            failure.jsonrpc = jsonrpc;
+            failure.id = id;
+            Output::Failure(failure)
+        }
+    })
+}
+
+impl<M: Metadata> Middleware<M> for CoalescedReads {
+    type Future = middleware::NoopFuture;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, M) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let (key, jsonrpc, id) = match &call {
+            Call::MethodCall(method_call)
+                if self.enabled && COALESCED_METHODS.contains(&method_call.method.as_str()) =>
+            {
+                let params = serde_json::to_string(&method_call.params).unwrap_or_default();
+                (
+                    (method_call.method.clone(), params),
+                    method_call.jsonrpc,
+                    method_call.id.clone(),
+                )
+            }
+            _ => return Either::Right(next(call, meta)),
+        };
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let response = match in_flight.get(&key) {
+            Some(response) => {
+                datapoint_info!("evm_bridge-coalesced_read", ("method", key.0, String));
+                response.clone()
+            }
+            None => {
+                let response = next(call, meta);
+                let coalesced_reads = self.clone();
+                let finished_key = key.clone();
+                let response: BoxFuture<Option<Output>> = Box::pin(async move {
+                    let output = response.await;
+                    // later calls should get fresh response
+                    coalesced_reads
+                        .in_flight
+                        .lock()
+                        .unwrap()
+                        .remove(&finished_key);
+                    output
+                });
+                let response = response.shared();
+                in_flight.insert(key, response.clone());
+                response
+            }
+        };
+        drop(in_flight);
+        Either::Left(Box::pin(
+            response.map(move |output| readdress(output, jsonrpc, id)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{futures::future::join_all, MetaIoHandler, Value};
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    const CONCURRENT_CALLS: usize = 10;
+
+    fn io(enabled: bool, upstream_calls: Arc<AtomicUsize>) -> MetaIoHandler<(), CoalescedReads> {
+        let mut io = MetaIoHandler::with_middleware(CoalescedReads::new(enabled));
+        for method in &["eth_getBalance", "eth_sendRawTransaction"] {
+            let upstream_calls = upstream_calls.clone();
+            io.add_method(method, move |_| {
+                let call = upstream_calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(Value::from(call))
+                }
+            });
+        }
+        io
+    }
+
+    async fn call_concurrently(io: &MetaIoHandler<(), CoalescedReads>, method: &str) -> Vec<Value> {
+        let requests: Vec<_> = (0..CONCURRENT_CALLS)
+            .map(|id| {
+                format!(
+                    r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":["0x01","latest"]}}"#,
+                    id, method
+                )
+            })
+            .collect();
+        let responses = join_all(
+            requests
+                .iter()
+                .map(|request| io.handle_request(request, ())),
+        )
+        .await;
+        responses
+            .into_iter()
+            .map(|response| serde_json::from_str(&response.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_identical_reads_coalesced() {
+        let upstream_calls = Arc::new(AtomicUsize::new(0));
+        let io = io(true, upstream_calls.clone());
+
+        let responses = call_concurrently(&io, "eth_getBalance").await;
+        assert_eq!(upstream_calls.load(Ordering::SeqCst), 1);
+        for (id, response) in responses.iter().enumerate() {
+            assert_eq!(response["id"], id);
+            assert_eq!(response["result"], 0);
+        }
+
+        // finished calls are not reused
+        call_concurrently(&io, "eth_getBalance").await;
+        assert_eq!(upstream_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sends_not_coalesced() {
+        let upstream_calls = Arc::new(AtomicUsize::new(0));
+        let io = io(true, upstream_calls.clone());
+        call_concurrently(&io, "eth_sendRawTransaction").await;
+        assert_eq!(upstream_calls.load(Ordering::SeqCst), CONCURRENT_CALLS);
+    }
+
+    #[tokio::test]
+    async fn test_coalescing_disabled() {
+        let upstream_calls = Arc::new(AtomicUsize::new(0));
+        let io = io(false, upstream_calls.clone());
+        call_concurrently(&io, "eth_getBalance").await;
+        assert_eq!(upstream_calls.load(Ordering::SeqCst), CONCURRENT_CALLS);
+    }
+}
@@ -0,0 +1,356 @@
+use std::io::Write;
+
+use flate2::{
+    write::{DeflateEncoder, GzEncoder},
+    Compression,
+};
+use jsonrpc_core::{middleware, MetaIoHandler, Metadata, Middleware};
+use jsonrpc_http_server::{hyper, RequestMiddleware, RequestMiddlewareAction};
+use log::*;
+
+use crate::stale;
+
+/// Maximum size of request body, same as default limit of `jsonrpc_http_server`.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// Selects encoding from `Accept-Encoding` header, gzip is preferred.
+    fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let accepted: Vec<&str> = accept_encoding
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';').map(str::trim);
+                let name = parts.next()?;
+                let disabled = parts.any(|param| {
+                    let param = param.replace(' ', "");
+                    let quality = param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok());
+                    quality == Some(0.0)
+                });
+                (!disabled).then(|| name)
+            })
+            .collect();
+        if accepted.contains(&"gzip") {
+            Some(Encoding::Gzip)
+        } else if accepted.contains(&"deflate") {
+            Some(Encoding::Deflate)
+        } else {
+            None
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Encoding::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Handles POST requests of clients, that accept gzip or deflate encoding,
+/// and compresses responses bigger than `min_size`.
+/// If stale header is enabled, all POST requests are handled, to report stale responses in headers.
+/// Other requests are passed to the server unchanged.
+/// Handled requests bypass body size limit of the server, so it is enforced by middleware.
+pub struct CompressionMiddleware<T: Metadata, S: Middleware<T> = middleware::Noop> {
+    io: MetaIoHandler<T, S>,
+    meta: T,
+    min_size: Option<usize>,
+    stale_header: bool,
+    max_body_size: usize,
+}
+
+impl<T: Metadata, S: Middleware<T>> CompressionMiddleware<T, S> {
+    pub fn new(io: MetaIoHandler<T, S>, meta: T, min_size: usize) -> Self {
+        Self {
+            io,
+            meta,
+            min_size: Some(min_size),
+            stale_header: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Creates middleware, that doesn't compress responses.
+    pub fn uncompressed(io: MetaIoHandler<T, S>, meta: T) -> Self {
+        Self {
+            io,
+            meta,
+            min_size: None,
+            stale_header: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Adds `X-Velas-Stale` header with age in seconds, to responses with values served by `StaleReads`.
+    pub fn with_stale_header(mut self) -> Self {
+        self.stale_header = true;
+        self
+    }
+
+    /// Rejects requests with body bigger than `max_body_size` bytes.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+}
+
+/// Reads request body, unless it is bigger than `max_size` bytes.
+async fn read_body(
+    mut body: hyper::Body,
+    max_size: usize,
+) -> Result<Option<Vec<u8>>, hyper::Error> {
+    use hyper::body::HttpBody;
+
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if data.len() + chunk.len() > max_size {
+            return Ok(None);
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(Some(data))
+}
+
+impl<T: Metadata, S: Middleware<T> + Clone> RequestMiddleware for CompressionMiddleware<T, S> {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        let encoding = request
+            .headers()
+            .get(hyper::header::ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Encoding::negotiate);
+        let (encoding, min_size) = match (encoding, self.min_size) {
+            (Some(encoding), Some(min_size)) => (Some(encoding), min_size),
+            _ => (None, usize::MAX),
+        };
+        if request.method() != hyper::Method::POST || (encoding.is_none() && !self.stale_header) {
+            return request.into();
+        }
+        let has_origin = request.headers().contains_key(hyper::header::ORIGIN);
+        let content_length = request
+            .headers()
+            .get(hyper::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+        let max_body_size = self.max_body_size;
+
+        let io = self.io.clone();
+        let meta = self.meta.clone();
+        RequestMiddlewareAction::Respond {
+            should_validate_hosts: true,
+            response: Box::pin(async move {
+                let body = match content_length {
+                    Some(length) if length > max_body_size => None,
+                    _ => read_body(request.into_body(), max_body_size).await?,
+                };
+                let body = match body {
+                    Some(body) => body,
+                    None => {
+                        let mut builder =
+                            hyper::Response::builder().status(hyper::StatusCode::PAYLOAD_TOO_LARGE);
+                        if has_origin {
+                            builder =
+                                builder.header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+                        }
+                        return Ok(builder
+                            .body(hyper::Body::from("Request body is too large"))
+                            .unwrap());
+                    }
+                };
+                let body = String::from_utf8_lossy(&body);
+                // batch requests are handled by io as well, so they are compressed as a whole
+                let (response, stale_age) = stale::track(io.handle_request(&body, meta)).await;
+                let response = response.unwrap_or_default();
+
+                let mut builder = hyper::Response::builder()
+                    .header(
+                        hyper::header::CONTENT_TYPE,
+                        "application/json; charset=utf-8",
+                    )
+                    .header(hyper::header::VARY, "accept-encoding");
+                if has_origin {
+                    builder = builder.header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+                }
+                if let Some(age) = stale_age {
+                    builder = builder.header(stale::STALE_HEADER, age.as_secs());
+                }
+
+                let body = match encoding {
+                    Some(encoding) if response.len() >= min_size => {
+                        match encoding.compress(response.as_bytes()) {
+                            Ok(compressed) => {
+                                builder = builder
+                                    .header(hyper::header::CONTENT_ENCODING, encoding.name());
+                                compressed
+                            }
+                            Err(e) => {
+                                warn!("Unable to compress response: {:?}", e);
+                                response.into_bytes()
+                            }
+                        }
+                    }
+                    _ => response.into_bytes(),
+                };
+                Ok(builder.body(hyper::Body::from(body)).unwrap())
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use jsonrpc_core::Value;
+    use std::io::Read;
+
+    fn middleware() -> CompressionMiddleware<()> {
+        let mut io = MetaIoHandler::default();
+        io.add_method("big", |_| async { Ok(Value::String("a".repeat(4096))) });
+        io.add_method("small", |_| async { Ok(Value::String("ok".to_string())) });
+        CompressionMiddleware::new(io, (), 1024)
+    }
+
+    fn request(body: &str, accept_encoding: Option<&str>) -> hyper::Request<hyper::Body> {
+        let mut builder = hyper::Request::builder().method(hyper::Method::POST);
+        if let Some(accept_encoding) = accept_encoding {
+            builder = builder.header(hyper::header::ACCEPT_ENCODING, accept_encoding);
+        }
+        builder.body(hyper::Body::from(body.to_string())).unwrap()
+    }
+
+    async fn respond(
+        middleware: &CompressionMiddleware<()>,
+        request: hyper::Request<hyper::Body>,
+    ) -> (Option<String>, Vec<u8>) {
+        let response = response(middleware, request).await;
+        let encoding = response
+            .headers()
+            .get(hyper::header::CONTENT_ENCODING)
+            .map(|value| value.to_str().unwrap().to_string());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (encoding, body.to_vec())
+    }
+
+    async fn response(
+        middleware: &CompressionMiddleware<()>,
+        request: hyper::Request<hyper::Body>,
+    ) -> hyper::Response<hyper::Body> {
+        match middleware.on_request(request) {
+            RequestMiddlewareAction::Respond { response, .. } => response.await.unwrap(),
+            RequestMiddlewareAction::Proceed { .. } => panic!("request should be handled"),
+        }
+    }
+
+    fn gunzip(data: &[u8]) -> Value {
+        let mut decoded = String::new();
+        GzDecoder::new(data).read_to_string(&mut decoded).unwrap();
+        serde_json::from_str(&decoded).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_large_response_gzipped() {
+        let middleware = middleware();
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"big"}"#;
+        let (encoding, data) = respond(&middleware, request(body, Some("deflate, gzip"))).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert_eq!(gunzip(&data)["result"], Value::String("a".repeat(4096)));
+    }
+
+    #[tokio::test]
+    async fn test_batch_response_gzipped() {
+        let middleware = middleware();
+        let body = r#"[{"jsonrpc":"2.0","id":1,"method":"big"},{"jsonrpc":"2.0","id":2,"method":"small"}]"#;
+        let (encoding, data) = respond(&middleware, request(body, Some("gzip"))).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        let batch = gunzip(&data);
+        assert_eq!(batch.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_small_response_not_compressed() {
+        let middleware = middleware();
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"small"}"#;
+        let (encoding, data) = respond(&middleware, request(body, Some("gzip"))).await;
+        assert_eq!(encoding, None);
+        let response: Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(response["result"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_too_large_body_rejected() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"small"}"#;
+        let limited = middleware().with_max_body_size(32);
+        let rejected = response(&limited, request(body, Some("gzip"))).await;
+        assert_eq!(rejected.status(), hyper::StatusCode::PAYLOAD_TOO_LARGE);
+
+        // declared length is checked before reading
+        let mut declared = request(body, Some("gzip"));
+        declared.headers_mut().insert(
+            hyper::header::CONTENT_LENGTH,
+            hyper::header::HeaderValue::from(1024 * 1024),
+        );
+        let rejected = response(&limited, declared).await;
+        assert_eq!(rejected.status(), hyper::StatusCode::PAYLOAD_TOO_LARGE);
+
+        let exact = middleware().with_max_body_size(body.len());
+        let (_, data) = respond(&exact, request(body, Some("gzip"))).await;
+        let response: Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(response["result"], "ok");
+    }
+
+    #[test]
+    fn test_passthrough_without_accept_encoding() {
+        let middleware = middleware();
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"big"}"#;
+        assert!(matches!(
+            middleware.on_request(request(body, None)),
+            RequestMiddlewareAction::Proceed { .. }
+        ));
+        assert!(matches!(
+            middleware.on_request(request(body, Some("br, gzip;q=0"))),
+            RequestMiddlewareAction::Proceed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Encoding::negotiate("gzip"), Some(Encoding::Gzip));
+        assert_eq!(
+            Encoding::negotiate("deflate, gzip;q=0.5"),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(Encoding::negotiate("deflate"), Some(Encoding::Deflate));
+        assert_eq!(
+            Encoding::negotiate("gzip; q=0, deflate"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(Encoding::negotiate("gzip;q=0.0, deflate;q=0.000"), None);
+        assert_eq!(
+            Encoding::negotiate("gzip;q=0.001, deflate"),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(Encoding::negotiate("identity"), None);
+    }
+}
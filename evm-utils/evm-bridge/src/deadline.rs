@@ -0,0 +1,165 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use jsonrpc_core::{
+    futures::future::Either, middleware, BoxFuture, Call, Metadata, MethodCall, Middleware, Output,
+};
+use solana_metrics::datapoint_info;
+
+tokio::task_local! {
+    /// Time in microseconds spent waiting for upstream node, by current call.
+    static UPSTREAM_TIME: Arc<AtomicU64>;
+}
+
+/// Runs request to upstream node, and attributes its time to the upstream phase of current call.
+pub fn upstream<T>(request: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = request();
+    let elapsed = started.elapsed().as_micros() as u64;
+    // calls outside of middleware (workers, self test) are not tracked
+    let _ = UPSTREAM_TIME.try_with(|time| time.fetch_add(elapsed, Ordering::Relaxed));
+    result
+}
+
+/// Enforces deadline of each call, and records time spent in upstream and local phases.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestDeadline {
+    deadline: Option<Duration>,
+}
+
+impl RequestDeadline {
+    pub fn new(deadline: Option<Duration>) -> Self {
+        Self { deadline }
+    }
+}
+
+impl<M: Metadata> Middleware<M> for RequestDeadline {
+    type Future = middleware::NoopFuture;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, M) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let (deadline, method_call) = match (self.deadline, &call) {
+            (Some(deadline), Call::MethodCall(method_call)) => (deadline, method_call.clone()),
+            _ => return Either::Right(next(call, meta)),
+        };
+        let started = Instant::now();
+        let upstream_time = Arc::new(AtomicU64::new(0));
+        // handlers may call upstream synchronously, before returning future
+        let response = UPSTREAM_TIME.sync_scope(upstream_time.clone(), || next(call, meta));
+        Either::Left(Box::pin(UPSTREAM_TIME.scope(
+            upstream_time.clone(),
+            with_deadline(response, method_call, deadline, started, upstream_time),
+        )))
+    }
+}
+
+async fn with_deadline<X>(
+    response: X,
+    method_call: MethodCall,
+    deadline: Duration,
+    started: Instant,
+    upstream_time: Arc<AtomicU64>,
+) -> Option<Output>
+where
+    X: Future<Output = Option<Output>>,
+{
+    // blocking upstream requests can't be interrupted, so deadline is also checked after completion
+    let remaining = deadline.saturating_sub(started.elapsed());
+    let output = tokio::time::timeout(remaining, response).await.ok();
+    let total = started.elapsed();
+    let upstream = Duration::from_micros(upstream_time.load(Ordering::Relaxed)).min(total);
+    let local = total - upstream;
+    datapoint_info!(
+        "evm_bridge-request_phases",
+        ("method", method_call.method, String),
+        ("upstream_us", upstream.as_micros() as i64, i64),
+        ("local_us", local.as_micros() as i64, i64)
+    );
+    match output {
+        Some(output) if total <= deadline => output,
+        _ => {
+            let phase = if upstream >= local {
+                "upstream"
+            } else {
+                "local"
+            };
+            let error = evm_rpc::Error::DeadlineExceeded {
+                deadline_ms: deadline.as_millis() as u64,
+                upstream_ms: upstream.as_millis() as u64,
+                local_ms: local.as_millis() as u64,
+                phase: phase.to_string(),
+            };
+            Some(Output::from(
+                Err(error.into()),
+                method_call.id,
+                method_call.jsonrpc,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{MetaIoHandler, Value};
+    use std::thread::sleep;
+
+    const DEADLINE: Duration = Duration::from_millis(50);
+
+    fn io() -> MetaIoHandler<(), RequestDeadline> {
+        let mut io = MetaIoHandler::with_middleware(RequestDeadline::new(Some(DEADLINE)));
+        io.add_method("fast", |_| async { Ok(Value::from(1)) });
+        io.add_method("slow_upstream", |_| async {
+            upstream(|| sleep(DEADLINE * 2));
+            Ok(Value::from(1))
+        });
+        io.add_method("slow_local", |_| async {
+            sleep(DEADLINE * 2);
+            Ok(Value::from(1))
+        });
+        io
+    }
+
+    async fn call(method: &str) -> Value {
+        let request = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{}"}}"#, method);
+        let response = io().handle_request(&request, ()).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fast_call_within_deadline() {
+        let response = call("fast").await;
+        assert_eq!(response["result"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_upstream_exceeds_deadline() {
+        let response = call("slow_upstream").await;
+        assert_eq!(response["error"]["code"], 2010);
+        let message = response["error"]["message"].as_str().unwrap();
+        assert!(message.ends_with("mostly spent in upstream"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_slow_local_exceeds_deadline() {
+        let response = call("slow_local").await;
+        assert_eq!(response["error"]["code"], 2010);
+        let message = response["error"]["message"].as_str().unwrap();
+        assert!(message.ends_with("mostly spent in local"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_outside_of_call() {
+        assert_eq!(upstream(|| 5), 5);
+    }
+}
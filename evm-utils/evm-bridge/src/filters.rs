@@ -0,0 +1,359 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use evm_rpc::{
+    chain::ChainERPC, filters::FilterERPC, BlockId, BlockRelId, Either, Error, Hex, RPCLog,
+    RPCLogFilter,
+};
+use evm_state::H256;
+use jsonrpc_core::BoxFuture;
+use log::*;
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use crate::{logs_in_range, pubsub::block_header, ChainErpcProxy, EvmBridge, EvmResult};
+
+/// Filters, that weren't polled for this time, are removed.
+const DEFAULT_FILTER_TTL: Duration = Duration::from_secs(5 * 60);
+/// Default maximum number of installed filters.
+pub const DEFAULT_MAX_FILTERS: usize = 10_000;
+/// Maximum number of block hashes returned by one poll, if filter is lagging behind the chain.
+const MAX_BLOCKS_PER_POLL: u64 = 64;
+
+enum FilterKind {
+    /// Logs matching filter, from blocks after `last_block`.
+    Logs {
+        filter: RPCLogFilter,
+        last_block: u64,
+    },
+    /// Hashes of blocks after `last_block`.
+    Blocks { last_block: u64 },
+    /// Hashes of transactions, imported into the pool since previous poll.
+    PendingTransactions(broadcast::Receiver<H256>),
+}
+
+struct Filter {
+    kind: FilterKind,
+    last_poll: Instant,
+}
+
+/// What should be requested from upstream to get changes of filter.
+enum Poll {
+    Hashes(Vec<Hex<H256>>),
+    Blocks(u64),
+    Logs(RPCLogFilter, u64),
+}
+
+/// Installed filters of poll-based filter API, by id.
+pub struct Filters {
+    ttl: Duration,
+    max_filters: usize,
+    active: Mutex<HashMap<u64, Filter>>,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILTER_TTL, DEFAULT_MAX_FILTERS)
+    }
+}
+
+impl Filters {
+    pub fn new(ttl: Duration, max_filters: usize) -> Self {
+        Self {
+            ttl,
+            max_filters,
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn add(&self, kind: FilterKind) -> EvmResult<Hex<u64>> {
+        let mut active = self.active.lock().unwrap();
+        self.remove_expired(&mut active);
+        if active.len() >= self.max_filters {
+            return Err(Error::TooManyFilters {
+                max: self.max_filters,
+            });
+        }
+
+        // ids are random, so filters of other clients can't be guessed
+        let id = loop {
+            let id = rand::random::<u64>();
+            if !active.contains_key(&id) {
+                break id;
+            }
+        };
+        active.insert(
+            id,
+            Filter {
+                kind,
+                last_poll: Instant::now(),
+            },
+        );
+        Ok(Hex(id))
+    }
+
+    fn remove(&self, id: u64) -> bool {
+        self.active.lock().unwrap().remove(&id).is_some()
+    }
+
+    /// Calls `f` with filter, and resets its idle time.
+    fn poll<F, T>(&self, id: u64, f: F) -> EvmResult<T>
+    where
+        F: FnOnce(&mut FilterKind) -> T,
+    {
+        let mut active = self.active.lock().unwrap();
+        self.remove_expired(&mut active);
+        let filter = active.get_mut(&id).ok_or(Error::FilterNotFound { id })?;
+        filter.last_poll = Instant::now();
+        Ok(f(&mut filter.kind))
+    }
+
+    /// Moves cursor of logs or blocks filter, if it wasn't moved further by concurrent poll.
+    fn advance(&self, id: u64, block: u64) {
+        if let Some(filter) = self.active.lock().unwrap().get_mut(&id) {
+            match &mut filter.kind {
+                FilterKind::Logs { last_block, .. } | FilterKind::Blocks { last_block } => {
+                    *last_block = block.max(*last_block)
+                }
+                FilterKind::PendingTransactions(_) => (),
+            }
+        }
+    }
+
+    fn remove_expired(&self, active: &mut HashMap<u64, Filter>) {
+        let ttl = self.ttl;
+        active.retain(|id, filter| {
+            let alive = filter.last_poll.elapsed() <= ttl;
+            if !alive {
+                debug!("Filter {:#x} expired", id);
+            }
+            alive
+        });
+    }
+}
+
+/// Takes hashes of transactions imported since previous poll.
+/// If filter is polled too rarely, the oldest hashes are skipped.
+fn drain_imported(imported: &mut broadcast::Receiver<H256>) -> Vec<Hex<H256>> {
+    let mut hashes = vec![];
+    loop {
+        match imported.try_recv() {
+            Ok(hash) => hashes.push(Hex(hash)),
+            Err(TryRecvError::Lagged(skipped)) => {
+                warn!("Pending transaction filter skipped {} hashes", skipped)
+            }
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return hashes,
+        }
+    }
+}
+
+fn latest_block(bridge: &EvmBridge) -> EvmResult<u64> {
+    bridge.block_to_number(Some(BlockId::RelativeId(BlockRelId::Latest)))
+}
+
+pub struct FilterErpcImpl;
+
+impl FilterERPC for FilterErpcImpl {
+    type Metadata = Arc<EvmBridge>;
+
+    fn new_filter(
+        &self,
+        meta: Self::Metadata,
+        log_filter: RPCLogFilter,
+    ) -> BoxFuture<EvmResult<Hex<u64>>> {
+        Box::pin(async move {
+            let last_block = latest_block(&meta)?;
+            meta.filters.add(FilterKind::Logs {
+                filter: log_filter,
+                last_block,
+            })
+        })
+    }
+
+    fn new_block_filter(&self, meta: Self::Metadata) -> BoxFuture<EvmResult<Hex<u64>>> {
+        Box::pin(async move {
+            let last_block = latest_block(&meta)?;
+            meta.filters.add(FilterKind::Blocks { last_block })
+        })
+    }
+
+    fn new_pending_transaction_filter(&self, meta: Self::Metadata) -> EvmResult<Hex<u64>> {
+        let imported = meta.pool.subscribe_imported();
+        meta.filters.add(FilterKind::PendingTransactions(imported))
+    }
+
+    fn filter_changes(
+        &self,
+        meta: Self::Metadata,
+        id: Hex<u64>,
+    ) -> BoxFuture<EvmResult<Either<Vec<Hex<H256>>, Vec<RPCLog>>>> {
+        Box::pin(async move {
+            let poll = meta.filters.poll(id.0, |kind| match kind {
+                FilterKind::Logs { filter, last_block } => Poll::Logs(filter.clone(), *last_block),
+                FilterKind::Blocks { last_block } => Poll::Blocks(*last_block),
+                FilterKind::PendingTransactions(imported) => Poll::Hashes(drain_imported(imported)),
+            })?;
+            match poll {
+                Poll::Hashes(hashes) => Ok(Either::Left(hashes)),
+                Poll::Blocks(last_block) => {
+                    let latest = latest_block(&meta)?;
+                    let lowest = latest.saturating_sub(MAX_BLOCKS_PER_POLL - 1);
+                    let mut hashes = vec![];
+                    for number in lowest.max(last_block + 1)..=latest {
+                        match block_header(&meta, number) {
+                            Some(header) => hashes.push(header.hash),
+                            None => break,
+                        }
+                        meta.filters.advance(id.0, number);
+                    }
+                    Ok(Either::Left(hashes))
+                }
+                Poll::Logs(filter, last_block) => {
+                    let latest = latest_block(&meta)?;
+                    let ending = match filter.to_block {
+                        Some(BlockId::Num(to_block)) => to_block.0.min(latest),
+                        _ => latest,
+                    };
+                    let starting = match filter.from_block {
+                        Some(BlockId::Num(from_block)) => from_block.0.max(last_block + 1),
+                        _ => last_block + 1,
+                    };
+                    if starting > ending {
+                        return Ok(Either::Right(vec![]));
+                    }
+                    let logs = logs_in_range(meta.clone(), filter, starting, ending).await?;
+                    meta.filters.advance(id.0, ending);
+                    Ok(Either::Right(logs))
+                }
+            }
+        })
+    }
+
+    fn filter_logs(&self, meta: Self::Metadata, id: Hex<u64>) -> BoxFuture<EvmResult<Vec<RPCLog>>> {
+        Box::pin(async move {
+            let filter = meta.filters.poll(id.0, |kind| match kind {
+                FilterKind::Logs { filter, .. } => Some(filter.clone()),
+                _ => None,
+            })?;
+            let filter = filter.ok_or_else(|| Error::InvalidParams {
+                details: format!("Filter {:#x} is not a logs filter", id.0),
+            })?;
+            ChainErpcProxy.logs(meta, filter).await
+        })
+    }
+
+    fn uninstall_filter(&self, meta: Self::Metadata, id: Hex<u64>) -> EvmResult<bool> {
+        Ok(meta.filters.remove(id.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::PooledTransaction;
+    use crate::tests::{mocked_bridge, signed_tx, test_log};
+    use evm_state::{Address, U256};
+    use serde_json::json;
+    use solana_client::{mock_sender::Mocks, rpc_client::RpcClient, rpc_request::RpcRequest};
+    use std::collections::HashSet;
+
+    /// Replaces upstream responses, installed filters are kept.
+    fn with_mocks(bridge: Arc<EvmBridge>, mocks: Mocks) -> Arc<EvmBridge> {
+        let mut bridge = Arc::try_unwrap(bridge).ok().unwrap();
+        bridge.rpc_client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        Arc::new(bridge)
+    }
+
+    fn latest_block_mocks(latest: u64) -> Mocks {
+        vec![(RpcRequest::EthBlockNumber, json!(Hex(latest)))]
+            .into_iter()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_log_filter_changes() {
+        let bridge = Arc::new(mocked_bridge(latest_block_mocks(7)));
+        let filter = RPCLogFilter {
+            from_block: None,
+            to_block: None,
+            address: Some(Either::Right(Hex(Address::repeat_byte(0x33)))),
+            topics: None,
+        };
+        let id = FilterErpcImpl
+            .new_filter(bridge.clone(), filter)
+            .await
+            .unwrap();
+
+        // matching log appears in the next block
+        let mut mocks = latest_block_mocks(8);
+        mocks.insert(RpcRequest::EthGetLogs, json!([test_log(8, 0)]));
+        let bridge = with_mocks(bridge, mocks);
+        let changes = FilterErpcImpl.filter_changes(bridge.clone(), id).await;
+        assert!(matches!(
+            changes,
+            Ok(Either::Right(logs)) if logs.len() == 1 && logs[0].block_number == Hex(U256::from(8))
+        ));
+
+        // no new blocks since previous poll
+        let bridge = with_mocks(bridge, latest_block_mocks(8));
+        let changes = FilterErpcImpl.filter_changes(bridge.clone(), id).await;
+        assert!(matches!(changes, Ok(Either::Right(logs)) if logs.is_empty()));
+
+        assert!(FilterErpcImpl.uninstall_filter(bridge.clone(), id).unwrap());
+        assert!(!FilterErpcImpl.uninstall_filter(bridge.clone(), id).unwrap());
+        assert!(matches!(
+            FilterErpcImpl.filter_changes(bridge, id).await,
+            Err(Error::FilterNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pending_transaction_filter_changes() {
+        let bridge = Arc::new(mocked_bridge(Mocks::default()));
+        let id = FilterErpcImpl
+            .new_pending_transaction_filter(bridge.clone())
+            .unwrap();
+
+        let tx = signed_tx(0);
+        let hash = tx.tx_id_hash();
+        let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+        let tx = PooledTransaction::new(tx, HashSet::new(), sender).unwrap();
+        bridge.pool.import(tx).unwrap();
+
+        let changes = FilterErpcImpl.filter_changes(bridge.clone(), id).await;
+        assert!(matches!(changes, Ok(Either::Left(hashes)) if hashes == vec![Hex(hash)]));
+        let changes = FilterErpcImpl.filter_changes(bridge, id).await;
+        assert!(matches!(changes, Ok(Either::Left(hashes)) if hashes.is_empty()));
+    }
+
+    #[test]
+    fn test_idle_filter_expires() {
+        let filters = Filters::new(Duration::from_millis(10), DEFAULT_MAX_FILTERS);
+        let id = filters.add(FilterKind::Blocks { last_block: 0 }).unwrap();
+        assert!(filters.poll(id.0, |_| ()).is_ok());
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(matches!(
+            filters.poll(id.0, |_| ()),
+            Err(Error::FilterNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_max_filters() {
+        let filters = Filters::new(DEFAULT_FILTER_TTL, 2);
+        let first = filters.add(FilterKind::Blocks { last_block: 0 }).unwrap();
+        let second = filters.add(FilterKind::Blocks { last_block: 0 }).unwrap();
+        assert_ne!(first, second);
+        assert!(matches!(
+            filters.add(FilterKind::Blocks { last_block: 0 }),
+            Err(Error::TooManyFilters { max: 2 })
+        ));
+
+        // uninstalled filter frees a slot
+        assert!(filters.remove(first.0));
+        assert!(filters.add(FilterKind::Blocks { last_block: 0 }).is_ok());
+    }
+}
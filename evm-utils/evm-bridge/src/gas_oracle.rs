@@ -0,0 +1,152 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use evm_rpc::{percentile, BlockId, Either, GasPriceConfig, Hex, RPCBlock};
+use evm_state::U256;
+use log::*;
+use serde_json::json;
+use solana_client::{client_error::ClientError, rpc_client::RpcClient, rpc_request::RpcRequest};
+
+/// How long computed gas price is reused.
+const GAS_ORACLE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Suggests gas price from gas prices of transactions in recent blocks.
+#[derive(Debug, Default)]
+pub struct GasOracle {
+    /// Number of latest blocks to sample, oracle is disabled if 0.
+    blocks: u64,
+    /// Percentile of sampled gas prices.
+    percentile: f64,
+    cached: Mutex<Option<(Instant, U256)>>,
+}
+
+impl GasOracle {
+    pub fn new(config: &GasPriceConfig) -> Self {
+        Self {
+            blocks: config.oracle_blocks,
+            percentile: config.oracle_percentile,
+            cached: Mutex::default(),
+        }
+    }
+
+    /// Returns gas price at configured percentile, but not less than `min_gas_price`.
+    pub fn gas_price(&self, rpc_client: &RpcClient, min_gas_price: U256) -> U256 {
+        if self.blocks == 0 {
+            return min_gas_price;
+        }
+        if let Some((computed_at, gas_price)) = *self.cached.lock().unwrap() {
+            if computed_at.elapsed() < GAS_ORACLE_CACHE_TTL {
+                return gas_price.max(min_gas_price);
+            }
+        }
+
+        // sample without lock, so concurrent callers are not blocked by rpc requests
+        let gas_price = match self.sample(rpc_client) {
+            Ok(mut gas_prices) => {
+                gas_prices.sort();
+                percentile(&gas_prices, self.percentile).unwrap_or_default()
+            }
+            Err(e) => {
+                // cache fallback as well, to not retry on every call to unavailable node
+                warn!("Unable to sample gas prices of recent blocks: {:?}", e);
+                U256::zero()
+            }
+        };
+        *self.cached.lock().unwrap() = Some((Instant::now(), gas_price));
+        gas_price.max(min_gas_price)
+    }
+
+    /// Collects gas prices of transactions from latest blocks.
+    fn sample(&self, rpc_client: &RpcClient) -> Result<Vec<U256>, ClientError> {
+        let latest: Hex<u64> = rpc_client.send(RpcRequest::EthBlockNumber, json!([]))?;
+        let oldest = (latest.0 + 1).saturating_sub(self.blocks);
+
+        let mut gas_prices = Vec::new();
+        for block_num in oldest..=latest.0 {
+            let block: Option<RPCBlock> = rpc_client.send(
+                RpcRequest::EthGetBlockByNumber,
+                json!([BlockId::from(block_num), true]),
+            )?;
+            if let Some(RPCBlock {
+                transactions: Either::Right(txs),
+                ..
+            }) = block
+            {
+                gas_prices.extend(txs.iter().filter_map(|tx| tx.gas_price.map(|p| p.0)));
+            }
+        }
+        Ok(gas_prices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::mock_sender::Mocks;
+
+    fn test_oracle(blocks: u64, percentile: f64) -> GasOracle {
+        GasOracle::new(&GasPriceConfig {
+            oracle_blocks: blocks,
+            oracle_percentile: percentile,
+            ..GasPriceConfig::default()
+        })
+    }
+
+    fn client_with_prices(prices: &[u64]) -> RpcClient {
+        let transactions = prices
+            .iter()
+            .map(|price| evm_rpc::RPCTransaction {
+                gas_price: Some(Hex(U256::from(*price))),
+                ..serde_json::from_str("{}").unwrap()
+            })
+            .collect();
+        let block = RPCBlock {
+            transactions: Either::Right(transactions),
+            ..RPCBlock::default()
+        };
+        let mocks: Mocks = vec![
+            (RpcRequest::EthBlockNumber, json!("0x10")),
+            (
+                RpcRequest::EthGetBlockByNumber,
+                serde_json::to_value(&block).unwrap(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+    }
+
+    #[test]
+    fn test_gas_price_floored_and_cached() {
+        let client = client_with_prices(&[10, 20, 30, 40, 50]);
+        let oracle = test_oracle(3, 60.0);
+        assert_eq!(oracle.gas_price(&client, 5.into()), U256::from(30));
+
+        // cached value is floored by changed minimum
+        assert_eq!(oracle.gas_price(&client, 100.into()), U256::from(100));
+        assert_eq!(oracle.gas_price(&client, 5.into()), U256::from(30));
+
+        let oracle = test_oracle(3, 60.0);
+        assert_eq!(oracle.gas_price(&client, 100.into()), U256::from(100));
+    }
+
+    #[test]
+    fn test_failed_sample_cached() {
+        let failing = RpcClient::new_mock("fails".to_string());
+        let oracle = test_oracle(3, 60.0);
+        assert_eq!(oracle.gas_price(&failing, 5.into()), U256::from(5));
+
+        // fallback is reused until cache expires
+        let client = client_with_prices(&[10, 20, 30, 40, 50]);
+        assert_eq!(oracle.gas_price(&client, 5.into()), U256::from(5));
+    }
+
+    #[test]
+    fn test_disabled_oracle() {
+        let client = client_with_prices(&[10, 20, 30]);
+        let oracle = GasOracle::default();
+        assert_eq!(oracle.gas_price(&client, 5.into()), U256::from(5));
+    }
+}
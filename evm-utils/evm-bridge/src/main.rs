@@ -1,11 +1,21 @@
+mod coalesce;
+mod compression;
+mod deadline;
+mod filters;
+mod gas_oracle;
 mod pool;
+mod pubsub;
+mod reorg;
+mod request_id;
 mod sol_proxy;
+mod stale;
+mod tracer;
 
 use log::*;
 use solana_sdk::commitment_config::CommitmentConfig;
 use std::future::ready;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::Duration;
 use std::{
@@ -13,13 +23,17 @@ use std::{
     net::SocketAddr,
 };
 
-use evm_rpc::bridge::BridgeERPC;
-use evm_rpc::chain::ChainERPC;
-use evm_rpc::general::GeneralERPC;
-use evm_rpc::trace::TraceERPC;
-use evm_rpc::error::{Error, *};
-use evm_rpc::trace::TraceMeta;
-use evm_rpc::*;
+use evm_rpc::{
+    bridge::BridgeERPC,
+    chain::ChainERPC,
+    error::{Error, *},
+    filters::FilterERPC,
+    general::GeneralERPC,
+    trace::{TraceERPC, TraceMeta},
+    txpool::TxPoolERPC,
+    velas::VelasERPC,
+    *,
+};
 use evm_state::*;
 use sha3::{Digest, Keccak256};
 
@@ -33,8 +47,7 @@ use snafu::ResultExt;
 use derivative::*;
 use solana_evm_loader_program::scope::*;
 use solana_sdk::{
-    clock::MS_PER_TICK, fee_calculator::DEFAULT_TARGET_LAMPORTS_PER_SIGNATURE, pubkey::Pubkey,
-    signers::Signers, transaction::TransactionError,
+    clock::MS_PER_TICK, pubkey::Pubkey, signers::Signers, transaction::TransactionError,
 };
 
 use solana_client::{
@@ -47,19 +60,22 @@ use solana_client::{
 };
 
 use tracing_attributes::instrument;
-use tracing_subscriber::prelude::*;
-use tracing_subscriber::{
-    filter::{LevelFilter, Targets},
-    layer::{Layer, SubscriberExt},
-};
 
 use ::tokio;
 use ::tokio::sync::mpsc;
 
+use coalesce::CoalescedReads;
+use deadline::RequestDeadline;
+use filters::{FilterErpcImpl, Filters};
+use gas_oracle::GasOracle;
 use pool::{
-    worker_cleaner, worker_deploy, worker_signature_checker, EthPool, PooledTransaction,
-    SystemClock,
+    worker_cleaner, worker_deploy, worker_queue, worker_signature_checker, EthPool,
+    PooledTransaction, SystemClock,
 };
+use pubsub::{worker_new_heads, worker_pending_transactions, Subscriptions, WsMetadata};
+use reorg::{worker_reorg_tracker, ReorgTracker};
+use request_id::RequestIdValidation;
+use stale::StaleReads;
 
 use rlp::Encodable;
 use secp256k1::Message;
@@ -68,6 +84,25 @@ type EvmResult<T> = StdResult<T, evm_rpc::Error>;
 
 const MAX_NUM_BLOCKS_IN_BATCH: u64 = 2000; // should be less or equal to const core::evm_rpc_impl::logs::MAX_NUM_BLOCKS
 
+/// Maximum number of blocks in eth_feeHistory, bigger requests are clamped.
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Default maximum size in bytes of web3_sha3 and eth_sign input.
+const DEFAULT_MAX_INPUT_SIZE: usize = 1024 * 1024;
+
+/// Default maximum size in bytes of input (calldata) of sent transaction.
+const DEFAULT_MAX_TX_INPUT_BYTES: usize = 256 * 1024;
+
+/// Maximum delay between status checks of sent transactions.
+const MAX_SEND_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+/// Delay between status checks of self-test transaction.
+const SELF_TEST_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of status checks before self-test is considered failed.
+const SELF_TEST_MAX_POLLS: usize = 60;
+/// Gas limit of transfer without input.
+const SIMPLE_TRANSFER_GAS: u64 = 21000;
+
 // A compatibility layer, to make software more fluently.
 mod compatibility {
     use evm_rpc::Hex;
@@ -169,7 +204,10 @@ mod compatibility {
 macro_rules! proxy_evm_rpc {
     (@silent $rpc: expr, $rpc_call:ident $(, $calls:expr)*) => (
         {
-            match RpcClient::send(&$rpc, RpcRequest::$rpc_call, json!([$($calls,)*])) {
+            let result = deadline::upstream(|| {
+                RpcClient::send(&$rpc, RpcRequest::$rpc_call, json!([$($calls,)*]))
+            });
+            match result {
                 Err(e) => Err(from_client_error(e).into()),
                 Ok(o) => Ok(o)
             }
@@ -197,7 +235,27 @@ pub struct EvmBridge {
     simulate: bool,
     max_logs_blocks: u64,
     pool: EthPool<SystemClock>,
-    min_gas_price: U256,
+    gas_price_config: GasPriceConfig,
+    /// How much raw transaction nonce can be behind the latest on-chain nonce.
+    nonce_tolerance: u64,
+    /// Reject eth_call to addresses without code.
+    strict_call: bool,
+    /// Allow signing with managed accounts (eth_sign, eth_signTransaction, eth_sendTransaction).
+    managed_signing: bool,
+    /// Accept raw transactions without replay protection (pre EIP-155).
+    allow_unprotected_txs: bool,
+    /// Maximum size in bytes of web3_sha3 and eth_sign input.
+    max_input_size: usize,
+    /// Maximum size in bytes of input of sent transaction.
+    max_tx_input_bytes: usize,
+    /// Retry policy of sending transactions in chunks.
+    send_retry_config: SendRetryConfig,
+    reorg_tracker: Mutex<ReorgTracker>,
+    gas_oracle: GasOracle,
+    #[derivative(Debug = "ignore")]
+    subscriptions: Subscriptions,
+    #[derivative(Debug = "ignore")]
+    filters: Filters,
 }
 
 impl EvmBridge {
@@ -209,7 +267,7 @@ impl EvmBridge {
         verbose_errors: bool,
         simulate: bool,
         max_logs_blocks: u64,
-        min_gas_price: U256,
+        gas_price_config: GasPriceConfig,
     ) -> Self {
         info!("EVM chain id {}", evm_chain_id);
 
@@ -230,7 +288,7 @@ impl EvmBridge {
         let key = solana_sdk::signature::read_keypair_file(&keypath).unwrap();
 
         info!("Creating mempool...");
-        let pool = EthPool::new(SystemClock);
+        let pool = EthPool::new_with_gas_price_config(SystemClock, gas_price_config);
 
         Self {
             evm_chain_id,
@@ -241,7 +299,18 @@ impl EvmBridge {
             simulate,
             max_logs_blocks,
             pool,
-            min_gas_price,
+            gas_price_config,
+            nonce_tolerance: 0,
+            strict_call: false,
+            managed_signing: false,
+            allow_unprotected_txs: false,
+            max_input_size: DEFAULT_MAX_INPUT_SIZE,
+            max_tx_input_bytes: DEFAULT_MAX_TX_INPUT_BYTES,
+            send_retry_config: SendRetryConfig::default(),
+            reorg_tracker: Mutex::default(),
+            gas_oracle: GasOracle::new(&gas_price_config),
+            subscriptions: Subscriptions::default(),
+            filters: Filters::default(),
         }
     }
 
@@ -249,18 +318,16 @@ impl EvmBridge {
     async fn send_tx(
         &self,
         tx: evm::Transaction,
+        caller: Address,
         meta_keys: HashSet<Pubkey>,
     ) -> EvmResult<Hex<H256>> {
         let (sender, mut receiver) = mpsc::channel::<EvmResult<Hex<H256>>>(1);
 
-        if tx.gas_price < self.min_gas_price {
-            return Err(Error::GasPriceTooLow {
-                need: self.min_gas_price,
-            });
-        }
+        self.check_gas_price(&tx)?;
+        self.check_tx_input_size(&tx)?;
+        check_precompile_call(&tx)?;
 
-        let tx = PooledTransaction::new(tx, meta_keys, sender)
-            .map_err(|source| evm_rpc::Error::EvmStateError { source })?;
+        let tx = PooledTransaction::with_caller(tx, caller, meta_keys, sender);
         let tx = match self.pool.import(tx) {
             // tx was already processed on this bridge, return hash.
             Err(txpool::Error::AlreadyImported(h)) => return Ok(Hex(h)),
@@ -274,12 +341,126 @@ impl EvmBridge {
         };
 
         if self.simulate {
-            receiver.recv().await.unwrap()
+            // sender is dropped only after sending result, but stay safe if channel closed
+            receiver
+                .recv()
+                .await
+                .unwrap_or(Err(evm_rpc::Error::TransactionRemoved {}))
         } else {
             Ok(tx.inner.tx_id_hash().into())
         }
     }
 
+    /// Signing with server-held keys is available to anyone who can reach rpc,
+    /// so it should be enabled only on private endpoints.
+    fn check_managed_signing(&self, method: &str) -> EvmResult<()> {
+        if !self.managed_signing {
+            return Err(Error::MethodDisabled {
+                method: method.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects too large input of methods, which process it without upstream node.
+    fn check_input_size(&self, input: &[u8]) -> EvmResult<()> {
+        if input.len() > self.max_input_size {
+            return Err(Error::InputTooLarge {
+                size: input.len(),
+                max_size: self.max_input_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects transaction with oversized input, before it occupies pool.
+    fn check_tx_input_size(&self, tx: &evm::Transaction) -> EvmResult<()> {
+        if tx.input.len() > self.max_tx_input_bytes {
+            return Err(Error::InputTooLarge {
+                size: tx.input.len(),
+                max_size: self.max_tx_input_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects transaction with gas price below minimal one.
+    fn check_gas_price(&self, tx: &evm::Transaction) -> EvmResult<()> {
+        if tx.gas_price < self.gas_price_config.default_gas_price {
+            return Err(Error::GasPriceTooLow {
+                need: self.gas_price_config.default_gas_price,
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects transaction signed for another chain.
+    /// Transactions without chain id are accepted only if `allow_unprotected_txs` is set.
+    fn check_chain_id(&self, tx: &evm::Transaction) -> EvmResult<()> {
+        match tx.signature.chain_id() {
+            Some(chain_id) if chain_id == self.evm_chain_id => Ok(()),
+            None if self.allow_unprotected_txs => Ok(()),
+            tx_chain_id => Err(Error::WrongChainId {
+                chain_id: self.evm_chain_id,
+                tx_chain_id,
+            }),
+        }
+    }
+
+    /// Rejects transaction, which nonce is already used on chain.
+    /// If on-chain nonce is unavailable, the check is skipped.
+    fn check_nonce(&self, tx: &evm::Transaction, sender: Address) -> EvmResult<()> {
+        let expected = match self.rpc_client.get_evm_transaction_count(&sender) {
+            Ok(nonce) => nonce,
+            Err(e) => {
+                warn!("Unable to get nonce of {:?}, skip nonce check: {:?}", sender, e);
+                return Ok(());
+            }
+        };
+        self.pool.update_state_nonce(&sender, expected);
+        if tx.nonce.saturating_add(self.nonce_tolerance.into()) < expected {
+            return Err(Error::NonceTooLow {
+                got: tx.nonce,
+                expected,
+            });
+        }
+        Ok(())
+    }
+
+    /// Nonce for the next transaction of managed account, if it isn't set explicitly.
+    ///
+    /// Last seen on-chain nonce is the floor, nonces of transactions in the pool are taken
+    /// starting from it, so a gap left by dropped transactions is filled first. The floor is
+    /// resynced with chain by signature checker, and fetched from chain if unknown (e.g. after
+    /// restart). If chain is unavailable, nonce after the last pooled transaction is used.
+    fn next_nonce(&self, address: &Address) -> U256 {
+        if let Some(nonce) = self.pool.next_nonce(address) {
+            return nonce;
+        }
+        match self.rpc_client.get_evm_transaction_count(address) {
+            Ok(state_nonce) => {
+                self.pool.update_state_nonce(address, state_nonce);
+                self.pool.first_free_nonce(address, state_nonce)
+            }
+            Err(e) => {
+                warn!("Unable to get nonce of {:?}: {:?}", address, e);
+                self.pool.transaction_count(address).unwrap_or_default()
+            }
+        }
+    }
+
+    /// Sets nonce of transaction, simulated on pending block, to the nonce, that would be assigned
+    /// to the next transaction of sender (see `next_nonce`), so it is simulated in sequence.
+    fn with_pending_nonce(&self, mut tx: RPCTransaction, block: Option<BlockId>) -> RPCTransaction {
+        if !matches!(block, Some(BlockId::RelativeId(BlockRelId::Pending))) || tx.nonce.is_some() {
+            return tx;
+        }
+        if let Some(from) = tx.from {
+            tx.nonce = Some(Hex(self.next_nonce(&from.0)));
+        }
+        tx
+    }
+
     fn block_to_number(&self, block: Option<BlockId>) -> EvmResult<u64> {
         let block = block.unwrap_or_default();
         let block_num = match block {
@@ -337,6 +518,8 @@ impl BridgeERPC for BridgeErpcImpl {
 
     #[instrument]
     fn sign(&self, meta: Self::Metadata, address: Hex<Address>, data: Bytes) -> EvmResult<Bytes> {
+        meta.check_managed_signing("eth_sign")?;
+        meta.check_input_size(&data.0)?;
         let secret_key = meta
             .accounts
             .get(&address.0)
@@ -362,6 +545,7 @@ impl BridgeERPC for BridgeErpcImpl {
         tx: RPCTransaction,
     ) -> BoxFuture<EvmResult<Bytes>> {
         let future = async move {
+            meta.check_managed_signing("eth_signTransaction")?;
             let address = tx.from.map(|a| a.0).unwrap_or_default();
 
             debug!("sign_transaction from = {}", address);
@@ -374,16 +558,14 @@ impl BridgeERPC for BridgeErpcImpl {
             let nonce = tx
                 .nonce
                 .map(|a| a.0)
-                .or_else(|| meta.pool.transaction_count(&address))
-                .or_else(|| meta.rpc_client.get_evm_transaction_count(&address).ok())
-                .unwrap_or_default();
+                .unwrap_or_else(|| meta.next_nonce(&address));
 
             let tx = UnsignedTransaction {
                 nonce,
                 gas_price: tx
                     .gas_price
                     .map(|a| a.0)
-                    .unwrap_or_else(|| meta.min_gas_price),
+                    .unwrap_or_else(|| meta.gas_price_config.default_gas_price),
                 gas_limit: tx.gas.map(|a| a.0).unwrap_or_else(|| 30000000.into()),
                 action: tx
                     .to
@@ -407,6 +589,7 @@ impl BridgeERPC for BridgeErpcImpl {
         meta_keys: Option<Vec<String>>,
     ) -> BoxFuture<EvmResult<Hex<H256>>> {
         let future = async move {
+            meta.check_managed_signing("eth_sendTransaction")?;
             let address = tx.from.map(|a| a.0).unwrap_or_default();
 
             debug!("send_transaction from = {}", address);
@@ -426,16 +609,14 @@ impl BridgeERPC for BridgeErpcImpl {
             let nonce = tx
                 .nonce
                 .map(|a| a.0)
-                .or_else(|| meta.pool.transaction_count(&address))
-                .or_else(|| meta.rpc_client.get_evm_transaction_count(&address).ok())
-                .unwrap_or_default();
+                .unwrap_or_else(|| meta.next_nonce(&address));
 
             let tx_create = evm::UnsignedTransaction {
                 nonce,
                 gas_price: tx
                     .gas_price
                     .map(|a| a.0)
-                    .unwrap_or_else(|| meta.min_gas_price),
+                    .unwrap_or_else(|| meta.gas_price_config.default_gas_price),
                 gas_limit: tx.gas.map(|a| a.0).unwrap_or_else(|| 30000000.into()),
                 action: tx
                     .to
@@ -447,7 +628,7 @@ impl BridgeERPC for BridgeErpcImpl {
 
             let tx = tx_create.sign(secret_key, Some(meta.evm_chain_id));
 
-            meta.send_tx(tx, meta_keys).await
+            meta.send_tx(tx, address, meta_keys).await
         };
 
         Box::pin(future)
@@ -469,21 +650,21 @@ impl BridgeERPC for BridgeErpcImpl {
                 .collect::<StdResult<HashSet<_>, _>>()
                 .map_err(|e| into_native_error(e, meta.verbose_errors))?;
 
-            let tx: compatibility::Transaction =
-                rlp::decode(&bytes.0).with_context(|| RlpError {
-                    struct_name: "RawTransaction".to_string(),
-                    input_data: hex::encode(&bytes.0),
-                })?;
-            let tx: evm::Transaction = tx.into();
+            let tx = decode_raw_transaction(&bytes)?;
 
-            // TODO: Check chain_id.
-            // TODO: check gas price.
+            // reject transactions with garbage signature, before they occupy pool
+            let sender = tx.caller().map_err(|e| Error::InvalidParams {
+                details: format!("Unable to recover transaction sender: {}", e),
+            })?;
+            debug!("recovered sender = {:?}", sender);
 
-            let unsigned_tx: evm::UnsignedTransaction = tx.clone().into();
-            let hash = unsigned_tx.signing_hash(Some(meta.evm_chain_id));
-            debug!("loaded tx_hash = {}", hash);
+            meta.check_chain_id(&tx)?;
 
-            meta.send_tx(tx, meta_keys).await
+            debug!("loaded tx_hash = {}", tx.tx_id_hash());
+
+            meta.check_nonce(&tx, sender)?;
+
+            meta.send_tx(tx, sender, meta_keys).await
         };
 
         Box::pin(future)
@@ -493,6 +674,83 @@ impl BridgeERPC for BridgeErpcImpl {
     fn compilers(&self, _meta: Self::Metadata) -> EvmResult<Vec<String>> {
         Ok(vec![])
     }
+
+    #[instrument]
+    fn fee_history(
+        &self,
+        meta: Self::Metadata,
+        block_count: Hex<u64>,
+        newest_block: BlockId,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> BoxFuture<EvmResult<RPCFeeHistory>> {
+        Box::pin(async move {
+            if let Some(percentiles) = &reward_percentiles {
+                let in_range = percentiles.iter().all(|p| (0.0..=100.0).contains(p));
+                let ascending = percentiles.windows(2).all(|pair| pair[0] <= pair[1]);
+                if !in_range || !ascending {
+                    return Err(Error::InvalidParams {
+                        details: "Reward percentiles should be in range 0..=100 and ascending"
+                            .to_string(),
+                    });
+                }
+            }
+            // velas has no base fee, transactions pay at least min gas price
+            let base_fee = meta.gas_price_config.default_gas_price;
+            let newest = meta.block_to_number(Some(newest_block))?;
+            let block_count = block_count.0.min(MAX_FEE_HISTORY_BLOCKS).min(newest + 1);
+            let oldest = newest + 1 - block_count;
+
+            let full = reward_percentiles.is_some();
+            let mut gas_used_ratio = Vec::with_capacity(block_count as usize);
+            let mut reward = Vec::with_capacity(block_count as usize);
+            for block_num in oldest..=newest {
+                let block: Option<RPCBlock> = proxy_evm_rpc!(
+                    meta.rpc_client,
+                    EthGetBlockByNumber,
+                    BlockId::from(block_num),
+                    full
+                )?;
+                let block = block.ok_or(Error::BlockNotFound {
+                    block: block_num.into(),
+                })?;
+                gas_used_ratio.push(if block.gas_limit.0.is_zero() {
+                    0.0
+                } else {
+                    block.gas_used.0.low_u64() as f64 / block.gas_limit.0.low_u64() as f64
+                });
+                if let Some(percentiles) = &reward_percentiles {
+                    let gas_prices = match block.transactions {
+                        evm_rpc::Either::Right(txs) => {
+                            txs.iter().filter_map(|tx| tx.gas_price).collect()
+                        }
+                        evm_rpc::Either::Left(_) => vec![],
+                    };
+                    reward.push(HexVec(block_rewards(gas_prices, base_fee, percentiles)));
+                }
+            }
+
+            Ok(RPCFeeHistory {
+                oldest_block: Hex(oldest),
+                base_fee_per_gas: HexVec(vec![base_fee; gas_used_ratio.len() + 1]),
+                gas_used_ratio,
+                reward: reward_percentiles.map(|_| reward),
+            })
+        })
+    }
+}
+
+/// Priority fees (gas price above base fee) of block transactions, at requested percentiles.
+fn block_rewards(gas_prices: Vec<Hex<U256>>, base_fee: U256, percentiles: &[f64]) -> Vec<U256> {
+    let mut gas_prices: Vec<U256> = gas_prices.into_iter().map(|price| price.0).collect();
+    gas_prices.sort();
+    percentiles
+        .iter()
+        .map(|percentile| {
+            evm_rpc::percentile(&gas_prices, *percentile)
+                .unwrap_or_default()
+                .saturating_sub(base_fee)
+        })
+        .collect()
 }
 
 #[derive(Debug)]
@@ -523,7 +781,8 @@ impl GeneralERPC for GeneralErpcProxy {
     }
 
     #[instrument]
-    fn sha3(&self, _meta: Self::Metadata, bytes: Bytes) -> EvmResult<Hex<H256>> {
+    fn sha3(&self, meta: Self::Metadata, bytes: Bytes) -> EvmResult<Hex<H256>> {
+        meta.check_input_size(&bytes.0)?;
         Ok(Hex(H256::from_slice(
             Keccak256::digest(bytes.0.as_slice()).as_slice(),
         )))
@@ -561,7 +820,15 @@ impl GeneralERPC for GeneralErpcProxy {
 
     #[instrument]
     fn gas_price(&self, meta: Self::Metadata) -> EvmResult<Hex<Gas>> {
-        Ok(Hex(meta.min_gas_price))
+        Ok(Hex(meta.gas_oracle.gas_price(
+            &meta.rpc_client,
+            meta.gas_price_config.default_gas_price,
+        )))
+    }
+
+    #[instrument]
+    fn max_priority_fee_per_gas(&self, meta: Self::Metadata) -> EvmResult<Hex<Gas>> {
+        Ok(Hex(meta.gas_price_config.max_priority_fee_per_gas()))
     }
 }
 
@@ -634,7 +901,7 @@ impl ChainERPC for ChainErpcProxy {
         &self,
         meta: Self::Metadata,
         block: BlockId,
-    ) -> BoxFuture<EvmResult<Hex<usize>>> {
+    ) -> BoxFuture<EvmResult<Option<Hex<usize>>>> {
         Box::pin(ready(proxy_evm_rpc!(
             meta.rpc_client,
             EthGetBlockTransactionCountByNumber,
@@ -647,7 +914,7 @@ impl ChainERPC for ChainErpcProxy {
         &self,
         meta: Self::Metadata,
         block_hash: Hex<H256>,
-    ) -> BoxFuture<EvmResult<Hex<usize>>> {
+    ) -> BoxFuture<EvmResult<Option<Hex<usize>>>> {
         Box::pin(ready(proxy_evm_rpc!(
             meta.rpc_client,
             EthGetBlockTransactionCountByHash,
@@ -711,11 +978,9 @@ impl ChainERPC for ChainErpcProxy {
         tx_hash: Hex<H256>,
     ) -> BoxFuture<EvmResult<Option<RPCTransaction>>> {
         // TODO: chain all possible outcomes properly
-        if let Some(tx) = meta.pool.transaction_by_hash(tx_hash) {
-            if let Ok(tx) = RPCTransaction::from_transaction((**tx).clone().into()) {
-                // TODO: should we `patch` tx?
-                return Box::pin(ready(Ok(Some(tx))));
-            }
+        if let Some(tx) = meta.pool.rpc_transaction_by_hash(tx_hash) {
+            // TODO: should we `patch` tx?
+            return Box::pin(ready(Ok(Some(tx))));
         }
         Box::pin(ready(
             proxy_evm_rpc!(meta.rpc_client, EthGetTransactionByHash, tx_hash)
@@ -773,13 +1038,38 @@ impl ChainERPC for ChainErpcProxy {
         tx: RPCTransaction,
         block: Option<BlockId>,
         meta_keys: Option<Vec<String>>,
+        state_overrides: Option<StateOverrides>,
     ) -> BoxFuture<EvmResult<Bytes>> {
+        let tx = meta.with_pending_nonce(tx, block);
+        if meta.strict_call {
+            // overridden code is not known to the node
+            let code_overridden = |address| {
+                state_overrides
+                    .as_ref()
+                    .and_then(|overrides| overrides.get(&address))
+                    .map_or(false, |account| account.code.is_some())
+            };
+            if let Some(address) = tx.to.filter(|address| !code_overridden(*address)) {
+                let code: EvmResult<Bytes> =
+                    proxy_evm_rpc!(meta.rpc_client, EthGetCode, address, block);
+                match code {
+                    Ok(code) if code.0.is_empty() => {
+                        return Box::pin(ready(Err(Error::NoContractAtAddress {
+                            address: address.0,
+                        })))
+                    }
+                    Ok(_) => {}
+                    Err(e) => return Box::pin(ready(Err(e))),
+                }
+            }
+        }
         Box::pin(ready(proxy_evm_rpc!(
             meta.rpc_client,
             EthCall,
             tx,
             block,
-            meta_keys
+            meta_keys,
+            state_overrides
         )))
     }
 
@@ -804,7 +1094,7 @@ impl ChainERPC for ChainErpcProxy {
     fn logs(
         &self,
         meta: Self::Metadata,
-        mut log_filter: RPCLogFilter,
+        log_filter: RPCLogFilter,
     ) -> BoxFuture<EvmResult<Vec<RPCLog>>> {
         let starting_block = match meta.block_to_number(log_filter.from_block) {
             Ok(res) => res,
@@ -832,39 +1122,7 @@ impl ChainERPC for ChainErpcProxy {
             })));
         }
 
-        let mut starting = starting_block;
-
-        // make execution parallel
-        Box::pin(async move {
-            let mut collector = Vec::new();
-            while starting <= ending_block {
-                let ending = (starting.saturating_add(MAX_NUM_BLOCKS_IN_BATCH)).min(ending_block);
-                log_filter.from_block = Some(starting.into());
-                log_filter.to_block = Some(ending.into());
-
-                let cloned_filter = log_filter.clone();
-                let cloned_meta = meta.clone();
-                // Parallel execution:
-                collector.push(tokio::task::spawn_blocking(move || {
-                    info!("filter = {:?}", cloned_filter);
-                    let result: EvmResult<Vec<RPCLog>> =
-                        proxy_evm_rpc!(@silent cloned_meta.rpc_client, EthGetLogs, cloned_filter);
-                    info!("logs = {:?}", result);
-
-                    result
-                }));
-
-                starting = starting.saturating_add(MAX_NUM_BLOCKS_IN_BATCH + 1);
-            }
-            // join all execution, fast fail on any error.
-            let mut result = Vec::new();
-            for collection in collector {
-                result.extend(collection.await.map_err(|details| Error::RuntimeError {
-                    details: details.to_string(),
-                })??)
-            }
-            Ok(result)
-        })
+        Box::pin(logs_in_range(meta, log_filter, starting_block, ending_block))
     }
 
     #[instrument]
@@ -906,85 +1164,561 @@ impl ChainERPC for ChainErpcProxy {
     }
 }
 
+fn decode_raw_transaction(bytes: &Bytes) -> EvmResult<evm::Transaction> {
+    // EIP-2718: typed transaction starts with type byte, legacy one - with rlp list.
+    // evm_state recovers sender only from signing hash of legacy transaction.
+    if let Some(tx_type) = bytes.0.first().filter(|tx_type| **tx_type <= 0x7f) {
+        return Err(Error::InvalidParams {
+            details: format!(
+                "Transactions of type {:#x} are not supported yet, send legacy transaction",
+                tx_type
+            ),
+        });
+    }
+    let tx: compatibility::Transaction = rlp::decode(&bytes.0).with_context(|| RlpError {
+        struct_name: "RawTransaction".to_string(),
+        input_data: hex::encode(&bytes.0),
+    })?;
+    Ok(tx.into())
+}
+
+/// Rejects call to native contract with malformed input, which would fail only on deploy.
+fn check_precompile_call(tx: &evm::Transaction) -> EvmResult<()> {
+    if let TransactionAction::Call(address) = tx.action {
+        solana_evm_loader_program::precompiles::check_native_call(address, &tx.input).map_err(
+            |e| Error::InvalidPrecompileCall {
+                address,
+                details: e.to_string(),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Requests logs for blocks `starting..=ending` in batches of `MAX_NUM_BLOCKS_IN_BATCH`.
+async fn logs_in_range(
+    meta: Arc<EvmBridge>,
+    mut log_filter: RPCLogFilter,
+    starting_block: u64,
+    ending_block: u64,
+) -> EvmResult<Vec<RPCLog>> {
+    let mut starting = starting_block;
+
+    // make execution parallel
+    let mut collector = Vec::new();
+    while starting <= ending_block {
+        let ending = (starting.saturating_add(MAX_NUM_BLOCKS_IN_BATCH)).min(ending_block);
+        log_filter.from_block = Some(starting.into());
+        log_filter.to_block = Some(ending.into());
+
+        let cloned_filter = log_filter.clone();
+        let cloned_meta = meta.clone();
+        // Parallel execution:
+        collector.push(tokio::task::spawn_blocking(move || {
+            info!("filter = {:?}", cloned_filter);
+            let result: EvmResult<Vec<RPCLog>> =
+                proxy_evm_rpc!(@silent cloned_meta.rpc_client, EthGetLogs, cloned_filter);
+            info!("logs = {:?}", result);
+
+            result
+        }));
+
+        starting = starting.saturating_add(MAX_NUM_BLOCKS_IN_BATCH + 1);
+    }
+    // join all execution, fast fail on any error.
+    let mut result = Vec::new();
+    for collection in collector {
+        result.extend(collection.await.map_err(|details| Error::RuntimeError {
+            details: details.to_string(),
+        })??)
+    }
+    Ok(result)
+}
+
+/// Warns about managed accounts with balance below `min_balance`, which can't pay for gas.
+/// Returns addresses of such accounts.
+fn check_account_balances(meta: &EvmBridge, min_balance: U256) -> Vec<Address> {
+    let mut low_balance = vec![];
+    for address in meta.accounts.keys() {
+        match meta.rpc_client.get_evm_balance(address) {
+            Ok(balance) if balance < min_balance => {
+                warn!(
+                    "Managed account {:?} has balance {} wei, below minimum of {} wei, its transactions may fail",
+                    address, balance, min_balance
+                );
+                low_balance.push(*address);
+            }
+            Ok(_) => (),
+            Err(e) => warn!(
+                "Unable to get balance of managed account {:?}: {:?}",
+                address, e
+            ),
+        }
+    }
+    low_balance
+}
+
+async fn worker_balance_check(meta: Arc<EvmBridge>, min_balance: U256, interval: Duration) {
+    info!("Running managed accounts balance check task...");
+    loop {
+        tokio::time::sleep(interval).await;
+        check_account_balances(&meta, min_balance);
+    }
+}
+
+/// Signs and submits zero-value transfer from managed account to itself,
+/// and waits until it lands on chain.
+async fn self_test(meta: Arc<EvmBridge>) -> EvmResult<H256> {
+    let (address, secret_key) = meta
+        .accounts
+        .iter()
+        .next()
+        .ok_or_else(|| Error::RuntimeError {
+            details: "Self-test requires managed account, run bridge with --dev".to_string(),
+        })?;
+    let nonce = meta
+        .rpc_client
+        .get_evm_transaction_count(address)
+        .map_err(from_client_error)?;
+    let tx = evm::UnsignedTransaction {
+        nonce,
+        gas_price: meta.gas_price_config.default_gas_price,
+        gas_limit: SIMPLE_TRANSFER_GAS.into(),
+        action: evm::TransactionAction::Call(*address),
+        value: 0.into(),
+        input: vec![],
+    }
+    .sign(secret_key, Some(meta.evm_chain_id));
+
+    let hash = meta.send_tx(tx, *address, HashSet::new()).await?.0;
+    info!("Self-test transaction {:?} submitted", hash);
+
+    for _ in 0..SELF_TEST_MAX_POLLS {
+        if meta.is_transaction_landed(&hash) == Some(true) {
+            return Ok(hash);
+        }
+        tokio::time::sleep(SELF_TEST_POLL_INTERVAL).await;
+    }
+    Err(Error::RuntimeError {
+        details: format!("Self-test transaction {:?} was not confirmed", hash),
+    })
+}
+
 #[derive(Debug)]
-pub struct TraceErpcProxy;
-impl TraceERPC for TraceErpcProxy {
+pub struct VelasErpcProxy;
+impl VelasERPC for VelasErpcProxy {
     type Metadata = Arc<EvmBridge>;
 
+    #[instrument(skip(self, meta))]
+    fn logs_count(
+        &self,
+        meta: Self::Metadata,
+        log_filter: RPCLogFilter,
+    ) -> BoxFuture<EvmResult<RPCLogsCount>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetLogsCount,
+            log_filter
+        )))
+    }
+
     #[instrument]
-    fn trace_call(
+    fn block_range(&self, meta: Self::Metadata) -> BoxFuture<EvmResult<RPCBlockRange>> {
+        Box::pin(ready(proxy_evm_rpc!(meta.rpc_client, VelasGetBlockRange)))
+    }
+
+    #[instrument]
+    fn next_nonce(
         &self,
         meta: Self::Metadata,
-        tx: RPCTransaction,
-        traces: Vec<String>,
-        block: Option<BlockId>,
-        meta_info: Option<TraceMeta>,
-    ) -> BoxFuture<EvmResult<evm_rpc::trace::TraceResultsWithTransactionHash>> {
-        Box::pin(ready(proxy_evm_rpc!(meta.rpc_client, EthTraceCall, tx, traces, block, meta_info)))
+        address: Hex<Address>,
+    ) -> BoxFuture<EvmResult<Hex<U256>>> {
+        let state_nonce: Hex<U256> =
+            match proxy_evm_rpc!(meta.rpc_client, EthGetTransactionCount, address) {
+                Ok(nonce) => nonce,
+                Err(e) => return Box::pin(ready(Err(e))),
+            };
+        Box::pin(ready(Ok(Hex(meta.pool.first_free_nonce(&address.0, state_nonce.0)))))
     }
 
     #[instrument]
-    fn trace_call_many(
+    fn block_by_timestamp(
         &self,
         meta: Self::Metadata,
-        tx_traces: Vec<(RPCTransaction, Vec<String>, Option<TraceMeta>)>,
-        block: Option<BlockId>,
-    ) -> BoxFuture<EvmResult<Vec<evm_rpc::trace::TraceResultsWithTransactionHash>>> {
-        Box::pin(ready(proxy_evm_rpc!(meta.rpc_client, EthTraceCallMany, tx_traces, block)))
+        timestamp: Hex<u64>,
+        round_up: bool,
+    ) -> BoxFuture<EvmResult<Option<RPCBlock>>> {
+        Box::pin(ready(
+            proxy_evm_rpc!(meta.rpc_client, VelasGetBlockByTimestamp, timestamp, round_up)
+                .map(|o: Option<_>| o.map(compatibility::patch_block)),
+        ))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn logs_for_contracts(
+        &self,
+        meta: Self::Metadata,
+        addresses: Vec<Hex<Address>>,
+        topics: Option<Vec<Option<RPCTopicFilter>>>,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+    ) -> BoxFuture<EvmResult<Vec<RPCLog>>> {
+        // empty address list in log filter matches any contract
+        if addresses.is_empty() {
+            return Box::pin(ready(Err(Error::InvalidParams {
+                details: "Expected at least one contract address".to_string(),
+            })));
+        }
+        let log_filter = RPCLogFilter {
+            from_block,
+            to_block,
+            address: Some(evm_rpc::Either::Left(addresses)),
+            topics,
+        };
+        // range cap is checked by eth_getLogs
+        let logs = ChainErpcProxy.logs(meta, log_filter);
+        Box::pin(async move {
+            let mut logs = logs.await?;
+            logs.sort_by_key(|log| (log.block_number, log.transaction_index, log.log_index));
+            Ok(logs)
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn find_transaction(
+        &self,
+        meta: Self::Metadata,
+        raw_tx: Bytes,
+    ) -> BoxFuture<EvmResult<RPCTransactionLookup>> {
+        let hash = match decode_raw_transaction(&raw_tx) {
+            Ok(tx) => Hex(tx.tx_id_hash()),
+            Err(e) => return Box::pin(ready(Err(e))),
+        };
+        let receipt: Option<RPCReceipt> =
+            match proxy_evm_rpc!(meta.rpc_client, EthGetTransactionReceipt, hash) {
+                Ok(receipt) => receipt,
+                Err(e) => return Box::pin(ready(Err(e))),
+            };
+        // deployed transactions stay in cache until confirmed
+        let status = if receipt.is_some() {
+            RPCTransactionStatus::Mined
+        } else if meta.pool.transaction_by_hash(hash).is_some()
+            || meta.pool.transaction_for_redeploy(&hash.0).is_some()
+        {
+            RPCTransactionStatus::Pending
+        } else {
+            RPCTransactionStatus::Unknown
+        };
+        Box::pin(ready(Ok(RPCTransactionLookup {
+            hash,
+            status,
+            receipt,
+        })))
     }
 
     #[instrument]
-    fn trace_replay_transaction(
+    fn transactions_compact(
+        &self,
+        meta: Self::Metadata,
+        tx_hashes: Vec<Hex<H256>>,
+        fields: Vec<String>,
+    ) -> BoxFuture<EvmResult<Vec<Option<serde_json::Value>>>> {
+        Box::pin(async move {
+            RPCTransaction::validate_compact_request(tx_hashes.len(), &fields)?;
+            let mut result = Vec::with_capacity(tx_hashes.len());
+            for tx_hash in tx_hashes {
+                // lookup in mempool first, same as eth_getTransactionByHash
+                let tx = ChainErpcProxy
+                    .transaction_by_hash(meta.clone(), tx_hash)
+                    .await?;
+                result.push(tx.map(|tx| tx.to_compact(&fields)));
+            }
+            Ok(result)
+        })
+    }
+
+    #[instrument(skip(self, meta))]
+    fn reorg_stats(&self, meta: Self::Metadata) -> BoxFuture<EvmResult<RPCReorgStats>> {
+        Box::pin(ready(Ok(meta.reorg_tracker.lock().unwrap().stats())))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn mempool_stats(&self, meta: Self::Metadata) -> BoxFuture<EvmResult<RPCMempoolStats>> {
+        Box::pin(ready(Ok(meta.pool.mempool_stats())))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn contract_creation(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+    ) -> BoxFuture<EvmResult<Option<RPCContractCreation>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetContractCreation,
+            address
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn storage_at_multi(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+        slots: Vec<Hex<U256>>,
+        block: Option<BlockId>,
+    ) -> BoxFuture<EvmResult<Vec<Hex<H256>>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetStorageAtMulti,
+            address,
+            slots,
+            block
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn receipt_with_trace(
         &self,
         meta: Self::Metadata,
         tx_hash: Hex<H256>,
         traces: Vec<String>,
-        meta_info: Option<TraceMeta>,
-    ) -> BoxFuture<EvmResult<Option<trace::TraceResultsWithTransactionHash>>> {
+    ) -> BoxFuture<EvmResult<Option<RPCReceiptWithTrace>>> {
         Box::pin(ready(proxy_evm_rpc!(
             meta.rpc_client,
-            EthTraceReplayTransaction,
+            VelasGetReceiptWithTrace,
             tx_hash,
-            traces,
-            meta_info
+            traces
         )))
     }
 
-    #[instrument]
-    fn trace_replay_block(
+    #[instrument(skip(self, meta))]
+    fn blocks_by_hashes(
         &self,
         meta: Self::Metadata,
-        block: BlockId,
-        traces: Vec<String>,
-        meta_info: Option<TraceMeta>,
-    ) -> BoxFuture<EvmResult<Vec<trace::TraceResultsWithTransactionHash>>> {
+        block_hashes: Vec<Hex<H256>>,
+        full: bool,
+    ) -> BoxFuture<EvmResult<Vec<Option<RPCBlock>>>> {
+        Box::pin(ready(
+            proxy_evm_rpc!(meta.rpc_client, VelasGetBlocksByHashes, block_hashes, full).map(
+                |blocks: Vec<Option<_>>| {
+                    blocks
+                        .into_iter()
+                        .map(|o| o.map(compatibility::patch_block))
+                        .collect()
+                },
+            ),
+        ))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn estimate_gas_range(
+        &self,
+        meta: Self::Metadata,
+        tx: RPCTransaction,
+        block: Option<BlockId>,
+    ) -> BoxFuture<EvmResult<RPCGasRange>> {
         Box::pin(ready(proxy_evm_rpc!(
             meta.rpc_client,
-            EthTraceReplayBlock,
-            block,
-            traces,
-            meta_info
+            VelasEstimateGasRange,
+            tx,
+            block
         )))
     }
-}
 
-pub(crate) fn from_client_error(client_error: ClientError) -> evm_rpc::Error {
-    let client_error_kind = client_error.kind();
-    match client_error_kind {
-        ClientErrorKind::RpcError(solana_client::rpc_request::RpcError::RpcResponseError {
-            code,
-            message,
-            data,
-            original_err,
-        }) => {
-            match data {
-                // if transaction preflight, try to get last log messages, and return it as error.
-                RpcResponseErrorData::SendTransactionPreflightFailure(
-                    RpcSimulateTransactionResult {
-                        err: Some(TransactionError::InstructionError(_, _)),
-                        logs: Some(logs),
-                        ..
-                    },
+    #[instrument(skip(self, meta))]
+    fn transaction_position(
+        &self,
+        meta: Self::Metadata,
+        tx_hash: Hex<H256>,
+    ) -> BoxFuture<EvmResult<Option<RPCTransactionPosition>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetTransactionPosition,
+            tx_hash
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn effective_gas_price(
+        &self,
+        meta: Self::Metadata,
+        tx_hash: Hex<H256>,
+    ) -> BoxFuture<EvmResult<Option<Hex<Gas>>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetEffectiveGasPrice,
+            tx_hash
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn block_hashes_by_range(
+        &self,
+        meta: Self::Metadata,
+        from: BlockId,
+        to: BlockId,
+    ) -> BoxFuture<EvmResult<Vec<Hex<H256>>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetBlockHashesByRange,
+            from,
+            to
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn replay_block_transactions_with_results(
+        &self,
+        meta: Self::Metadata,
+        block: BlockId,
+    ) -> BoxFuture<EvmResult<Vec<RPCTransactionReplay>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasReplayBlockTransactionsWithResults,
+            block
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn logs_delta(
+        &self,
+        meta: Self::Metadata,
+        log_filter: RPCLogFilter,
+        since_block: Hex<u64>,
+    ) -> BoxFuture<EvmResult<RPCLogsDelta>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetLogsDelta,
+            log_filter,
+            since_block
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn code_size(
+        &self,
+        meta: Self::Metadata,
+        address: Hex<Address>,
+        block: Option<BlockId>,
+    ) -> BoxFuture<EvmResult<Hex<usize>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetCodeSize,
+            address,
+            block
+        )))
+    }
+
+    #[instrument(skip(self, meta))]
+    fn non_empty_blocks(
+        &self,
+        meta: Self::Metadata,
+        from: BlockId,
+        to: BlockId,
+    ) -> BoxFuture<EvmResult<RPCNonEmptyBlocks>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            VelasGetNonEmptyBlocks,
+            from,
+            to
+        )))
+    }
+}
+
+#[derive(Debug)]
+pub struct TxPoolErpcImpl;
+impl TxPoolERPC for TxPoolErpcImpl {
+    type Metadata = Arc<EvmBridge>;
+
+    fn status(&self, meta: Self::Metadata) -> EvmResult<RPCTxPoolStatus> {
+        Ok(meta.pool.status())
+    }
+
+    fn content(&self, meta: Self::Metadata) -> EvmResult<RPCTxPoolContent> {
+        Ok(meta.pool.content())
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceErpcProxy;
+impl TraceERPC for TraceErpcProxy {
+    type Metadata = Arc<EvmBridge>;
+
+    #[instrument]
+    fn trace_call(
+        &self,
+        meta: Self::Metadata,
+        tx: RPCTransaction,
+        traces: Vec<String>,
+        block: Option<BlockId>,
+        meta_info: Option<TraceMeta>,
+    ) -> BoxFuture<EvmResult<evm_rpc::trace::TraceResultsWithTransactionHash>> {
+        Box::pin(ready(proxy_evm_rpc!(meta.rpc_client, EthTraceCall, tx, traces, block, meta_info)))
+    }
+
+    #[instrument]
+    fn trace_call_many(
+        &self,
+        meta: Self::Metadata,
+        tx_traces: Vec<(RPCTransaction, Vec<String>, Option<TraceMeta>)>,
+        block: Option<BlockId>,
+    ) -> BoxFuture<EvmResult<Vec<evm_rpc::trace::TraceResultsWithTransactionHash>>> {
+        Box::pin(ready(proxy_evm_rpc!(meta.rpc_client, EthTraceCallMany, tx_traces, block)))
+    }
+
+    #[instrument]
+    fn trace_replay_transaction(
+        &self,
+        meta: Self::Metadata,
+        tx_hash: Hex<H256>,
+        traces: Vec<String>,
+        meta_info: Option<TraceMeta>,
+    ) -> BoxFuture<EvmResult<Option<trace::TraceResultsWithTransactionHash>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            EthTraceReplayTransaction,
+            tx_hash,
+            traces,
+            meta_info
+        )))
+    }
+
+    #[instrument]
+    fn trace_replay_block(
+        &self,
+        meta: Self::Metadata,
+        block: BlockId,
+        traces: Vec<String>,
+        meta_info: Option<TraceMeta>,
+    ) -> BoxFuture<EvmResult<Vec<trace::TraceResultsWithTransactionHash>>> {
+        Box::pin(ready(proxy_evm_rpc!(
+            meta.rpc_client,
+            EthTraceReplayBlock,
+            block,
+            traces,
+            meta_info
+        )))
+    }
+}
+
+pub(crate) fn from_client_error(client_error: ClientError) -> evm_rpc::Error {
+    let client_error_kind = client_error.kind();
+    match client_error_kind {
+        ClientErrorKind::RpcError(solana_client::rpc_request::RpcError::RpcResponseError {
+            code,
+            message,
+            data,
+            original_err,
+        }) => {
+            match data {
+                // if transaction preflight, try to get last log messages, and return it as error.
+                RpcResponseErrorData::SendTransactionPreflightFailure(
+                    RpcSimulateTransactionResult {
+                        err: Some(TransactionError::InstructionError(_, _)),
+                        logs: Some(logs),
+                        ..
+                    },
                 ) if !logs.is_empty() => {
                     let first_entry = logs.len().saturating_sub(2); // take two elements from logs
                     let last_log = logs[first_entry..].join(";");
@@ -1026,6 +1760,19 @@ struct Args {
     evm_chain_id: u64,
     #[structopt(long = "min-gas-price")]
     min_gas_price: Option<String>,
+    /// Percent by which gas price should be increased to replace pending transaction with same nonce.
+    #[structopt(long = "replacement-bump-percent", default_value = "10")]
+    replacement_bump_percent: u64,
+    /// Tip in wei, returned by eth_maxPriorityFeePerGas.
+    #[structopt(long = "max-priority-fee", default_value = "0")]
+    max_priority_fee: u64,
+    /// Number of latest blocks, which transactions are sampled by eth_gasPrice oracle.
+    /// Set 0 to always return min gas price.
+    #[structopt(long = "gas-oracle-blocks", default_value = "20")]
+    gas_oracle_blocks: u64,
+    /// Percentile of sampled gas prices, returned by eth_gasPrice.
+    #[structopt(long = "gas-oracle-percentile", default_value = "60")]
+    gas_oracle_percentile: f64,
     #[structopt(long = "verbose-errors")]
     verbose_errors: bool,
     #[structopt(long = "no-simulate")]
@@ -1036,17 +1783,124 @@ struct Args {
 
     #[structopt(long = "jaeger-collector-url", short = "j")]
     jaeger_collector_url: Option<String>,
+
+    /// How much raw transaction nonce can be behind the latest on-chain nonce.
+    #[structopt(long = "nonce-tolerance", default_value = "0")]
+    nonce_tolerance: u64,
+
+    /// Return error on eth_call to address without code, instead of empty result.
+    #[structopt(long = "strict-call")]
+    strict_call: bool,
+
+    /// Development mode: expose an account controlled by the well-known dummy key.
+    #[structopt(long = "dev")]
+    dev: bool,
+
+    /// File with EVM secret keys of accounts managed by bridge,
+    /// as JSON array of hex strings, or one hex string per line.
+    #[structopt(long = "evm-keyfile")]
+    evm_keyfile: Option<String>,
+
+    /// Send a self-transfer from managed account at startup, and wait for its confirmation.
+    #[structopt(long = "self-test")]
+    self_test: bool,
+
+    /// Same as --self-test, but exit if the self-test fails.
+    #[structopt(long = "require-self-test")]
+    require_self_test: bool,
+
+    /// Enable eth_sign, eth_signTransaction and eth_sendTransaction with managed accounts.
+    /// Anyone with access to rpc can sign with these keys, so don't enable it on public endpoints.
+    #[structopt(long = "enable-managed-signing")]
+    enable_managed_signing: bool,
+
+    /// Maximum size in bytes of web3_sha3 and eth_sign input.
+    #[structopt(long = "max-input-size", default_value = "1048576")]
+    max_input_size: usize,
+
+    /// Maximum size in bytes of input (calldata) of transactions,
+    /// sent by eth_sendRawTransaction and eth_sendTransaction.
+    #[structopt(long = "max-tx-input-bytes", default_value = "262144")]
+    max_tx_input_bytes: usize,
+
+    /// Number of rounds of sending transaction chunks, failed chunks are re-signed before next round.
+    #[structopt(long = "send-retries", default_value = "5")]
+    send_retries: usize,
+
+    /// Number of status checks of transaction chunks in each round of sending.
+    #[structopt(long = "status-retries", default_value = "15")]
+    status_retries: usize,
+
+    /// Delay in milliseconds between status checks in the first round of sending,
+    /// doubled in each next round, with random jitter.
+    #[structopt(long = "send-retry-base-delay", default_value = "500")]
+    send_retry_base_delay: u64,
+
+    /// Maximum number of transactions in mempool, the cheapest are evicted above it.
+    #[structopt(long = "max-pool-size", default_value = "4096")]
+    max_pool_size: usize,
+
+    /// Accept raw transactions without chain id in signature (pre EIP-155),
+    /// which can be replayed on other chains.
+    #[structopt(long = "allow-unprotected-txs")]
+    allow_unprotected_txs: bool,
+
+    /// Compress http responses with gzip or deflate, if client supports it.
+    #[structopt(long = "enable-compression")]
+    enable_compression: bool,
+
+    /// Minimal size of http response in bytes, that should be compressed.
+    #[structopt(long = "compression-min-size", default_value = "1024")]
+    compression_min_size: usize,
+
+    /// Reject requests with oversized ids, and batches that reuse the same id.
+    #[structopt(long = "strict-request-ids")]
+    strict_request_ids: bool,
+
+    /// Maximum time in milliseconds to process a call, longer calls return DeadlineExceeded error.
+    #[structopt(long = "request-deadline")]
+    request_deadline: Option<u64>,
+
+    /// Warn at startup about managed accounts with balance below this amount in wei.
+    /// Defaults to the cost of a simple transfer at minimal gas price.
+    #[structopt(long = "min-account-balance", parse(try_from_str = U256::from_dec_str))]
+    min_account_balance: Option<U256>,
+
+    /// Repeat the balance check of managed accounts every this many seconds.
+    #[structopt(long = "balance-check-interval")]
+    balance_check_interval: Option<u64>,
+
+    /// Remove filters created by eth_newFilter and similar methods,
+    /// if they weren't polled for this many seconds.
+    #[structopt(long = "filter-ttl", default_value = "300")]
+    filter_ttl: u64,
+
+    /// Maximum number of filters created by eth_newFilter and similar methods.
+    #[structopt(long = "max-filters", default_value = "10000")]
+    max_filters: usize,
+
+    /// Maximum number of eth_subscribe subscriptions of single websocket connection.
+    #[structopt(long = "max-subscriptions-per-connection", default_value = "100")]
+    max_subscriptions_per_connection: usize,
+
+    /// Maximum number of eth_subscribe subscriptions of all websocket connections.
+    #[structopt(long = "max-subscriptions", default_value = "10000")]
+    max_subscriptions: usize,
+
+    /// Serve last successful responses of read methods not older than this many seconds,
+    /// while upstream node is unavailable. Age of stale value is returned in `X-Velas-Stale` header.
+    #[structopt(long = "stale-reads-max-age")]
+    stale_reads_max_age: Option<u64>,
+
+    /// Send each of identical concurrent read requests to upstream node,
+    /// instead of sharing response of the first one.
+    #[structopt(long = "no-coalesce-reads")]
+    no_coalesce_reads: bool,
 }
 
 impl Args {
     fn min_gas_price_or_default(&self) -> U256 {
         let gwei: U256 = 1_000_000_000.into();
-        fn min_gas_price() -> U256 {
-            //TODO: Add gas logic
-            (21000 * solana_evm_loader_program::scope::evm::LAMPORTS_TO_GWEI_PRICE
-                / DEFAULT_TARGET_LAMPORTS_PER_SIGNATURE)
-                .into() // 21000 is smallest call in evm
-        }
 
         let mut gas_price = match self
             .min_gas_price
@@ -1058,7 +1912,7 @@ impl Args {
                 gas_price
             }
             None => {
-                let default_price = min_gas_price();
+                let default_price = GasPriceConfig::default().default_gas_price;
                 warn!(
                     r#"Value of "--min-gas-price" is not set or unable to parse. Default value is: {}"#,
                     default_price
@@ -1070,60 +1924,148 @@ impl Args {
         gas_price += gwei - 1;
         gas_price - gas_price % gwei
     }
+
+    fn min_account_balance(&self) -> U256 {
+        self.min_account_balance
+            .unwrap_or_else(|| self.min_gas_price_or_default() * SIMPLE_TRANSFER_GAS)
+    }
+
+    fn gas_price_config(&self) -> GasPriceConfig {
+        GasPriceConfig {
+            default_gas_price: self.min_gas_price_or_default(),
+            replacement_bump_percent: self.replacement_bump_percent,
+            max_priority_fee: self.max_priority_fee.into(),
+            oracle_blocks: self.gas_oracle_blocks,
+            oracle_percentile: self.gas_oracle_percentile,
+        }
+    }
+
+    /// Returns EVM keys managed by bridge, loaded from `--evm-keyfile`.
+    /// Without keyfile, dummy key is used, but it is publicly known, so it is only allowed in `--dev` mode.
+    fn evm_keys(&self) -> StdResult<Vec<evm::SecretKey>, anyhow::Error> {
+        if let Some(path) = &self.evm_keyfile {
+            let keys = load_evm_keys(path)?;
+            for secret_key in &keys {
+                let public_key =
+                    evm_state::PublicKey::from_secret_key(evm_state::SECP256K1, secret_key);
+                info!(
+                    "Bridge manages account {:?}",
+                    evm_state::addr_from_public_key(&public_key)
+                );
+            }
+            if !keys.is_empty() {
+                return Ok(keys);
+            }
+        }
+        if !self.dev {
+            return Ok(vec![]);
+        }
+        let secret_key = evm::SecretKey::from_slice(&SECRET_KEY_DUMMY).unwrap();
+        let public_key = evm_state::PublicKey::from_secret_key(evm_state::SECP256K1, &secret_key);
+        warn!(
+            "DEV MODE: bridge manages account {:?} with the well-known dummy private key, never fund this address!",
+            evm_state::addr_from_public_key(&public_key)
+        );
+        Ok(vec![secret_key])
+    }
 }
 
 const SECRET_KEY_DUMMY: [u8; 32] = [1; 32];
 
+/// Reads secp256k1 secret keys from file, either as JSON array of hex strings,
+/// or one hex string per line.
+fn load_evm_keys(path: &str) -> StdResult<Vec<evm::SecretKey>, anyhow::Error> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Unable to read EVM keyfile {}: {}", path, e))?;
+    let keys: Vec<String> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid JSON in EVM keyfile {}: {}", path, e))?
+    } else {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    keys.iter()
+        .enumerate()
+        .map(|(index, key)| {
+            let key = key.trim();
+            hex::decode(key.strip_prefix("0x").unwrap_or(key))
+                .ok()
+                .and_then(|bytes| evm::SecretKey::from_slice(&bytes).ok())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Invalid secret key #{} in EVM keyfile {}", index + 1, path)
+                })
+        })
+        .collect()
+}
+
 #[paw::main]
 #[tokio::main]
 async fn main(args: Args) -> StdResult<(), Box<dyn std::error::Error>> {
     env_logger::init();
-    let min_gas_price = args.min_gas_price_or_default();
+    let gas_price_config = args.gas_price_config();
+    let min_account_balance = args.min_account_balance();
     let keyfile_path = args
         .keyfile
         .unwrap_or_else(|| solana_cli_config::Config::default().keypair_path);
     let server_path = args.rpc_address;
     let binding_address = args.binding_address;
 
+    let tracing_enabled = args.jaeger_collector_url.is_some();
     if let Some(collector) = args.jaeger_collector_url {
-        // init tracer
-        let fmt_filter = std::env::var("RUST_LOG")
-            .ok()
-            .and_then(|rust_log| match rust_log.parse::<Targets>() {
-                Ok(targets) => Some(targets),
-                Err(e) => {
-                    eprintln!("failed to parse `RUST_LOG={:?}`: {}", rust_log, e);
-                    None
-                }
-            })
-            .unwrap_or_else(|| Targets::default().with_default(LevelFilter::WARN));
-
-        let tracer = opentelemetry_jaeger::new_pipeline()
-            .with_service_name("evm-bridge-tracer")
-            .with_collector_endpoint(collector)
-            .install_batch(opentelemetry::runtime::Tokio)
-            .unwrap();
-        let opentelemetry = tracing_opentelemetry::layer().with_tracer(tracer);
-        let registry = tracing_subscriber::registry()
-            .with(tracing_subscriber::fmt::layer().with_filter(fmt_filter))
-            .with(opentelemetry);
-
-        registry.try_init().unwrap();
+        tracer::init(collector)?;
     }
 
-    let meta = EvmBridge::new(
+    let evm_keys = args.evm_keys()?;
+    let mut meta = EvmBridge::new(
         args.evm_chain_id,
         &keyfile_path,
-        vec![evm::SecretKey::from_slice(&SECRET_KEY_DUMMY).unwrap()],
+        evm_keys,
         server_path,
         args.verbose_errors,
         !args.no_simulate, // invert argument
         args.max_logs_blocks,
-        min_gas_price,
+        gas_price_config,
+    );
+    meta.nonce_tolerance = args.nonce_tolerance;
+    meta.strict_call = args.strict_call;
+    meta.managed_signing = args.enable_managed_signing;
+    meta.allow_unprotected_txs = args.allow_unprotected_txs;
+    meta.max_input_size = args.max_input_size;
+    meta.max_tx_input_bytes = args.max_tx_input_bytes;
+    meta.send_retry_config = SendRetryConfig {
+        send_retries: args.send_retries,
+        status_retries: args.status_retries,
+        base_delay: Duration::from_millis(args.send_retry_base_delay),
+    };
+    meta.pool.set_max_pool_size(args.max_pool_size);
+    meta.filters = Filters::new(Duration::from_secs(args.filter_ttl), args.max_filters);
+    meta.subscriptions = Subscriptions::new(
+        args.max_subscriptions_per_connection,
+        args.max_subscriptions,
     );
     let meta = Arc::new(meta);
 
-    let mut io = MetaIoHandler::default();
+    if meta.managed_signing {
+        check_account_balances(&meta, min_account_balance);
+        if let Some(interval) = args.balance_check_interval {
+            tokio::task::spawn(worker_balance_check(
+                meta.clone(),
+                min_account_balance,
+                Duration::from_secs(interval),
+            ));
+        }
+    }
+
+    let mut io = MetaIoHandler::with_middleware((
+        RequestIdValidation::new(args.strict_request_ids),
+        StaleReads::new(args.stale_reads_max_age.map(Duration::from_secs)),
+        RequestDeadline::new(args.request_deadline.map(Duration::from_millis)),
+        CoalescedReads::new(!args.no_coalesce_reads),
+    ));
 
     {
         use solana_core::rpc::rpc_minimal::Minimal;
@@ -1144,6 +2086,12 @@ async fn main(args: Args) -> StdResult<(), Box<dyn std::error::Error>> {
     io.extend_with(ether_general.to_delegate());
     let ether_trace = TraceErpcProxy;
     io.extend_with(ether_trace.to_delegate());
+    let velas = VelasErpcProxy;
+    io.extend_with(velas.to_delegate());
+    let txpool = TxPoolErpcImpl;
+    io.extend_with(txpool.to_delegate());
+    let filters = FilterErpcImpl;
+    io.extend_with(filters.to_delegate());
 
     let mempool_worker = worker_deploy(meta.clone());
 
@@ -1151,9 +2099,30 @@ async fn main(args: Args) -> StdResult<(), Box<dyn std::error::Error>> {
 
     let signature_checker = worker_signature_checker(meta.clone());
 
+    let queue = worker_queue(meta.clone());
+
+    let reorg_tracker = worker_reorg_tracker(meta.clone());
+
+    let new_heads = worker_new_heads(meta.clone());
+
+    let pending_transactions = worker_pending_transactions(meta.clone());
+
+    let mempool_task = tokio::task::spawn(mempool_worker);
+
+    if args.self_test || args.require_self_test {
+        match self_test(meta.clone()).await {
+            Ok(hash) => info!("Self-test passed, transaction {:?} confirmed", hash),
+            Err(e) if args.require_self_test => {
+                error!("Self-test failed: {}", e);
+                std::process::exit(1);
+            }
+            Err(e) => warn!("Self-test failed: {}", e),
+        }
+    }
+
     info!("Creating server with: {}", binding_address);
     let meta_clone = meta.clone();
-    let server = ServerBuilder::with_meta_extractor(
+    let mut server_builder = ServerBuilder::with_meta_extractor(
         io.clone(),
         move |_req: &hyper::Request<hyper::Body>| meta_clone.clone(),
     )
@@ -1162,21 +2131,51 @@ async fn main(args: Args) -> StdResult<(), Box<dyn std::error::Error>> {
     ]))
     .threads(4)
     .cors_max_age(86400)
-    .start_http(&binding_address)
-    .expect("Unable to start EVM bridge server");
+    .max_request_body_size(compression::DEFAULT_MAX_BODY_SIZE);
+    if args.enable_compression || args.stale_reads_max_age.is_some() {
+        let mut middleware = if args.enable_compression {
+            info!(
+                "Compressing responses bigger than {} bytes",
+                args.compression_min_size
+            );
+            compression::CompressionMiddleware::new(
+                io.clone(),
+                meta.clone(),
+                args.compression_min_size,
+            )
+        } else {
+            compression::CompressionMiddleware::uncompressed(io.clone(), meta.clone())
+        };
+        if let Some(max_age) = args.stale_reads_max_age {
+            info!("Serving stale reads up to {} seconds old", max_age);
+            middleware = middleware.with_stale_header();
+        }
+        server_builder = server_builder.request_middleware(middleware);
+    }
+    let server = server_builder
+        .start_http(&binding_address)
+        .expect("Unable to start EVM bridge server");
 
     let ws_server = {
         let mut websocket_binding = binding_address;
         websocket_binding.set_port(binding_address.port() + 1);
         info!("Creating websocket server: {}", websocket_binding);
-        jsonrpc_ws_server::ServerBuilder::with_meta_extractor(io, move |_: &_| meta.clone())
-            .start(&websocket_binding)
-            .expect("Unable to start EVM bridge server")
+        jsonrpc_ws_server::ServerBuilder::with_meta_extractor(
+            pubsub::ws_io(io),
+            move |context: &jsonrpc_ws_server::RequestContext| {
+                WsMetadata::new(meta.clone(), context)
+            },
+        )
+        .start(&websocket_binding)
+        .expect("Unable to start EVM bridge server")
     };
 
     let _cleaner = tokio::task::spawn(cleaner);
     let _signature_checker = tokio::task::spawn(signature_checker);
-    let mempool_task = tokio::task::spawn(mempool_worker);
+    let _queue = tokio::task::spawn(queue);
+    let _reorg_tracker = tokio::task::spawn(reorg_tracker);
+    let _new_heads = tokio::task::spawn(new_heads);
+    let _pending_transactions = tokio::task::spawn(pending_transactions);
     let servers_waiter = tokio::task::spawn_blocking(|| {
         ws_server.wait().unwrap();
         server.wait();
@@ -1191,18 +2190,58 @@ async fn main(args: Args) -> StdResult<(), Box<dyn std::error::Error>> {
             println!("Mempool task exited.");
         }
     };
+    if tracing_enabled {
+        // exporter runs on this runtime, so it should not be blocked while flushing
+        tokio::task::block_in_place(tracer::shutdown);
+    }
     Ok(())
 }
 
+/// Retry policy of `send_and_confirm_transactions`.
+#[derive(Debug, Clone, Copy)]
+pub struct SendRetryConfig {
+    /// Rounds of sending, unconfirmed transactions are re-signed with new blockhash before next round.
+    pub send_retries: usize,
+    /// Status checks of sent transactions in each round.
+    pub status_retries: usize,
+    /// Delay between status checks in the first round, doubled in each next round.
+    pub base_delay: Duration,
+}
+
+impl Default for SendRetryConfig {
+    fn default() -> Self {
+        Self {
+            send_retries: 5,
+            status_retries: 15,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl SendRetryConfig {
+    /// Delay between status checks in `round`, without jitter.
+    fn round_delay(&self, round: usize) -> Duration {
+        let factor = 1u32.checked_shl(round as u32).unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .unwrap_or(MAX_SEND_RETRY_DELAY)
+            .min(MAX_SEND_RETRY_DELAY)
+    }
+}
+
+/// Adds random jitter up to half of `delay`, so retries of concurrent senders spread out.
+fn with_jitter(delay: Duration) -> Duration {
+    delay + delay.mul_f64(rand::random::<f64>() / 2.0)
+}
+
 fn send_and_confirm_transactions<T: Signers>(
     rpc_client: &RpcClient,
     mut transactions: Vec<solana::Transaction>,
     signer_keys: &T,
+    retry: &SendRetryConfig,
 ) -> StdResult<(), anyhow::Error> {
-    const SEND_RETRIES: usize = 5;
-    const STATUS_RETRIES: usize = 15;
-
-    for _ in 0..SEND_RETRIES {
+    for round in 0..retry.send_retries {
+        let delay = retry.round_delay(round);
         // Send all transactions
         let mut transactions_signatures = transactions
             .drain(..)
@@ -1231,12 +2270,12 @@ fn send_and_confirm_transactions<T: Signers>(
             })
             .collect::<Vec<_>>();
 
-        for _ in 0..STATUS_RETRIES {
+        for _ in 0..retry.status_retries {
             // Collect statuses for all the transactions, drop those that are confirmed
 
             if cfg!(not(test)) {
-                // Retry twice a second
-                sleep(Duration::from_millis(500));
+                // Back off more in each round, when network is congested
+                sleep(with_jitter(delay));
             }
 
             transactions_signatures.retain(|(_transaction, signature)| {
@@ -1268,33 +2307,1195 @@ fn send_and_confirm_transactions<T: Signers>(
 
 #[cfg(test)]
 mod tests {
-    use crate::{BridgeErpcImpl, EthPool, EvmBridge, SystemClock};
-    use evm_rpc::{BridgeERPC, Hex};
-    use evm_state::Address;
+    use crate::{
+        check_precompile_call, self_test, Args, BridgeErpcImpl, ChainErpcProxy, EthPool, EvmBridge,
+        Filters, GasOracle, GeneralErpcProxy, PooledTransaction, SendRetryConfig, Subscriptions,
+        SystemClock, TxPoolErpcImpl, VelasErpcProxy, DEFAULT_MAX_INPUT_SIZE,
+        DEFAULT_MAX_TX_INPUT_BYTES, SECRET_KEY_DUMMY,
+    };
+    use evm_rpc::{
+        txpool::TxPoolERPC, AccountOverride, BlockId, BlockRelId, BridgeERPC, Bytes, ChainERPC,
+        Either, Error, GasPriceConfig, GeneralERPC, Hex, RPCBlock, RPCLog, RPCLogFilter,
+        RPCLogsCount, RPCReceipt, RPCTransaction, RPCTransactionStatus, StateOverrides, VelasERPC,
+    };
+    use evm_state::{Address, H160, H256, U256};
     use secp256k1::SecretKey;
-    use solana_client::rpc_client::RpcClient;
-    use solana_sdk::signature::Keypair;
+    use solana_client::{mock_sender::Mocks, rpc_client::RpcClient, rpc_request::RpcRequest};
+    use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+    use std::collections::HashSet;
     use std::str::FromStr;
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
+    use structopt::StructOpt;
 
-    #[test]
-    fn test_eth_sign() {
-        let signing_key =
-            SecretKey::from_str("c21020a52198632ae7d5c1adaa3f83da2e0c98cf541c54686ddc8d202124c086")
-                .unwrap();
-        let public_key = evm_state::PublicKey::from_secret_key(evm_state::SECP256K1, &signing_key);
-        let public_key = evm_state::addr_from_public_key(&public_key);
-        let bridge = Arc::new(EvmBridge {
+    fn test_bridge(accounts: Vec<SecretKey>, rpc_client: RpcClient) -> EvmBridge {
+        let accounts = accounts
+            .into_iter()
+            .map(|secret_key| {
+                let public_key =
+                    evm_state::PublicKey::from_secret_key(evm_state::SECP256K1, &secret_key);
+                (evm_state::addr_from_public_key(&public_key), secret_key)
+            })
+            .collect();
+        EvmBridge {
             evm_chain_id: 111u64,
             key: Keypair::new(),
-            accounts: vec![(public_key, signing_key)].into_iter().collect(),
-            rpc_client: RpcClient::new("".to_string()),
+            accounts,
+            rpc_client,
             verbose_errors: true,
             simulate: false,
             max_logs_blocks: 0u64,
             pool: EthPool::new(SystemClock),
-            min_gas_price: 0.into(),
-        });
+            gas_price_config: GasPriceConfig {
+                default_gas_price: 0.into(),
+                replacement_bump_percent: 0,
+                max_priority_fee: 0.into(),
+                oracle_blocks: 0,
+                oracle_percentile: GasPriceConfig::DEFAULT_ORACLE_PERCENTILE,
+            },
+            nonce_tolerance: 0,
+            strict_call: false,
+            managed_signing: false,
+            allow_unprotected_txs: false,
+            max_input_size: DEFAULT_MAX_INPUT_SIZE,
+            max_tx_input_bytes: DEFAULT_MAX_TX_INPUT_BYTES,
+            send_retry_config: SendRetryConfig::default(),
+            reorg_tracker: Mutex::default(),
+            gas_oracle: GasOracle::default(),
+            subscriptions: Subscriptions::default(),
+            filters: Filters::default(),
+        }
+    }
+
+    pub(crate) fn signed_tx(nonce: u64) -> evm_state::Transaction {
+        signed_tx_for_chain(nonce, Some(111))
+    }
+
+    fn signed_tx_for_chain(nonce: u64, chain_id: Option<u64>) -> evm_state::Transaction {
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        evm_state::UnsignedTransaction {
+            nonce: nonce.into(),
+            gas_price: 0.into(),
+            gas_limit: 30000000.into(),
+            action: evm_state::TransactionAction::Create,
+            value: 0.into(),
+            input: vec![],
+        }
+        .sign(&secret_key, chain_id)
+    }
+
+    fn nonce_mocks(on_chain_nonce: u64) -> Mocks {
+        vec![(
+            RpcRequest::EthGetTransactionCount,
+            serde_json::to_value(Hex(U256::from(on_chain_nonce))).unwrap(),
+        )]
+        .into_iter()
+        .collect()
+    }
+
+    fn check_nonce(bridge: &EvmBridge, nonce: u64) -> Result<(), Error> {
+        let tx = signed_tx(nonce);
+        bridge.check_nonce(&tx, tx.caller().unwrap())
+    }
+
+    #[test]
+    fn test_nonce_too_low() {
+        let bridge = mocked_bridge(nonce_mocks(5));
+        let err = check_nonce(&bridge, 4).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::NonceTooLow { got, expected } if got == 4.into() && expected == 5.into()
+        ));
+
+        let mut bridge = mocked_bridge(nonce_mocks(5));
+        bridge.nonce_tolerance = 1;
+        check_nonce(&bridge, 4).unwrap();
+    }
+
+    fn raw_tx(tx: &evm_state::Transaction) -> Bytes {
+        Bytes(rlp::encode(tx).to_vec())
+    }
+
+    #[test]
+    fn test_check_precompile_call() {
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let call = |address, input| {
+            evm_state::UnsignedTransaction {
+                nonce: 0.into(),
+                gas_price: 0.into(),
+                gas_limit: 30000000.into(),
+                action: evm_state::TransactionAction::Call(address),
+                value: 0.into(),
+                input,
+            }
+            .sign(&secret_key, Some(111))
+        };
+        let precompile = *solana_evm_loader_program::precompiles::ETH_TO_VLX_ADDR;
+
+        // transferToNative(bytes32) selector, followed by recipient
+        let mut input = vec![0xb1, 0xd6, 0x92, 0x7a];
+        input.extend_from_slice(&Pubkey::new_unique().to_bytes());
+        check_precompile_call(&call(precompile, input.clone())).unwrap();
+
+        let mut wrong_selector = input.clone();
+        wrong_selector[0] = 0;
+        for malformed in vec![vec![], input[..20].to_vec(), wrong_selector] {
+            let err = check_precompile_call(&call(precompile, malformed)).unwrap_err();
+            assert!(matches!(
+                err,
+                Error::InvalidPrecompileCall { address, .. } if address == precompile
+            ));
+        }
+
+        // other contracts are not checked
+        check_precompile_call(&call(H160::repeat_byte(0x11), vec![1, 2, 3])).unwrap();
+    }
+
+    #[test]
+    fn test_check_chain_id() {
+        let mut bridge = mocked_bridge(Mocks::default());
+        bridge.check_chain_id(&signed_tx(0)).unwrap();
+
+        let err = bridge
+            .check_chain_id(&signed_tx_for_chain(0, Some(1)))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::WrongChainId {
+                chain_id: 111,
+                tx_chain_id: Some(1)
+            }
+        ));
+
+        let unprotected = signed_tx_for_chain(0, None);
+        let err = bridge.check_chain_id(&unprotected).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::WrongChainId {
+                chain_id: 111,
+                tx_chain_id: None
+            }
+        ));
+        bridge.allow_unprotected_txs = true;
+        bridge.check_chain_id(&unprotected).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_gas_price() {
+        let mut bridge = mocked_bridge(nonce_mocks(0));
+        let min_gas_price = U256::from(3_000_000_000u64);
+        bridge.gas_price_config.default_gas_price = min_gas_price;
+        let bridge = Arc::new(bridge);
+        let send = |bytes| BridgeErpcImpl.send_raw_transaction(bridge.clone(), bytes, None);
+
+        // legacy transaction below minimal gas price
+        let err = send(raw_tx(&signed_tx(0))).await.unwrap_err();
+        assert!(matches!(err, Error::GasPriceTooLow { need } if need == min_gas_price));
+
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let tx = evm_state::UnsignedTransaction {
+            nonce: 0.into(),
+            gas_price: min_gas_price,
+            gas_limit: 30000000.into(),
+            action: evm_state::TransactionAction::Create,
+            value: 0.into(),
+            input: vec![],
+        }
+        .sign(&secret_key, Some(111));
+        let hash = send(raw_tx(&tx)).await.unwrap();
+        assert_eq!(hash.0, tx.tx_id_hash());
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_input_size() {
+        let mut bridge = mocked_bridge(nonce_mocks(0));
+        bridge.max_tx_input_bytes = 8;
+        let bridge = Arc::new(bridge);
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let tx_with_input = |input| {
+            evm_state::UnsignedTransaction {
+                nonce: 0.into(),
+                gas_price: 0.into(),
+                gas_limit: 30000000.into(),
+                action: evm_state::TransactionAction::Create,
+                value: 0.into(),
+                input,
+            }
+            .sign(&secret_key, Some(111))
+        };
+
+        let oversized = tx_with_input(vec![0; 9]);
+        let err = BridgeErpcImpl
+            .send_raw_transaction(bridge.clone(), raw_tx(&oversized), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InputTooLarge {
+                size: 9,
+                max_size: 8
+            }
+        ));
+        assert!(bridge
+            .pool
+            .transaction_by_hash(Hex(oversized.tx_id_hash()))
+            .is_none());
+
+        let at_limit = tx_with_input(vec![0; 8]);
+        let hash = BridgeErpcImpl
+            .send_raw_transaction(bridge.clone(), raw_tx(&at_limit), None)
+            .await
+            .unwrap();
+        assert_eq!(hash.0, at_limit.tx_id_hash());
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_wrong_chain_id() {
+        let bridge = Arc::new(mocked_bridge(nonce_mocks(0)));
+        let tx = signed_tx_for_chain(0, Some(1));
+        let err = BridgeErpcImpl
+            .send_raw_transaction(bridge.clone(), raw_tx(&tx), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::WrongChainId { .. }));
+        assert!(bridge
+            .pool
+            .transaction_by_hash(Hex(tx.tx_id_hash()))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_recovers_sender() {
+        let bridge = Arc::new(mocked_bridge(nonce_mocks(0)));
+        let tx = signed_tx(0);
+        let hash = BridgeErpcImpl
+            .send_raw_transaction(bridge, raw_tx(&tx), None)
+            .await
+            .unwrap();
+        assert_eq!(hash.0, tx.tx_id_hash());
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_unrecoverable_sender() {
+        let bridge = Arc::new(mocked_bridge(nonce_mocks(0)));
+        let mut tx = signed_tx(0);
+        tx.signature.r = H256::zero();
+        tx.signature.s = H256::zero();
+        let err = BridgeErpcImpl
+            .send_raw_transaction(bridge.clone(), raw_tx(&tx), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidParams { .. }));
+        assert!(bridge.pool.transaction_by_hash(Hex(tx.tx_id_hash())).is_none());
+    }
+
+    // chain id 111, nonce 7, 1/2 gwei fees, transfer of 1 VLX with one access list item
+    const EIP1559_RAW_TX: &str = "02f8ab6f07843b9aca008477359400825208941111111111111111111111111111111111111111880de0b6b3a764000080f838f7942222222222222222222222222222222222222222e1a0000000000000000000000000000000000000000000000000000000000000000101a0568350862b75d93cc35fd080c47cf3293c2e9f4372c9522e59ca081881069a18a04c9d1a730e574f61dcf3ef966b483ffde1c6238f55389304c2d2d777206489d6";
+    // chain id 111, nonce 3, 2 gwei gas price, erc20 transfer selector with two storage keys in access list
+    const EIP2930_RAW_TX: &str = "01f8c56f03847735940082c3509411111111111111111111111111111111111111118084a9059cbbf85bf859942222222222222222222222222222222222222222f842a00000000000000000000000000000000000000000000000000000000000000001a0000000000000000000000000000000000000000000000000000000000000000201a0f07ce9dcddc2d971a2faa32294d81d2fba028185954f97e8abe082c92bd5301ca0714bf9317596f83a60d0241e84eb06eaa0f2ddf561da970ec09965fb152a0226";
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_rejects_typed_transactions() {
+        let bridge = Arc::new(mocked_bridge(nonce_mocks(0)));
+        for (raw, tx_type) in &[(EIP2930_RAW_TX, "0x1"), (EIP1559_RAW_TX, "0x2")] {
+            let bytes = Bytes(hex::decode(raw).unwrap());
+            let err = BridgeErpcImpl
+                .send_raw_transaction(bridge.clone(), bytes, None)
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(&err, Error::InvalidParams { details } if details.contains(tx_type)),
+                "unexpected error {:?}",
+                err
+            );
+        }
+        assert_eq!(bridge.pool.size(), 0);
+    }
+
+    #[test]
+    fn test_decode_legacy_and_unsupported_transaction_types() {
+        let tx = signed_tx(0);
+        let bytes = raw_tx(&tx);
+        assert_eq!(crate::decode_raw_transaction(&bytes).unwrap(), tx);
+
+        // EIP-4844 blob transaction
+        let mut bytes = hex::decode(EIP1559_RAW_TX).unwrap();
+        bytes[0] = 0x03;
+        assert!(crate::decode_raw_transaction(&Bytes(bytes)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_by_hash_reports_legacy_type() {
+        // node stores every transaction in legacy form
+        let tx = signed_tx(0);
+        let hash = tx.tx_id_hash();
+        let stored = RPCTransaction::from_transaction(tx.into()).unwrap();
+        let mocks = vec![(
+            RpcRequest::EthGetTransactionByHash,
+            serde_json::to_value(&stored).unwrap(),
+        )]
+        .into_iter()
+        .collect();
+        let bridge = Arc::new(mocked_bridge(mocks));
+
+        let tx = ChainErpcProxy
+            .transaction_by_hash(bridge, Hex(hash))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.transaction_type, Some(Hex(0)));
+        assert_eq!(serde_json::to_value(&tx).unwrap()["type"], "0x0");
+    }
+
+    #[tokio::test]
+    async fn test_next_nonce_fills_gap() {
+        let bridge = Arc::new(mocked_bridge(nonce_mocks(5)));
+        let mut caller = None;
+        for nonce in &[3, 5, 6, 8] {
+            let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+            let tx = PooledTransaction::new(signed_tx(*nonce), HashSet::new(), sender).unwrap();
+            caller = Some(tx.caller().unwrap());
+            bridge.pool.import(tx).unwrap();
+        }
+        let caller = Hex(caller.unwrap());
+
+        let next = VelasErpcProxy
+            .next_nonce(bridge.clone(), caller)
+            .await
+            .unwrap();
+        assert_eq!(next.0, 7.into());
+        // pending count skips the gap
+        assert_eq!(bridge.pool.transaction_count(&caller.0), Some(9.into()));
+    }
+
+    #[test]
+    fn test_next_nonce_resets_after_drop() {
+        let bridge = mocked_bridge(nonce_mocks(6));
+        let mut hashes = vec![];
+        for nonce in &[5, 6, 7] {
+            let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+            let tx = PooledTransaction::new(signed_tx(*nonce), HashSet::new(), sender).unwrap();
+            hashes.push(tx.tx_id_hash());
+            bridge.pool.import(tx).unwrap();
+        }
+        let caller = signed_tx(5).caller().unwrap();
+        bridge.pool.update_state_nonce(&caller, 5.into());
+        assert_eq!(bridge.next_nonce(&caller), 8.into());
+
+        // dropped transaction leaves a gap, which is filled instead of extending the sequence
+        bridge.pool.remove(&hashes[0]).unwrap();
+        assert_eq!(bridge.pool.transaction_count(&caller), Some(8.into()));
+        assert_eq!(bridge.next_nonce(&caller), 5.into());
+
+        // transaction landed on chain through other node
+        assert_eq!(bridge.pool.resync_state_nonces(|_| Some(6.into())), 1);
+        assert_eq!(bridge.next_nonce(&caller), 8.into());
+
+        // remaining transactions are dropped, so state nonce is forgotten and fetched from chain
+        for hash in &hashes[1..] {
+            bridge.pool.remove(hash).unwrap();
+        }
+        assert_eq!(bridge.pool.resync_state_nonces(|_| unreachable!()), 0);
+        assert_eq!(bridge.pool.next_nonce(&caller), None);
+        assert_eq!(bridge.next_nonce(&caller), 6.into());
+        assert_eq!(bridge.pool.next_nonce(&caller), Some(6.into()));
+    }
+
+    #[test]
+    fn test_call_uses_pending_nonce() {
+        let bridge = mocked_bridge(nonce_mocks(5));
+        let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+        let pooled = PooledTransaction::new(signed_tx(5), HashSet::new(), sender).unwrap();
+        let caller = pooled.caller().unwrap();
+        bridge.pool.import(pooled).unwrap();
+
+        let call = |nonce: Option<u64>| -> RPCTransaction {
+            serde_json::from_value(serde_json::json!({
+                "from": Hex(caller),
+                "to": Hex(Address::repeat_byte(0x11)),
+                "nonce": nonce.map(|nonce| Hex(U256::from(nonce))),
+            }))
+            .unwrap()
+        };
+        let pending = Some(BlockId::RelativeId(BlockRelId::Pending));
+        let latest = Some(BlockId::RelativeId(BlockRelId::Latest));
+
+        let tx = bridge.with_pending_nonce(call(None), pending);
+        assert_eq!(tx.nonce, Some(Hex(6.into())));
+        // same nonce is assigned to the next sent transaction
+        assert_eq!(bridge.next_nonce(&caller), 6.into());
+        // explicit nonce and other blocks are left to the node
+        let tx = bridge.with_pending_nonce(call(Some(3)), pending);
+        assert_eq!(tx.nonce, Some(Hex(3.into())));
+        let tx = bridge.with_pending_nonce(call(None), latest);
+        assert_eq!(tx.nonce, None);
+    }
+
+    #[test]
+    fn test_nonce_exact_and_future() {
+        let bridge = mocked_bridge(nonce_mocks(5));
+        check_nonce(&bridge, 5).unwrap();
+
+        let bridge = mocked_bridge(nonce_mocks(5));
+        check_nonce(&bridge, 6).unwrap();
+    }
+
+    pub(crate) fn mocked_bridge(mocks: Mocks) -> EvmBridge {
+        test_bridge(
+            vec![],
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks),
+        )
+    }
+
+    pub(crate) fn test_log(block_number: u64, log_index: usize) -> RPCLog {
+        RPCLog {
+            removed: false,
+            log_index: Hex(log_index),
+            transaction_index: Hex(0),
+            transaction_hash: Hex(H256::repeat_byte(0x11)),
+            block_hash: Hex(H256::repeat_byte(0x22)),
+            block_number: Hex(U256::from(block_number)),
+            address: Hex(Address::repeat_byte(0x33)),
+            data: Bytes(vec![]),
+            topics: vec![],
+        }
+    }
+
+    fn range_filter(from: u64, to: u64) -> RPCLogFilter {
+        RPCLogFilter {
+            from_block: Some(from.into()),
+            to_block: Some(to.into()),
+            address: None,
+            topics: None,
+        }
+    }
+
+    #[test]
+    fn test_txpool_content() {
+        use serde_json::{json, Value};
+
+        let meta = Arc::new(mocked_bridge(Mocks::default()));
+        let mut io = jsonrpc_core::MetaIoHandler::default();
+        io.extend_with(TxPoolErpcImpl.to_delegate());
+
+        let caller = Hex(signed_tx(5).caller().unwrap());
+        meta.pool.update_state_nonce(&caller.0, 5.into());
+        let mut hashes = vec![];
+        for nonce in &[5, 6, 8] {
+            let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+            let tx = PooledTransaction::new(signed_tx(*nonce), HashSet::new(), sender).unwrap();
+            hashes.push(Hex(tx.tx_id_hash()));
+            meta.pool.import(tx).unwrap();
+        }
+
+        let call = |method: &str| -> Value {
+            let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": []});
+            let response = io
+                .handle_request_sync(&request.to_string(), meta.clone())
+                .unwrap();
+            let mut response: Value = serde_json::from_str(&response).unwrap();
+            assert!(response.get("error").is_none(), "{}: {}", method, response);
+            response["result"].take()
+        };
+
+        assert_eq!(
+            call("txpool_status"),
+            json!({"pending": "0x2", "queued": "0x1"})
+        );
+
+        let content = call("txpool_content");
+        let pending = &content["pending"][caller.to_string()];
+        assert_eq!(pending["5"]["hash"], json!(hashes[0]));
+        assert_eq!(pending["6"]["hash"], json!(hashes[1]));
+        // nonce 7 is missing, so the last one waits in queue
+        assert!(pending.get("8").is_none());
+        assert_eq!(
+            content["queued"][caller.to_string()]["8"]["hash"],
+            json!(hashes[2])
+        );
+    }
+
+    #[test]
+    fn test_uncles_always_empty() {
+        use serde_json::{json, Value};
+
+        let mocks: Mocks = vec![(
+            RpcRequest::EthGetBlockByNumber,
+            serde_json::to_value(RPCBlock::default()).unwrap(),
+        )]
+        .into_iter()
+        .collect();
+        let meta = Arc::new(mocked_bridge(mocks));
+        let mut io = jsonrpc_core::MetaIoHandler::default();
+        io.extend_with(ChainErpcProxy.to_delegate());
+
+        let call = |method: &str, params: Value| -> Value {
+            let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+            let response = io
+                .handle_request_sync(&request.to_string(), meta.clone())
+                .unwrap();
+            let mut response: Value = serde_json::from_str(&response).unwrap();
+            assert!(response.get("error").is_none(), "{}: {}", method, response);
+            response["result"].take()
+        };
+
+        let hash = Hex(H256::repeat_byte(0x11));
+        for params in [json!([hash, "0x0"]), json!([hash, "0x1"])] {
+            assert_eq!(call("eth_getUncleByBlockHashAndIndex", params), json!(null));
+        }
+        for params in [json!(["latest", "0x0"]), json!(["0x1", "0x1"])] {
+            assert_eq!(
+                call("eth_getUncleByBlockNumberAndIndex", params),
+                json!(null)
+            );
+        }
+        assert_eq!(
+            call("eth_getUncleCountByBlockHash", json!([hash])),
+            json!("0x0")
+        );
+        for params in [json!(["latest"]), json!(["0x1"])] {
+            assert_eq!(call("eth_getUncleCountByBlockNumber", params), json!("0x0"));
+        }
+
+        // blocks don't reference uncles either
+        for number in ["0x0", "0x1"] {
+            let block = call("eth_getBlockByNumber", json!([number, false]));
+            assert_eq!(block["uncles"], json!([]));
+            assert_eq!(
+                block["sha3Uncles"],
+                json!(Hex(evm_state::empty_ommers_hash()))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logs_count_proxied() {
+        let count = RPCLogsCount {
+            count: Hex(3),
+            capped: true,
+        };
+        let mocks: Mocks = vec![(
+            RpcRequest::VelasGetLogsCount,
+            serde_json::to_value(&count).unwrap(),
+        )]
+        .into_iter()
+        .collect();
+        let counted = VelasErpcProxy
+            .logs_count(Arc::new(mocked_bridge(mocks)), range_filter(1, 100))
+            .await
+            .unwrap();
+        assert_eq!(counted.count.0, 3);
+        assert!(counted.capped);
+    }
+
+    #[tokio::test]
+    async fn test_logs_for_contracts_merged_in_order() {
+        let first = Address::repeat_byte(0x33);
+        let second = Address::repeat_byte(0x44);
+        let mut second_log = test_log(1, 1);
+        second_log.address = Hex(second);
+        let logs = vec![test_log(2, 0), second_log, test_log(1, 0)];
+        let mocks: Mocks = vec![(RpcRequest::EthGetLogs, serde_json::to_value(&logs).unwrap())]
+            .into_iter()
+            .collect();
+
+        let mut bridge = mocked_bridge(mocks);
+        bridge.max_logs_blocks = 10;
+        let merged = VelasErpcProxy
+            .logs_for_contracts(
+                Arc::new(bridge),
+                vec![Hex(first), Hex(second)],
+                None,
+                Some(1.into()),
+                Some(2.into()),
+            )
+            .await
+            .unwrap();
+        let positions: Vec<_> = merged
+            .iter()
+            .map(|log| (log.block_number.0.as_u64(), log.log_index.0, log.address.0))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(1, 0, first), (1, 1, second), (2, 0, first)]
+        );
+
+        let mut bridge = mocked_bridge(Mocks::default());
+        bridge.max_logs_blocks = 10;
+        let bridge = Arc::new(bridge);
+        let too_wide = VelasErpcProxy
+            .logs_for_contracts(
+                bridge.clone(),
+                vec![Hex(first)],
+                None,
+                Some(1.into()),
+                Some(100.into()),
+            )
+            .await;
+        assert!(matches!(too_wide, Err(Error::InvalidBlocksRange { .. })));
+        let no_addresses = VelasErpcProxy
+            .logs_for_contracts(bridge, vec![], None, Some(1.into()), Some(2.into()))
+            .await;
+        assert!(matches!(no_addresses, Err(Error::InvalidParams { .. })));
+    }
+
+    #[test]
+    fn test_send_retry_delays_escalate() {
+        use crate::{with_jitter, MAX_SEND_RETRY_DELAY};
+        use std::time::Duration;
+
+        let retry = SendRetryConfig::default();
+        let delays: Vec<_> = (0..6).map(|round| retry.round_delay(round)).collect();
+        let expected: Vec<_> = vec![500, 1000, 2000, 4000, 8000, 8000]
+            .into_iter()
+            .map(Duration::from_millis)
+            .collect();
+        assert_eq!(delays, expected);
+        assert_eq!(retry.round_delay(100), MAX_SEND_RETRY_DELAY);
+        for delay in delays {
+            let jittered = with_jitter(delay);
+            assert!(jittered >= delay && jittered <= delay + delay / 2);
+        }
+
+        let args = Args::from_iter(&[
+            "evm-bridge",
+            "--send-retries",
+            "3",
+            "--send-retry-base-delay",
+            "100",
+        ]);
+        assert_eq!(args.send_retries, 3);
+        assert_eq!(args.status_retries, 15);
+        assert_eq!(args.send_retry_base_delay, 100);
+    }
+
+    #[test]
+    fn test_dummy_key_only_in_dev_mode() {
+        let args = Args::from_iter(&["evm-bridge"]);
+        assert!(args.evm_keys().unwrap().is_empty());
+
+        let args = Args::from_iter(&["evm-bridge", "--dev"]);
+        let dummy = SecretKey::from_slice(&SECRET_KEY_DUMMY).unwrap();
+        assert_eq!(args.evm_keys().unwrap(), vec![dummy]);
+    }
+
+    #[test]
+    fn test_evm_keys_from_file() {
+        let first = SecretKey::from_slice(&[2; 32]).unwrap();
+        let second = SecretKey::from_slice(&[3; 32]).unwrap();
+        let dir = std::env::temp_dir();
+        let lines_path = dir.join(format!("evm-keys-{}.txt", std::process::id()));
+        std::fs::write(
+            &lines_path,
+            format!("0x{}\n\n{}\n", hex::encode([2; 32]), hex::encode([3; 32])),
+        )
+        .unwrap();
+        let json_path = dir.join(format!("evm-keys-{}.json", std::process::id()));
+        std::fs::write(
+            &json_path,
+            serde_json::json!([hex::encode([2; 32]), hex::encode([3; 32])]).to_string(),
+        )
+        .unwrap();
+
+        for path in &[&lines_path, &json_path] {
+            let args = Args::from_iter(&[
+                "evm-bridge",
+                "--dev",
+                "--evm-keyfile",
+                path.to_str().unwrap(),
+            ]);
+            let keys = args.evm_keys().unwrap();
+            assert_eq!(keys, vec![first, second]);
+
+            let bridge = test_bridge(keys, RpcClient::new_mock("succeeds".to_string()));
+            assert_eq!(bridge.accounts.len(), 2);
+            for key in &[first, second] {
+                let public_key = evm_state::PublicKey::from_secret_key(evm_state::SECP256K1, key);
+                let address = evm_state::addr_from_public_key(&public_key);
+                assert_eq!(bridge.accounts.get(&address), Some(key));
+            }
+        }
+
+        std::fs::write(&lines_path, "0x1234\n").unwrap();
+        let args = Args::from_iter(&["evm-bridge", "--evm-keyfile", lines_path.to_str().unwrap()]);
+        assert!(args.evm_keys().is_err());
+
+        std::fs::remove_file(lines_path).unwrap();
+        std::fs::remove_file(json_path).unwrap();
+    }
+
+    #[test]
+    fn test_low_balance_of_managed_account() {
+        let dummy = SecretKey::from_slice(&SECRET_KEY_DUMMY).unwrap();
+        let bridge_with_balance = |balance: u64| {
+            let mocks = vec![(
+                RpcRequest::EthGetBalance,
+                serde_json::json!(Hex(U256::from(balance))),
+            )]
+            .into_iter()
+            .collect();
+            test_bridge(
+                vec![dummy],
+                RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks),
+            )
+        };
+        let min_balance = U256::from(21000);
+
+        let bridge = bridge_with_balance(1000);
+        let address = *bridge.accounts.keys().next().unwrap();
+        assert_eq!(
+            crate::check_account_balances(&bridge, min_balance),
+            vec![address]
+        );
+
+        let bridge = bridge_with_balance(21000);
+        assert!(crate::check_account_balances(&bridge, min_balance).is_empty());
+
+        // by default, account should be able to pay for a transfer
+        let args = Args::from_iter(&["evm-bridge", "--min-gas-price", "2000000000"]);
+        assert_eq!(
+            args.min_account_balance(),
+            U256::from(21000u64 * 2_000_000_000)
+        );
+        let args = Args::from_iter(&["evm-bridge", "--min-account-balance", "5"]);
+        assert_eq!(args.min_account_balance(), U256::from(5));
+    }
+
+    #[test]
+    fn test_gas_price_config_from_args() {
+        let args = Args::from_iter(&["evm-bridge"]);
+        assert_eq!(args.gas_price_config(), GasPriceConfig::default());
+
+        let args = Args::from_iter(&[
+            "evm-bridge",
+            "--min-gas-price",
+            "5000000000",
+            "--replacement-bump-percent",
+            "10",
+        ]);
+        let config = args.gas_price_config();
+        assert_eq!(config.default_gas_price, 5_000_000_000u64.into());
+        assert_eq!(config.replacement_bump_percent, 10);
+        assert_eq!(
+            config.replacement_gas_price(1000.into()),
+            U256::from(1100)
+        );
+    }
+
+    #[test]
+    fn test_gas_price_config_propagates() {
+        let gwei = 1_000_000_000u64;
+        let args = Args::from_iter(&[
+            "evm-bridge",
+            "--min-gas-price",
+            "1000000000",
+            "--replacement-bump-percent",
+            "20",
+            "--gas-oracle-blocks",
+            "3",
+            "--gas-oracle-percentile",
+            "60",
+        ]);
+        let config = args.gas_price_config();
+        assert_eq!(config.oracle_blocks, 3);
+        assert_eq!(config.oracle_percentile, 60.0);
+
+        let keypath = std::env::temp_dir().join(format!("bridge-key-{}.json", std::process::id()));
+        solana_sdk::signature::write_keypair_file(&Keypair::new(), &keypath).unwrap();
+        let mut bridge = EvmBridge::new(
+            111,
+            keypath.to_str().unwrap(),
+            vec![],
+            "http://127.0.0.1:8899".to_string(),
+            true,
+            false,
+            0,
+            config,
+        );
+        std::fs::remove_file(&keypath).unwrap();
+        assert_eq!(bridge.gas_price_config, config);
+
+        // each of 3 sampled blocks has transactions with 2, 4 and 6 gwei gas price
+        let transactions = [2, 4, 6]
+            .iter()
+            .map(|price| RPCTransaction {
+                gas_price: Some(Hex(U256::from(price * gwei))),
+                ..serde_json::from_str("{}").unwrap()
+            })
+            .collect();
+        let block = RPCBlock {
+            transactions: Either::Right(transactions),
+            ..RPCBlock::default()
+        };
+        let mocks: Mocks = vec![
+            (RpcRequest::EthBlockNumber, serde_json::json!("0x10")),
+            (
+                RpcRequest::EthGetBlockByNumber,
+                serde_json::to_value(&block).unwrap(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        bridge.rpc_client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let bridge = Arc::new(bridge);
+        let gas_price = GeneralErpcProxy.gas_price(bridge.clone()).unwrap();
+        assert_eq!(gas_price, Hex(U256::from(4 * gwei)));
+
+        // pool requires 20% bump to replace transaction
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let tx_with_price = |gas_price: u64| {
+            evm_state::UnsignedTransaction {
+                nonce: 0.into(),
+                gas_price: gas_price.into(),
+                gas_limit: 30000000.into(),
+                action: evm_state::TransactionAction::Create,
+                value: 0.into(),
+                input: vec![],
+            }
+            .sign(&secret_key, Some(111))
+        };
+        for (gas_price, imported) in &[(10 * gwei, true), (11 * gwei, false), (12 * gwei, true)] {
+            let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+            let tx =
+                PooledTransaction::new(tx_with_price(*gas_price), HashSet::new(), sender).unwrap();
+            assert_eq!(bridge.pool.import(tx).is_ok(), *imported);
+        }
+    }
+
+    #[test]
+    fn test_max_priority_fee_per_gas() {
+        let bridge = Arc::new(mocked_bridge(Mocks::default()));
+        let gas_price = GeneralErpcProxy.gas_price(bridge.clone()).unwrap();
+        let tip = GeneralErpcProxy.max_priority_fee_per_gas(bridge).unwrap();
+        assert_eq!(tip, Hex(U256::zero()));
+        assert!(tip.0 <= gas_price.0);
+
+        let args = Args::from_iter(&[
+            "evm-bridge",
+            "--min-gas-price",
+            "5000000000",
+            "--max-priority-fee",
+            "7000000000",
+        ]);
+        let config = args.gas_price_config();
+        assert_eq!(config.max_priority_fee_per_gas(), config.default_gas_price);
+    }
+
+    fn test_receipt(transaction_hash: H256) -> RPCReceipt {
+        RPCReceipt {
+            transaction_hash: Hex(transaction_hash),
+            transaction_index: Hex(0),
+            block_hash: Hex(H256::zero()),
+            block_number: Hex(U256::one()),
+            cumulative_gas_used: Hex(21000.into()),
+            gas_used: Hex(21000.into()),
+            contract_address: None,
+            logs_bloom: Default::default(),
+            to: None,
+            from: None,
+            logs: vec![],
+            status: Hex(1),
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_self_test_confirmed() {
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let mut mocks = nonce_mocks(3);
+        mocks.insert(
+            RpcRequest::EthGetTransactionReceipt,
+            serde_json::to_value(test_receipt(H256::zero())).unwrap(),
+        );
+        let bridge = Arc::new(test_bridge(
+            vec![secret_key],
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks),
+        ));
+
+        let hash = self_test(bridge.clone()).await.unwrap();
+        let pooled = bridge.pool.transaction_by_hash(Hex(hash)).unwrap();
+        assert_eq!(pooled.nonce, 3.into());
+        assert_eq!(
+            pooled.action,
+            evm_state::TransactionAction::Call(pooled.caller().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_transaction() {
+        let tx = signed_tx(0);
+        let hash = tx.tx_id_hash();
+
+        let bridge = Arc::new(mocked_bridge(Mocks::default()));
+        let lookup = VelasErpcProxy
+            .find_transaction(bridge.clone(), raw_tx(&tx))
+            .await
+            .unwrap();
+        assert_eq!(lookup.hash.0, hash);
+        assert_eq!(lookup.status, RPCTransactionStatus::Unknown);
+        assert!(lookup.receipt.is_none());
+
+        let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+        let pooled = PooledTransaction::new(tx.clone(), HashSet::new(), sender).unwrap();
+        bridge.pool.import(pooled).unwrap();
+        let lookup = VelasErpcProxy
+            .find_transaction(bridge, raw_tx(&tx))
+            .await
+            .unwrap();
+        assert_eq!(lookup.status, RPCTransactionStatus::Pending);
+
+        let mocks: Mocks = vec![(
+            RpcRequest::EthGetTransactionReceipt,
+            serde_json::to_value(test_receipt(hash)).unwrap(),
+        )]
+        .into_iter()
+        .collect();
+        let lookup = VelasErpcProxy
+            .find_transaction(Arc::new(mocked_bridge(mocks)), raw_tx(&tx))
+            .await
+            .unwrap();
+        assert_eq!(lookup.status, RPCTransactionStatus::Mined);
+        assert_eq!(lookup.receipt.unwrap().transaction_hash.0, hash);
+
+        let err = VelasErpcProxy
+            .find_transaction(Arc::new(mocked_bridge(Mocks::default())), Bytes(vec![1, 2]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::RlpError { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_self_test_without_accounts() {
+        let bridge = Arc::new(mocked_bridge(Mocks::default()));
+        assert!(matches!(
+            self_test(bridge).await,
+            Err(Error::RuntimeError { .. })
+        ));
+    }
+
+    fn call_to(address: Address) -> RPCTransaction {
+        RPCTransaction {
+            to: Some(Hex(address)),
+            ..serde_json::from_str("{}").unwrap()
+        }
+    }
+
+    fn fee_history_bridge() -> Arc<EvmBridge> {
+        let transactions = [40u64, 10, 30, 20]
+            .iter()
+            .map(|price| RPCTransaction {
+                gas_price: Some(Hex(U256::from(*price))),
+                ..call_to(Address::repeat_byte(1))
+            })
+            .collect();
+        let block = RPCBlock {
+            gas_limit: Hex(1000.into()),
+            gas_used: Hex(250.into()),
+            transactions: Either::Right(transactions),
+            ..RPCBlock::default()
+        };
+        let mocks: Mocks = vec![
+            (RpcRequest::EthBlockNumber, serde_json::json!("0x10")),
+            (
+                RpcRequest::EthGetBlockByNumber,
+                serde_json::to_value(&block).unwrap(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let mut bridge = mocked_bridge(mocks);
+        bridge.gas_price_config.default_gas_price = 10.into();
+        Arc::new(bridge)
+    }
+
+    #[tokio::test]
+    async fn test_fee_history() {
+        let latest = BlockId::RelativeId(BlockRelId::Latest);
+        let history = BridgeErpcImpl
+            .fee_history(
+                fee_history_bridge(),
+                Hex(3),
+                latest,
+                Some(vec![25.0, 50.0, 100.0]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(history.oldest_block, Hex(14));
+        assert_eq!(history.base_fee_per_gas.0, vec![U256::from(10); 4]);
+        assert_eq!(history.gas_used_ratio, vec![0.25; 3]);
+        let expected_reward = vec![U256::from(0), U256::from(10), U256::from(30)];
+        let reward = history.reward.unwrap();
+        assert_eq!(reward.len(), 3);
+        assert!(reward.iter().all(|block| block.0 == expected_reward));
+
+        // block count is clamped to available blocks, reward is omitted without percentiles
+        let history = BridgeErpcImpl
+            .fee_history(fee_history_bridge(), Hex(5000), latest, None)
+            .await
+            .unwrap();
+        assert_eq!(history.oldest_block, Hex(0));
+        assert_eq!(history.gas_used_ratio.len(), 17);
+        assert!(history.reward.is_none());
+
+        let result = BridgeErpcImpl
+            .fee_history(fee_history_bridge(), Hex(3), latest, Some(vec![50.0, 25.0]))
+            .await;
+        assert!(matches!(result, Err(Error::InvalidParams { .. })));
+    }
+
+    fn strict_bridge(code: &str) -> Arc<EvmBridge> {
+        let mocks: Mocks = vec![
+            (RpcRequest::EthGetCode, serde_json::json!(code)),
+            (RpcRequest::EthCall, serde_json::json!("0x01")),
+        ]
+        .into_iter()
+        .collect();
+        let mut bridge = mocked_bridge(mocks);
+        bridge.strict_call = true;
+        Arc::new(bridge)
+    }
+
+    #[tokio::test]
+    async fn test_strict_call_contract() {
+        let bridge = strict_bridge("0x6080");
+        let result = ChainErpcProxy
+            .call(bridge, call_to(Address::repeat_byte(1)), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(result.0, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_strict_call_eoa_and_missing_account() {
+        // Both EOA and non-existent account has empty code.
+        for address in &[Address::repeat_byte(2), Address::zero()] {
+            let bridge = strict_bridge("0x");
+            let err = ChainErpcProxy
+                .call(bridge, call_to(*address), None, None, None)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::NoContractAtAddress { address: a } if a == *address));
+        }
+
+        // lenient by default
+        let mocks: Mocks = vec![(RpcRequest::EthCall, serde_json::json!("0x"))]
+            .into_iter()
+            .collect();
+        let bridge = Arc::new(mocked_bridge(mocks));
+        let result = ChainErpcProxy
+            .call(bridge, call_to(Address::repeat_byte(2)), None, None, None)
+            .await
+            .unwrap();
+        assert!(result.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_strict_call_with_code_override() {
+        let bridge = strict_bridge("0x");
+        let address = Address::repeat_byte(2);
+        let overrides: StateOverrides = vec![(
+            Hex(address),
+            AccountOverride {
+                code: Some(Bytes(vec![0x60, 0x80])),
+                ..Default::default()
+            },
+        )]
+        .into_iter()
+        .collect();
+        let result = ChainErpcProxy
+            .call(bridge, call_to(address), None, None, Some(overrides))
+            .await
+            .unwrap();
+        assert_eq!(result.0, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_managed_signing_requires_opt_in() {
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let mut bridge = test_bridge(
+            vec![secret_key],
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), nonce_mocks(0)),
+        );
+        let address = *bridge.accounts.keys().next().unwrap();
+        let tx = RPCTransaction {
+            from: Some(Hex(address)),
+            to: Some(Hex(address)),
+            ..serde_json::from_str("{}").unwrap()
+        };
+
+        let disabled = Arc::new(test_bridge(
+            vec![secret_key],
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), nonce_mocks(0)),
+        ));
+        assert!(matches!(
+            BridgeErpcImpl.sign(disabled.clone(), Hex(address), Bytes(vec![1])),
+            Err(Error::MethodDisabled { .. })
+        ));
+        assert!(matches!(
+            BridgeErpcImpl
+                .sign_transaction(disabled.clone(), tx.clone())
+                .await,
+            Err(Error::MethodDisabled { .. })
+        ));
+        assert!(matches!(
+            BridgeErpcImpl
+                .send_transaction(disabled.clone(), tx.clone(), None)
+                .await,
+            Err(Error::MethodDisabled { .. })
+        ));
+        assert!(disabled.pool.transaction_count(&address).is_none());
+
+        bridge.managed_signing = true;
+        let bridge = Arc::new(bridge);
+        BridgeErpcImpl
+            .sign(bridge.clone(), Hex(address), Bytes(vec![1]))
+            .unwrap();
+        BridgeErpcImpl
+            .sign_transaction(bridge.clone(), tx.clone())
+            .await
+            .unwrap();
+        let hash = BridgeErpcImpl
+            .send_transaction(bridge.clone(), tx, None)
+            .await
+            .unwrap();
+        assert!(bridge.pool.transaction_by_hash(hash).is_some());
+    }
+
+    #[test]
+    fn test_input_size_limit() {
+        let secret_key = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let mut bridge = test_bridge(vec![secret_key], RpcClient::new("".to_string()));
+        bridge.managed_signing = true;
+        bridge.max_input_size = 4;
+        let address = *bridge.accounts.keys().next().unwrap();
+        let bridge = Arc::new(bridge);
+
+        GeneralErpcProxy
+            .sha3(bridge.clone(), Bytes(vec![0; 4]))
+            .unwrap();
+        assert!(matches!(
+            GeneralErpcProxy.sha3(bridge.clone(), Bytes(vec![0; 5])),
+            Err(Error::InputTooLarge {
+                size: 5,
+                max_size: 4
+            })
+        ));
+        BridgeErpcImpl
+            .sign(bridge.clone(), Hex(address), Bytes(vec![0; 4]))
+            .unwrap();
+        assert!(matches!(
+            BridgeErpcImpl.sign(bridge, Hex(address), Bytes(vec![0; 5])),
+            Err(Error::InputTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_eth_sign() {
+        let signing_key =
+            SecretKey::from_str("c21020a52198632ae7d5c1adaa3f83da2e0c98cf541c54686ddc8d202124c086")
+                .unwrap();
+        let mut bridge = test_bridge(vec![signing_key], RpcClient::new("".to_string()));
+        bridge.managed_signing = true;
+        let bridge = Arc::new(bridge);
 
         let rpc = BridgeErpcImpl {};
         let address = Address::from_str("0x141a4802f84bb64c0320917672ef7D92658e964e").unwrap();
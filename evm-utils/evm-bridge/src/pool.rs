@@ -3,13 +3,19 @@ mod listener;
 use std::{
     collections::{HashMap, HashSet},
     ops::Deref,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
-use ::tokio::sync::mpsc;
+use ::tokio::sync::{broadcast, mpsc};
 use borsh::BorshSerialize;
-use evm_rpc::{error::into_native_error, Bytes, Hex, RPCTransaction};
+use evm_rpc::{
+    error::into_native_error, Bytes, GasPriceConfig, Hex, RPCGasPriceBucket, RPCMempoolStats,
+    RPCTransaction, RPCTxPoolContent, RPCTxPoolStatus,
+};
 use evm_state::{Address, TransactionAction, H160, H256, U256};
 use listener::PoolListener;
 use log::*;
@@ -21,6 +27,7 @@ use solana_evm_loader_program::{
     scope::{evm, solana},
     tx_chunks::TxChunks,
 };
+use solana_metrics::datapoint_info;
 use solana_sdk::{
     commitment_config::{CommitmentConfig, CommitmentLevel},
     instruction::AccountMeta,
@@ -41,6 +48,8 @@ use crate::{from_client_error, send_and_confirm_transactions, EvmBridge, EvmResu
 
 type UnixTimeMs = u64;
 
+type TxPool = Pool<PooledTransaction, MyScoring, PoolListener>;
+
 /// Loop delay of signature check worker
 const SIG_CHECK_WORKER_PAUSE: Duration = Duration::from_secs(60);
 
@@ -56,9 +65,28 @@ const SENDER_PAUSE: Duration = Duration::from_secs(15);
 /// TODO: adjust value
 const TX_REIMPORT_THRESHOLD: Duration = Duration::from_secs(30);
 
+/// Loop delay of queue worker
+const QUEUE_WORKER_PAUSE: Duration = Duration::from_secs(10);
+
+/// Queued transaction is dropped, if its nonce gap isn't filled during this time
+const QUEUED_TX_LIFETIME: Duration = Duration::from_secs(3 * 3600);
+
+/// Default limit of transactions in the pool
+pub const DEFAULT_MAX_POOL_SIZE: usize = 4096;
+
+/// Number of import notifications kept for slow subscribers, older ones are skipped.
+const IMPORTED_CHANNEL_CAPACITY: usize = 1024;
+
+/// Mempool stats are recomputed on request, if they are older than this.
+const MEMPOOL_STATS_CACHE_TIME: Duration = Duration::from_secs(1);
+
+/// Maximum number of gas price ranges in mempool stats histogram.
+const MEMPOOL_HISTOGRAM_BUCKETS: usize = 10;
+
 #[derive(Debug)]
 pub struct CachedTransaction {
     evm_tx: evm_state::Transaction,
+    sender: Address,
     meta_keys: HashSet<Pubkey>,
     cached_at: UnixTimeMs,
     signature: Signature,
@@ -95,7 +123,7 @@ impl<T> Ready<T> for AlwaysReady {
 #[derive(Debug)]
 pub struct EthPool<C: Clock> {
     /// A pool of transactions, waiting to be deployed
-    pool: Mutex<Pool<PooledTransaction, MyScoring, PoolListener>>,
+    pool: Mutex<TxPool>,
 
     /// Timestamps of the last deployed transactions
     last_entry: Mutex<HashMap<Address, UnixTimeMs>>,
@@ -106,24 +134,132 @@ pub struct EthPool<C: Clock> {
 
     /// Clock used to determine whether transaction is stalled or ready to be deployed
     clock: C,
+
+    /// Scoring used to decide whether imported transaction replaces pending one
+    scoring: MyScoring,
+
+    /// Maximum number of transactions in the pool, cheapest sender tails are evicted above it
+    max_pool_size: usize,
+
+    /// Number of transactions evicted because the pool was full
+    evicted: AtomicU64,
+
+    /// Next nonce of senders in state, as last seen by the bridge.
+    /// Transactions separated from it by a nonce gap are queued until the gap is filled.
+    state_nonces: Mutex<HashMap<Address, U256>>,
+
+    /// Hashes of imported transactions, slow receivers lag instead of blocking import
+    imported: broadcast::Sender<H256>,
+
+    /// Last computed mempool stats, with time of computation
+    mempool_stats: Mutex<Option<(RPCMempoolStats, UnixTimeMs)>>,
 }
 
 impl<C: Clock> EthPool<C> {
     pub fn new(clock: C) -> Self {
+        Self::new_with_gas_price_config(clock, GasPriceConfig::default())
+    }
+
+    pub fn new_with_gas_price_config(clock: C, gas_price_config: GasPriceConfig) -> Self {
+        let scoring = MyScoring { gas_price_config };
+        let (imported, _) = broadcast::channel(IMPORTED_CHANNEL_CAPACITY);
         Self {
-            pool: Mutex::new(Pool::new(PoolListener, MyScoring, Default::default())),
+            pool: Mutex::new(Pool::new(PoolListener, scoring, Default::default())),
             last_entry: Mutex::new(HashMap::new()),
             after_deploy_check: Mutex::new(HashMap::new()),
             clock,
+            scoring,
+            max_pool_size: DEFAULT_MAX_POOL_SIZE,
+            evicted: AtomicU64::new(0),
+            state_nonces: Mutex::new(HashMap::new()),
+            imported,
+            mempool_stats: Mutex::new(None),
         }
     }
 
-    /// Imports transaction into the pool
+    pub fn set_max_pool_size(&mut self, max_pool_size: usize) {
+        self.max_pool_size = max_pool_size;
+    }
+
+    /// Imports transaction into the pool. If the pool is full, the cheapest (the oldest one
+    /// among equal) of the last transactions of other senders is evicted, or imported
+    /// transaction is rejected if it isn't more expensive. Only the highest nonce of a sender
+    /// is evicted, so its remaining transactions don't end up behind a nonce gap.
+    /// Transaction is evicted only after the new one is successfully imported.
     pub fn import(
         &self,
-        tx: PooledTransaction,
+        mut tx: PooledTransaction,
     ) -> Result<Arc<PooledTransaction>, txpool::Error<H256>> {
-        self.pool.lock().unwrap().import(tx, &MyScoring)
+        tx.imported_at = self.clock.now();
+
+        let mut pool = self.pool.lock().unwrap();
+        let mut victim = None;
+        if pool.light_status().transaction_count >= self.max_pool_size
+            && pool.find(&tx.hash).is_none()
+            && !pool
+                .pending_from_sender(AlwaysReady, &tx.sender, H256::zero())
+                .any(|pending| pending.nonce == tx.nonce)
+        {
+            let mut tails: HashMap<Address, Arc<PooledTransaction>> = HashMap::new();
+            for pending in pool.pending(AlwaysReady, H256::zero()) {
+                if pending.sender == tx.sender {
+                    continue;
+                }
+                let tail = tails
+                    .entry(pending.sender)
+                    .or_insert_with(|| pending.clone());
+                if pending.nonce > tail.nonce {
+                    *tail = pending;
+                }
+            }
+            let cheapest = tails
+                .into_values()
+                .min_by_key(|tail| (tail.gas_price, tail.imported_at));
+
+            match cheapest {
+                Some(cheapest) if cheapest.gas_price < tx.gas_price => victim = Some(cheapest),
+                cheapest => {
+                    let min_gas_price = cheapest.map(|tx| tx.gas_price).unwrap_or_default();
+                    return Err(txpool::Error::TooCheapToEnter(
+                        tx.hash,
+                        format!("{:#x}", min_gas_price),
+                    ));
+                }
+            }
+        }
+
+        let imported = pool.import(tx, &self.scoring)?;
+        if let Some(victim) = victim {
+            debug!(
+                "Pool is full, evicting transaction {} in favor of {}",
+                victim.hash, imported.hash
+            );
+            pool.remove(&victim.hash, false);
+            let evicted = self.evicted.fetch_add(1, Ordering::Relaxed) + 1;
+            datapoint_info!(
+                "evm_bridge-pool_eviction",
+                ("size", self.max_pool_size as i64, i64),
+                ("evicted", evicted as i64, i64)
+            );
+        }
+        // fails only if there are no receivers
+        let _ = self.imported.send(imported.hash);
+        Ok(imported)
+    }
+
+    /// Subscribes to hashes of transactions, imported after this call
+    pub fn subscribe_imported(&self) -> broadcast::Receiver<H256> {
+        self.imported.subscribe()
+    }
+
+    /// Number of transactions in the pool
+    pub fn size(&self) -> usize {
+        self.pool.lock().unwrap().light_status().transaction_count
+    }
+
+    /// Number of transactions evicted because the pool was full
+    pub fn evicted_count(&self) -> u64 {
+        self.evicted.load(Ordering::Relaxed)
     }
 
     /// Prevents pooled transactions from specified sender `address` from processing for certain amount of time
@@ -151,13 +287,18 @@ impl<C: Clock> EthPool<C> {
         hash.map(|hash| self.remove(&hash)).flatten()
     }
 
-    /// Gets reference to the next transaction in queue ready to be deployed
+    /// Gets reference to the next transaction in queue ready to be deployed,
+    /// queued transactions are skipped
     pub fn pending(&self) -> Option<Arc<PooledTransaction>> {
         let pool = self.pool.lock().unwrap();
+        let queued = self.queued_hashes(&pool);
         let last_entry = self.last_entry.lock().unwrap();
 
         pool.pending(
             |tx: &PooledTransaction| {
+                if queued.contains(&tx.hash) {
+                    return Readiness::Future;
+                }
                 if let Some(stop_before) = last_entry.get(&tx.sender) {
                     if self.clock.now() < *stop_before {
                         return Readiness::Stale;
@@ -170,6 +311,167 @@ impl<C: Clock> EthPool<C> {
         .next()
     }
 
+    /// Remembers next nonce of `sender` in state, queued transactions are promoted
+    /// once it reaches them. Nonce never goes back, so stale responses are ignored.
+    pub fn update_state_nonce(&self, sender: &Address, nonce: U256) {
+        let mut state_nonces = self.state_nonces.lock().unwrap();
+        let state_nonce = state_nonces.entry(*sender).or_insert(nonce);
+        *state_nonce = (*state_nonce).max(nonce);
+    }
+
+    /// Resyncs state nonces with chain, using `fetch` to get on-chain nonce of sender.
+    /// State nonces of senders without transactions in the pool are forgotten,
+    /// so they are fetched again when needed. Unlike `update_state_nonce`,
+    /// nonce may go back, e.g. after transactions are dropped by reorg.
+    /// Returns number of resynced senders.
+    pub fn resync_state_nonces<F>(&self, mut fetch: F) -> usize
+    where
+        F: FnMut(&Address) -> Option<U256>,
+    {
+        let senders: HashSet<Address> = self
+            .pool
+            .lock()
+            .unwrap()
+            .pending(AlwaysReady, H256::zero())
+            .map(|tx| tx.sender)
+            .collect();
+        self.state_nonces
+            .lock()
+            .unwrap()
+            .retain(|sender, _| senders.contains(sender));
+
+        let mut resynced = 0;
+        for sender in senders {
+            // no lock is held while nonce is fetched
+            if let Some(nonce) = fetch(&sender) {
+                self.state_nonces.lock().unwrap().insert(sender, nonce);
+                resynced += 1;
+            }
+        }
+        resynced
+    }
+
+    /// Hashes of transactions, separated from state nonce of their sender by a nonce gap.
+    /// Transactions of senders with unknown state nonce are never queued.
+    fn queued_hashes(&self, pool: &TxPool) -> HashSet<H256> {
+        let state_nonces = self.state_nonces.lock().unwrap();
+        let mut by_sender: HashMap<Address, Vec<Arc<PooledTransaction>>> = HashMap::new();
+        for tx in pool.pending(AlwaysReady, H256::zero()) {
+            if state_nonces.contains_key(&tx.sender) {
+                by_sender.entry(tx.sender).or_default().push(tx);
+            }
+        }
+
+        let mut queued = HashSet::new();
+        for (sender, mut txs) in by_sender {
+            let mut next_nonce = state_nonces[&sender];
+            txs.sort_by_key(|tx| tx.nonce);
+            for tx in txs {
+                if tx.nonce == next_nonce {
+                    next_nonce += U256::one();
+                } else if tx.nonce > next_nonce {
+                    queued.insert(tx.hash);
+                }
+            }
+        }
+        queued
+    }
+
+    /// Number of queued transactions
+    pub fn queued_count(&self) -> usize {
+        let pool = self.pool.lock().unwrap();
+        self.queued_hashes(&pool).len()
+    }
+
+    /// Senders, which have queued transactions
+    pub fn queued_senders(&self) -> HashSet<Address> {
+        let pool = self.pool.lock().unwrap();
+        self.queued_hashes(&pool)
+            .iter()
+            .filter_map(|hash| pool.find(hash))
+            .map(|tx| tx.sender)
+            .collect()
+    }
+
+    /// Numbers of pending and queued transactions
+    pub fn status(&self) -> RPCTxPoolStatus {
+        let pool = self.pool.lock().unwrap();
+        let queued = self.queued_hashes(&pool).len();
+        RPCTxPoolStatus {
+            pending: Hex(pool.light_status().transaction_count - queued),
+            queued: Hex(queued),
+        }
+    }
+
+    /// Pending and queued transactions, grouped by sender and nonce
+    pub fn content(&self) -> RPCTxPoolContent {
+        let pool = self.pool.lock().unwrap();
+        let queued = self.queued_hashes(&pool);
+        let mut content = RPCTxPoolContent::default();
+        for tx in pool.pending(AlwaysReady, H256::zero()) {
+            let rpc_tx = match tx.rpc_tx() {
+                Some(rpc_tx) => rpc_tx.clone(),
+                None => continue,
+            };
+            let group = if queued.contains(&tx.hash) {
+                &mut content.queued
+            } else {
+                &mut content.pending
+            };
+            group
+                .entry(Hex(tx.sender))
+                .or_default()
+                .insert(tx.nonce.to_string(), rpc_tx);
+        }
+        content
+    }
+
+    /// Gas price distribution of transactions in the pool,
+    /// cached for `MEMPOOL_STATS_CACHE_TIME`
+    pub fn mempool_stats(&self) -> RPCMempoolStats {
+        let now = self.clock.now();
+        let mut cached = self.mempool_stats.lock().unwrap();
+        match &*cached {
+            Some((stats, computed_at))
+                if *computed_at + MEMPOOL_STATS_CACHE_TIME.as_millis() as u64 > now =>
+            {
+                stats.clone()
+            }
+            _ => {
+                let gas_prices = {
+                    let pool = self.pool.lock().unwrap();
+                    pool.pending(AlwaysReady, H256::zero())
+                        .map(|tx| tx.gas_price)
+                        .collect()
+                };
+                let stats = mempool_stats(gas_prices);
+                *cached = Some((stats.clone(), now));
+                stats
+            }
+        }
+    }
+
+    /// Removes transactions, which stay queued longer than `QUEUED_TX_LIFETIME`,
+    /// returns number of removed transactions
+    pub fn drop_stuck_queued(&self) -> usize {
+        let now = self.clock.now();
+        let mut pool = self.pool.lock().unwrap();
+        let stuck: Vec<H256> = self
+            .queued_hashes(&pool)
+            .into_iter()
+            .filter(|hash| {
+                pool.find(hash).map_or(false, |tx| {
+                    tx.imported_at + QUEUED_TX_LIFETIME.as_millis() as u64 <= now
+                })
+            })
+            .collect();
+        for hash in &stuck {
+            info!("Transaction {} was queued for too long, removing", hash);
+            pool.remove(hash, false);
+        }
+        stuck.len()
+    }
+
     /// Returns nonce from transaction pool, or `None` if the it doesn't contain
     /// any transactions associated with the specified sender
     pub fn transaction_count(&self, sender: &Address) -> Option<U256> {
@@ -181,12 +483,43 @@ impl<C: Clock> EthPool<C> {
             .map(|tx| tx.nonce + 1)
     }
 
+    /// Returns lowest nonce, starting from last seen state nonce of `sender`, that isn't used
+    /// by transactions of `sender` in the pool, or `None` if state nonce is unknown.
+    /// Unlike `transaction_count`, nonce after a gap left by dropped transactions is never returned.
+    pub fn next_nonce(&self, sender: &Address) -> Option<U256> {
+        let state_nonce = self.state_nonces.lock().unwrap().get(sender).copied()?;
+        Some(self.first_free_nonce(sender, state_nonce))
+    }
+
+    /// Returns lowest nonce, starting from `state_nonce`, that isn't used
+    /// by transactions of `sender` in the pool
+    pub fn first_free_nonce(&self, sender: &Address, state_nonce: U256) -> U256 {
+        let used: HashSet<U256> = self
+            .pool
+            .lock()
+            .unwrap()
+            .pending_from_sender(AlwaysReady, sender, H256::zero())
+            .map(|tx| tx.nonce)
+            .collect();
+        let mut nonce = state_nonce;
+        while used.contains(&nonce) {
+            nonce += U256::one();
+        }
+        nonce
+    }
+
     /// Gets transaction from the pool by specified hash
     pub fn transaction_by_hash(&self, tx_hash: Hex<H256>) -> Option<Arc<PooledTransaction>> {
         let pool = self.pool.lock().unwrap();
         pool.find(&tx_hash.0)
     }
 
+    /// Gets RPC representation of pooled transaction, converted once at import
+    pub fn rpc_transaction_by_hash(&self, tx_hash: Hex<H256>) -> Option<RPCTransaction> {
+        self.transaction_by_hash(tx_hash)
+            .and_then(|tx| tx.rpc_tx().cloned())
+    }
+
     /// Strips outdated timestamps and returns the number of
     /// elements in the collection before and after the strip
     pub fn strip_outdated(&self) -> (usize, usize) {
@@ -204,17 +537,20 @@ impl<C: Clock> EthPool<C> {
     /// * `signature` - signature of Solana transaction to be checked for status
     /// * `meta_keys` -
     /// * `evm_tx` - ethereum tx to be redeployed in case of status error
+    /// * `sender` - sender of `evm_tx`, recovered when it was sent
     pub fn schedule_after_deploy_check(
         &self,
         hash: H256,
         signature: Signature,
         meta_keys: HashSet<Pubkey>,
         evm_tx: evm_state::Transaction,
+        sender: Address,
     ) {
         let cached_at = self.clock.now();
 
         let cached_tx = CachedTransaction {
             evm_tx,
+            sender,
             meta_keys,
             cached_at,
             signature,
@@ -263,6 +599,12 @@ pub struct PooledTransaction {
     sender: Address,
     hash: H256,
     hash_sender: Option<mpsc::Sender<EvmResult<Hex<H256>>>>,
+    /// Set once a result was passed to `hash_sender`
+    responded: AtomicBool,
+    /// Cached RPC representation, lives as long as transaction is in the pool
+    rpc_tx: Option<RPCTransaction>,
+    /// Time of import into the pool, used to evict the oldest of equally priced transactions
+    imported_at: UnixTimeMs,
 }
 
 impl PooledTransaction {
@@ -271,32 +613,59 @@ impl PooledTransaction {
         meta_keys: HashSet<Pubkey>,
         hash_sender: mpsc::Sender<EvmResult<Hex<H256>>>,
     ) -> Result<Self, evm_state::error::Error> {
-        let hash = transaction.tx_id_hash();
         let sender = transaction.caller()?;
-
-        Ok(Self {
-            inner: transaction,
+        Ok(Self::with_caller(
+            transaction,
             sender,
-            hash,
             meta_keys,
-            hash_sender: Some(hash_sender),
-        })
+            hash_sender,
+        ))
+    }
+
+    /// Creates transaction with already recovered sender, to avoid recovering it twice.
+    pub fn with_caller(
+        transaction: evm::Transaction,
+        sender: Address,
+        meta_keys: HashSet<Pubkey>,
+        hash_sender: mpsc::Sender<EvmResult<Hex<H256>>>,
+    ) -> Self {
+        let mut tx = Self::reimported(transaction, sender, meta_keys);
+        tx.hash_sender = Some(hash_sender);
+        tx
     }
 
     pub fn reimported(
         transaction: evm::Transaction,
+        sender: Address,
         meta_keys: HashSet<Pubkey>,
-    ) -> Result<Self, evm_state::error::Error> {
+    ) -> Self {
         let hash = transaction.tx_id_hash();
-        let sender = transaction.caller()?;
+        // use recovered sender instead of recovering it once more
+        let rpc_tx = RPCTransaction::from_transaction(transaction.clone().into())
+            .ok()
+            .map(|rpc_tx| RPCTransaction {
+                from: Some(Hex(sender)),
+                creates: rpc_tx
+                    .creates
+                    .map(|_| Hex(transaction.action.address(sender, transaction.nonce))),
+                ..rpc_tx
+            });
 
-        Ok(Self {
+        Self {
             inner: transaction,
             sender,
             hash,
             meta_keys,
             hash_sender: None,
-        })
+            responded: AtomicBool::new(false),
+            rpc_tx,
+            imported_at: 0,
+        }
+    }
+
+    /// RPC representation of transaction, computed at creation
+    pub fn rpc_tx(&self) -> Option<&RPCTransaction> {
+        self.rpc_tx.as_ref()
     }
 
     async fn send(
@@ -304,6 +673,7 @@ impl PooledTransaction {
         hash: EvmResult<Hex<H256>>,
     ) -> Result<(), SendError<EvmResult<Hex<H256>>>> {
         if let Some(hash_sender) = &self.hash_sender {
+            self.responded.store(true, Ordering::Relaxed);
             hash_sender.send(hash).await
         } else {
             Ok(())
@@ -315,6 +685,7 @@ impl PooledTransaction {
         hash: EvmResult<Hex<H256>>,
     ) -> Result<(), SendError<EvmResult<Hex<H256>>>> {
         if let Some(hash_sender) = &self.hash_sender {
+            self.responded.store(true, Ordering::Relaxed);
             hash_sender.blocking_send(hash)
         } else {
             Ok(())
@@ -322,6 +693,19 @@ impl PooledTransaction {
     }
 }
 
+/// Guarantees that the waiting client receives a terminal result,
+/// even if the transaction was dropped (or the worker panicked) without answer.
+impl Drop for PooledTransaction {
+    fn drop(&mut self) {
+        if let Some(hash_sender) = self.hash_sender.take() {
+            if !*self.responded.get_mut() {
+                debug!("Transaction {} dropped without result", self.hash);
+                let _result = hash_sender.try_send(Err(evm_rpc::Error::TransactionRemoved {}));
+            }
+        }
+    }
+}
+
 impl VerifiedTransaction for PooledTransaction {
     type Hash = H256;
 
@@ -348,8 +732,10 @@ impl Deref for PooledTransaction {
     }
 }
 
-#[derive(Debug)]
-pub struct MyScoring;
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MyScoring {
+    gas_price_config: GasPriceConfig,
+}
 
 impl Scoring<PooledTransaction> for MyScoring {
     type Score = H256;
@@ -362,7 +748,9 @@ impl Scoring<PooledTransaction> for MyScoring {
 
     fn choose(&self, old: &PooledTransaction, new: &PooledTransaction) -> Choice {
         if old.nonce == new.nonce {
-            if new.gas_price > old.gas_price {
+            if new.gas_price > old.gas_price
+                && new.gas_price >= self.gas_price_config.replacement_gas_price(old.gas_price)
+            {
                 Choice::ReplaceOld
             } else {
                 Choice::RejectNew
@@ -414,14 +802,22 @@ pub async fn worker_deploy(bridge: Arc<EvmBridge>) {
                 process_tx(cloned_bridge, tx, hash, sender, meta_keys)
             })
             .await
-            .expect("tokio should allow new spawns");
+            .unwrap_or_else(|e| {
+                Err(evm_rpc::Error::RuntimeError {
+                    details: format!("Deploy task failed: {}", e),
+                })
+            });
 
             match processed_tx {
                 Ok(hash) => {
                     info!("Transaction {} processed successfully", &hash);
+                    bridge.pool.update_state_nonce(&sender, nonce + 1);
                     let _result = pooled_tx.send(Ok(hash)).await;
                 }
                 Err(e) => {
+                    if let Some((_, state_nonce)) = nonce_mismatch(&e) {
+                        bridge.pool.update_state_nonce(&sender, state_nonce);
+                    }
                     // Any error is a reason to limit user activity.
                     // If error is recoverable, then implement delay to avoid flooding.
                     // If error is not recoverable, then client form invalid tx.
@@ -476,14 +872,44 @@ pub async fn worker_cleaner(bridge: Arc<EvmBridge>) {
     }
 }
 
+/// Refreshes state nonces of senders with queued transactions, so they are promoted
+/// once the gap is filled on chain, and drops transactions queued for too long
+pub async fn worker_queue(bridge: Arc<EvmBridge>) {
+    info!("Running queue worker task...");
+    loop {
+        tokio::time::sleep(QUEUE_WORKER_PAUSE).await;
+
+        for sender in bridge.pool.queued_senders() {
+            match bridge.rpc_client.get_evm_transaction_count(&sender) {
+                Ok(nonce) => bridge.pool.update_state_nonce(&sender, nonce),
+                Err(e) => warn!("Unable to get nonce of {:?}: {:?}", sender, e),
+            }
+        }
+
+        let dropped = bridge.pool.drop_stuck_queued();
+        if dropped > 0 {
+            info!("Dropped {} transactions queued for too long", dropped);
+        }
+    }
+}
+
 /// Checks signatures of deployed transactions and returns transaction back in the
-/// pool in case of status error
+/// pool in case of status error. Also resyncs state nonces of senders with chain.
 pub async fn worker_signature_checker(bridge: Arc<EvmBridge>) {
     info!("Running signature checker task...");
 
     loop {
         info!("Worker checks signatures");
 
+        let resynced = bridge.pool.resync_state_nonces(|sender| {
+            bridge
+                .rpc_client
+                .get_evm_transaction_count(sender)
+                .map_err(|e| warn!("Unable to get nonce of {:?}: {:?}", sender, e))
+                .ok()
+        });
+        debug!("Resynced state nonces of {} senders", resynced);
+
         for (hash, generated) in bridge.pool.get_scheduled_for_check_transactions() {
             debug!("Checking scheduled transaction {}", &hash);
 
@@ -501,23 +927,24 @@ pub async fn worker_signature_checker(bridge: Arc<EvmBridge>) {
                         match evm_tx {
                             Some(cached) => {
                                 warn!("Redeploying transaction {}", &hash);
-                                if let Ok(pooled_tx) =
-                                    PooledTransaction::reimported(cached.evm_tx, cached.meta_keys)
-                                {
-                                    match bridge.pool.import(pooled_tx) {
-                                        Ok(tx) => {
-                                            bridge.pool.drop_from_cache(&hash);
-                                            info!(
-                                                "Transaction reimported to the pool. New tx hash: {}",
-                                                tx.hash
-                                            )
-                                        }
-                                        Err(err) => {
-                                            warn!(
-                                                "Transaction can not be reimported to the pool: {:?}",
-                                                err
-                                            )
-                                        }
+                                let pooled_tx = PooledTransaction::reimported(
+                                    cached.evm_tx,
+                                    cached.sender,
+                                    cached.meta_keys,
+                                );
+                                match bridge.pool.import(pooled_tx) {
+                                    Ok(tx) => {
+                                        bridge.pool.drop_from_cache(&hash);
+                                        info!(
+                                            "Transaction reimported to the pool. New tx hash: {}",
+                                            tx.hash
+                                        )
+                                    }
+                                    Err(err) => {
+                                        warn!(
+                                            "Transaction can not be reimported to the pool: {:?}",
+                                            err
+                                        )
                                     }
                                 }
                             }
@@ -648,7 +1075,7 @@ fn process_tx(
 
     bridge
         .pool
-        .schedule_after_deploy_check(hash, signature, meta_keys, tx);
+        .schedule_after_deploy_check(hash, signature, meta_keys, tx, sender);
 
     Ok(Hex(hash))
 }
@@ -767,12 +1194,17 @@ fn deploy_big_tx(
 
     debug!("Write data txs: {:?}", write_data_txs);
 
-    send_and_confirm_transactions(&bridge.rpc_client, write_data_txs, &signers)
-        .map(|_| debug!("All write txs for storage {} was done", storage_pubkey))
-        .map_err(|e| {
-            error!("Error on write data to storage {}: {:?}", storage_pubkey, e);
-            into_native_error(e, bridge.verbose_errors)
-        })?;
+    send_and_confirm_transactions(
+        &bridge.rpc_client,
+        write_data_txs,
+        &signers,
+        &bridge.send_retry_config,
+    )
+    .map(|_| debug!("All write txs for storage {} was done", storage_pubkey))
+    .map_err(|e| {
+        error!("Error on write data to storage {}: {:?}", storage_pubkey, e);
+        into_native_error(e, bridge.verbose_errors)
+    })?;
 
     let (blockhash, _, _) = bridge
         .rpc_client
@@ -827,24 +1259,57 @@ fn deploy_big_tx(
 /// }
 
 fn is_recoverable_error(e: &evm_rpc::Error) -> bool {
-    static RECOVERABLE_NONCE: Lazy<regex::Regex> = Lazy::new(|| {
+    matches!(nonce_mismatch(e), Some((tx_nonce, state_nonce)) if tx_nonce > state_nonce)
+}
+
+/// Extracts transaction nonce and nonce in state from nonce mismatch error of node
+fn nonce_mismatch(e: &evm_rpc::Error) -> Option<(U256, U256)> {
+    static NONCE_MISMATCH: Lazy<regex::Regex> = Lazy::new(|| {
         regex::Regex::new(r#"Transaction nonce (?P<tx_nonce>\d+) differs from nonce in state (?P<state_nonce>\d+)"#).unwrap()
     });
 
     if let evm_rpc::Error::ProxyRpcError { source } = e {
-        let caps = RECOVERABLE_NONCE.captures(&source.message);
-        let caps = if let Some(caps) = caps {
-            caps
-        } else {
-            return false;
-        };
+        let caps = NONCE_MISMATCH.captures(&source.message)?;
         let tx_nonce: U256 = caps.name("tx_nonce").unwrap().as_str().parse().unwrap();
         let state_nonce: U256 = caps.name("state_nonce").unwrap().as_str().parse().unwrap();
-        if tx_nonce > state_nonce {
-            return true;
+        return Some((tx_nonce, state_nonce));
+    }
+    None
+}
+
+/// Computes percentiles and histogram of gas prices.
+fn mempool_stats(mut gas_prices: Vec<U256>) -> RPCMempoolStats {
+    gas_prices.sort();
+    let (min, max) = match (gas_prices.first(), gas_prices.last()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return RPCMempoolStats::default(),
+    };
+    // nearest-rank percentile
+    let percentile = |p: usize| gas_prices[((p * gas_prices.len() + 99) / 100).max(1) - 1];
+
+    let width = (max - min) / MEMPOOL_HISTOGRAM_BUCKETS + U256::one();
+    let mut histogram: Vec<RPCGasPriceBucket> = vec![];
+    for gas_price in &gas_prices {
+        let bucket = ((*gas_price - min) / width).as_usize();
+        while histogram.len() <= bucket {
+            let bucket_min = min + width * histogram.len();
+            histogram.push(RPCGasPriceBucket {
+                min: Hex(bucket_min),
+                max: Hex(bucket_min.saturating_add(width - U256::one()).min(max)),
+                count: Hex(0),
+            });
         }
+        histogram[bucket].count.0 += 1;
+    }
+
+    RPCMempoolStats {
+        count: Hex(gas_prices.len()),
+        min_gas_price: Some(Hex(min)),
+        median_gas_price: Some(Hex(percentile(50))),
+        p90_gas_price: Some(Hex(percentile(90))),
+        max_gas_price: Some(Hex(max)),
+        histogram,
     }
-    false
 }
 
 #[cfg(test)]
@@ -899,7 +1364,7 @@ mod tests {
 
     #[test]
     fn test_pending_queuing() {
-        let mut pool = Pool::new(PoolListener, MyScoring, Default::default());
+        let mut pool = Pool::new(PoolListener, MyScoring::default(), Default::default());
 
         import(&mut pool, test_tx(100, 1000, "foo", &SK1));
         import(&mut pool, test_tx(100, 1600, "foo", &SK1));
@@ -926,7 +1391,7 @@ mod tests {
 
     #[test]
     fn test_readiness() {
-        let mut pool = Pool::new(PoolListener, MyScoring, Default::default());
+        let mut pool = Pool::new(PoolListener, MyScoring::default(), Default::default());
 
         import(&mut pool, test_tx(1, 1, "11", &SK1));
         import(&mut pool, test_tx(1, 100, "22", &SK2));
@@ -1021,6 +1486,338 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_replacement_bump() {
+        let gas_price_config = GasPriceConfig {
+            replacement_bump_percent: 10,
+            ..GasPriceConfig::default()
+        };
+        let pool = EthPool::new_with_gas_price_config(SystemClock, gas_price_config);
+
+        pool.import(test_tx(1, 1000, "11", &SK1)).unwrap();
+        assert!(pool.import(test_tx(1, 1099, "22", &SK1)).is_err());
+        assert_eq!(pool.pending().unwrap().input, "11".as_bytes());
+
+        pool.import(test_tx(1, 1100, "33", &SK1)).unwrap();
+        assert_eq!(pool.pending().unwrap().input, "33".as_bytes());
+
+        // without bump any higher price replaces transaction
+        let gas_price_config = GasPriceConfig {
+            replacement_bump_percent: 0,
+            ..GasPriceConfig::default()
+        };
+        let pool = EthPool::new_with_gas_price_config(SystemClock, gas_price_config);
+        pool.import(test_tx(1, 1000, "11", &SK1)).unwrap();
+        pool.import(test_tx(1, 1001, "22", &SK1)).unwrap();
+        assert_eq!(pool.pending().unwrap().input, "22".as_bytes());
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        // default config requires 10% bump
+        let pool = EthPool::new(SystemClock);
+
+        let (sender, mut old_receiver) = mpsc::channel(1);
+        let old = test_evm_tx_with_price(1, 100, &SK1);
+        let old = PooledTransaction::new(old, HashSet::new(), sender).unwrap();
+        let old_hash = old.hash;
+        pool.import(old).unwrap();
+
+        // insufficient bump is rejected, pending transaction is kept
+        let cheap = test_evm_tx_with_price(1, 109, &SK1);
+        let (sender, _receiver) = mpsc::channel(1);
+        let cheap = PooledTransaction::new(cheap, HashSet::new(), sender).unwrap();
+        assert!(matches!(
+            pool.import(cheap),
+            Err(txpool::Error::TooCheapToReplace(..))
+        ));
+        assert_eq!(pool.pending().unwrap().hash, old_hash);
+        assert!(old_receiver.try_recv().is_err());
+
+        // transaction with unrelated nonce doesn't replace anything
+        pool.import(test_tx(2, 1, "22", &SK1)).unwrap();
+        assert_eq!(
+            pool.pool.lock().unwrap().light_status().transaction_count,
+            2
+        );
+        assert!(old_receiver.try_recv().is_err());
+
+        // sufficient bump evicts old transaction and notifies its waiter
+        let bumped = test_evm_tx_with_price(1, 110, &SK1);
+        let (sender, _receiver) = mpsc::channel(1);
+        let bumped = PooledTransaction::new(bumped, HashSet::new(), sender).unwrap();
+        let new_hash = bumped.hash;
+        pool.import(bumped).unwrap();
+
+        assert_eq!(pool.pending().unwrap().hash, new_hash);
+        assert!(pool.transaction_by_hash(Hex(old_hash)).is_none());
+        assert_eq!(
+            pool.pool.lock().unwrap().light_status().transaction_count,
+            2
+        );
+        assert!(matches!(
+            old_receiver.try_recv(),
+            Ok(Err(evm_rpc::Error::TransactionReplaced { hash })) if hash == new_hash
+        ));
+    }
+
+    #[test]
+    fn test_pool_size_limit() {
+        let test_clock = Arc::new(Mutex::new(TestClock { now: 0 }));
+        let mut pool = EthPool::new(test_clock.clone());
+        pool.set_max_pool_size(3);
+
+        let (sender, mut evicted_receiver) = mpsc::channel(1);
+        let evicted = test_evm_tx_with_price(1, 100, &SK2);
+        let evicted = PooledTransaction::new(evicted, HashSet::new(), sender).unwrap();
+        let evicted_hash = evicted.hash;
+        pool.import(evicted).unwrap();
+        test_clock.lock().unwrap().now = 1;
+        pool.import(test_tx(1, 100, "11", &SK1)).unwrap();
+        pool.import(test_tx(1, 200, "33", &SK3)).unwrap();
+        assert_eq!(pool.size(), 3);
+
+        // the oldest of the cheapest transactions is evicted
+        pool.import(test_tx(2, 150, "12", &SK1)).unwrap();
+        assert_eq!(pool.size(), 3);
+        assert_eq!(pool.evicted_count(), 1);
+        assert!(pool.transaction_by_hash(Hex(evicted_hash)).is_none());
+        assert!(matches!(
+            evicted_receiver.try_recv(),
+            Ok(Err(evm_rpc::Error::TransactionRemoved {}))
+        ));
+
+        // transaction cheaper than every pending one is rejected
+        assert!(matches!(
+            pool.import(test_tx(2, 50, "34", &SK3)),
+            Err(txpool::Error::TooCheapToEnter(..))
+        ));
+        assert_eq!(pool.size(), 3);
+        assert_eq!(pool.evicted_count(), 1);
+
+        // replacement doesn't grow the pool, so nothing is evicted
+        pool.import(test_tx(1, 300, "33", &SK3)).unwrap();
+        assert_eq!(pool.size(), 3);
+        assert_eq!(pool.evicted_count(), 1);
+    }
+
+    #[test]
+    fn test_pool_size_limit_evicts_sender_tail() {
+        let mut pool = EthPool::new(SystemClock);
+        pool.set_max_pool_size(3);
+
+        let head = test_tx(1, 100, "11", &SK1);
+        let head_hash = head.hash;
+        let tail = test_tx(2, 120, "12", &SK1);
+        let tail_hash = tail.hash;
+        pool.import(head).unwrap();
+        pool.import(tail).unwrap();
+        pool.import(test_tx(1, 130, "21", &SK2)).unwrap();
+
+        // the cheapest transaction isn't evicted, since it would strand the next nonce
+        pool.import(test_tx(1, 150, "31", &SK3)).unwrap();
+        assert_eq!(pool.size(), 3);
+        assert_eq!(pool.evicted_count(), 1);
+        assert!(pool.transaction_by_hash(Hex(head_hash)).is_some());
+        assert!(pool.transaction_by_hash(Hex(tail_hash)).is_none());
+    }
+
+    #[test]
+    fn test_queued_transaction_promoted_on_gap_fill() {
+        let pool = EthPool::new(SystemClock);
+
+        let tx = test_tx(2, 100, "12", &SK1);
+        let sender = tx.sender;
+        pool.update_state_nonce(&sender, 1.into());
+        pool.import(tx).unwrap();
+        pool.import(test_tx(3, 100, "13", &SK1)).unwrap();
+
+        // nonce 1 is missing, so both transactions are queued
+        assert_eq!(pool.queued_count(), 2);
+        assert!(pool.pending().is_none());
+        assert_eq!(
+            pool.queued_senders(),
+            [sender].iter().copied().collect::<HashSet<_>>()
+        );
+
+        // gap is filled by imported transaction
+        pool.import(test_tx(1, 100, "11", &SK1)).unwrap();
+        assert_eq!(pool.queued_count(), 0);
+        let next = pool.pending().unwrap();
+        assert_eq!(next.input, "11".as_bytes());
+
+        // deploy of transaction advances state nonce
+        pool.remove(&next.hash);
+        pool.update_state_nonce(&sender, 2.into());
+        assert_eq!(pool.pending().unwrap().input, "12".as_bytes());
+
+        // gap filled on chain, by transaction sent around the pool
+        let tx = test_tx(5, 100, "25", &SK2);
+        let other_sender = tx.sender;
+        pool.update_state_nonce(&other_sender, 4.into());
+        pool.import(tx).unwrap();
+        assert_eq!(pool.queued_count(), 1);
+        pool.update_state_nonce(&other_sender, 5.into());
+        assert_eq!(pool.queued_count(), 0);
+
+        // state nonce never goes back
+        pool.update_state_nonce(&other_sender, 4.into());
+        assert_eq!(pool.queued_count(), 0);
+    }
+
+    #[test]
+    fn test_drop_stuck_queued() {
+        let test_clock = Arc::new(Mutex::new(TestClock { now: 0 }));
+        let pool = EthPool::new(test_clock.clone());
+
+        let (sender, mut queued_receiver) = mpsc::channel(1);
+        let queued = PooledTransaction::new(test_evm_tx(3, &SK1), HashSet::new(), sender).unwrap();
+        let queued_hash = queued.hash;
+        pool.update_state_nonce(&queued.sender, 1.into());
+        pool.import(queued).unwrap();
+        pool.import(test_tx(1, 100, "11", &SK1)).unwrap();
+
+        test_clock.lock().unwrap().now = QUEUED_TX_LIFETIME.as_millis() as u64 - 1;
+        assert_eq!(pool.drop_stuck_queued(), 0);
+
+        // only queued transaction is dropped, pending one stays regardless of age
+        test_clock.lock().unwrap().now = QUEUED_TX_LIFETIME.as_millis() as u64;
+        assert_eq!(pool.drop_stuck_queued(), 1);
+        assert!(pool.transaction_by_hash(Hex(queued_hash)).is_none());
+        assert_eq!(pool.size(), 1);
+        assert!(matches!(
+            queued_receiver.try_recv(),
+            Ok(Err(evm_rpc::Error::TransactionRemoved {}))
+        ));
+    }
+
+    #[test]
+    fn test_rpc_transaction_cached() {
+        let pool = EthPool::new(SystemClock);
+
+        let tx = pool.import(test_tx(1, 100, "11", &SK1)).unwrap();
+        let hash = Hex(tx.hash);
+
+        let first = pool.transaction_by_hash(hash).unwrap();
+        let second = pool.transaction_by_hash(hash).unwrap();
+        // both lookups return the same conversion, stored together with pooled entry
+        assert!(std::ptr::eq(
+            first.rpc_tx().unwrap(),
+            second.rpc_tx().unwrap()
+        ));
+        assert_eq!(
+            pool.rpc_transaction_by_hash(hash).unwrap().hash,
+            Some(hash)
+        );
+
+        pool.remove(&hash.0);
+        assert!(pool.rpc_transaction_by_hash(hash).is_none());
+    }
+
+    #[test]
+    fn test_dropped_transaction_notifies_receiver() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let tx = PooledTransaction::new(test_evm_tx(1, &SK1), HashSet::new(), sender).unwrap();
+        drop(tx);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Err(evm_rpc::Error::TransactionRemoved {}))
+        ));
+
+        // answered transaction doesn't send second result
+        let (sender, mut receiver) = mpsc::channel(1);
+        let tx = PooledTransaction::new(test_evm_tx(1, &SK1), HashSet::new(), sender).unwrap();
+        let hash = Hex(tx.hash);
+        tx.blocking_send(Ok(hash)).unwrap();
+        drop(tx);
+        assert!(matches!(receiver.try_recv(), Ok(Ok(h)) if h == hash));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_imported_transactions_broadcast() {
+        let pool = EthPool::new(SystemClock);
+        let mut imported = pool.subscribe_imported();
+
+        let tx1 = test_tx(0, 1, "11", &SK1);
+        let tx2 = test_tx(0, 1, "21", &SK2);
+        let (hash1, hash2) = (tx1.hash, tx2.hash);
+        pool.import(tx1).unwrap();
+        pool.import(tx2).unwrap();
+        assert_eq!(imported.try_recv().unwrap(), hash1);
+        assert_eq!(imported.try_recv().unwrap(), hash2);
+
+        // rejected transaction isn't broadcasted
+        assert!(pool.import(test_tx(0, 1, "11", &SK1)).is_err());
+        assert!(imported.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_mempool_stats() {
+        let test_clock = Arc::new(Mutex::new(TestClock { now: 0 }));
+        let pool = EthPool::new(test_clock.clone());
+        assert_eq!(pool.mempool_stats(), RPCMempoolStats::default());
+
+        // cached stats of empty pool expire
+        test_clock.lock().unwrap().now = 1000;
+        for nonce in 0..10 {
+            let gas_price = (nonce + 1) * 10;
+            pool.import(test_tx(nonce, gas_price, "", &SK1)).unwrap();
+        }
+        let stats = pool.mempool_stats();
+        assert_eq!(stats.count, Hex(10));
+        assert_eq!(stats.min_gas_price, Some(Hex(10.into())));
+        assert_eq!(stats.median_gas_price, Some(Hex(50.into())));
+        assert_eq!(stats.p90_gas_price, Some(Hex(90.into())));
+        assert_eq!(stats.max_gas_price, Some(Hex(100.into())));
+        assert_eq!(stats.histogram.len(), MEMPOOL_HISTOGRAM_BUCKETS);
+        assert!(stats.histogram.iter().all(|bucket| bucket.count == Hex(1)));
+        assert_eq!(
+            stats.histogram[0],
+            RPCGasPriceBucket {
+                min: Hex(10.into()),
+                max: Hex(19.into()),
+                count: Hex(1),
+            }
+        );
+        assert_eq!(stats.histogram[9].max, Hex(100.into()));
+
+        // stats are cached briefly
+        pool.import(test_tx(10, 1000, "", &SK1)).unwrap();
+        assert_eq!(pool.mempool_stats(), stats);
+
+        test_clock.lock().unwrap().now = 2000;
+        let stats = pool.mempool_stats();
+        assert_eq!(stats.count, Hex(11));
+        assert_eq!(stats.median_gas_price, Some(Hex(60.into())));
+        assert_eq!(stats.p90_gas_price, Some(Hex(100.into())));
+        assert_eq!(stats.max_gas_price, Some(Hex(1000.into())));
+        // all but the most expensive transaction are in the first range
+        assert_eq!(stats.histogram.first().unwrap().count, Hex(10));
+        assert_eq!(stats.histogram.last().unwrap().count, Hex(1));
+    }
+
+    fn test_evm_tx(nonce: u32, secret_key: &[u8; 32]) -> evm::Transaction {
+        test_evm_tx_with_price(nonce, 1, secret_key)
+    }
+
+    fn test_evm_tx_with_price(
+        nonce: u32,
+        gas_price: u32,
+        secret_key: &[u8; 32],
+    ) -> evm::Transaction {
+        let tx_create = evm::UnsignedTransaction {
+            nonce: nonce.into(),
+            gas_price: gas_price.into(),
+            gas_limit: 30000000.into(),
+            action: evm::TransactionAction::Create,
+            value: 0.into(),
+            input: vec![],
+        };
+        let secret_key: evm_state::SecretKey = evm::SecretKey::from_slice(secret_key).unwrap();
+        tx_create.sign(&secret_key, Some(111))
+    }
+
     fn test_tx(nonce: u32, gas_price: u32, msg: &str, secret_key: &[u8; 32]) -> PooledTransaction {
         let tx_create = evm::UnsignedTransaction {
             nonce: nonce.into(),
@@ -1038,7 +1835,7 @@ mod tests {
     }
 
     fn import(pool: &mut Pool, tx: PooledTransaction) {
-        pool.import(tx, &MyScoring).unwrap();
+        pool.import(tx, &MyScoring::default()).unwrap();
     }
 
     fn pending_msgs<R>(pool: &Pool, ready: R) -> Vec<String>
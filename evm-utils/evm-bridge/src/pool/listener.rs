@@ -11,17 +11,21 @@ use super::PooledTransaction;
 pub struct PoolListener;
 impl PoolListener {
     fn notify_tx_removed(&self, tx: &Arc<PooledTransaction>) {
+        self.notify(tx, evm_rpc::Error::TransactionRemoved {})
+    }
+
+    fn notify(&self, tx: &Arc<PooledTransaction>, error: evm_rpc::Error) {
         if let Ok(handle) = Handle::try_current() {
             let tx = tx.clone();
             handle.spawn(async move {
-                if let Err(e) = tx.send(Err(evm_rpc::Error::TransactionRemoved {})).await {
+                if let Err(e) = tx.send(Err(error)).await {
                     warn!(
                         "PoolListener failed to notify tx sender about transaction, error:{:?}",
                         e
                     )
                 }
             });
-        } else if let Err(e) = tx.blocking_send(Err(evm_rpc::Error::TransactionRemoved {})) {
+        } else if let Err(e) = tx.blocking_send(Err(error)) {
             warn!(
                 "PoolListener failed to notify tx sender about transaction, error:{:?}",
                 e
@@ -39,7 +43,7 @@ impl Listener<PooledTransaction> for PoolListener {
                 "Transaction {} replaced with transaction {}",
                 old.hash, tx.hash
             );
-            self.notify_tx_removed(old)
+            self.notify(old, evm_rpc::Error::TransactionReplaced { hash: tx.hash })
         }
     }
 
@@ -0,0 +1,795 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use evm_rpc::{
+    pubsub::{EthPubSubERPC, RPCPubSubResult},
+    BlockId, Either, Error, Hex, RPCBlock, RPCLog, RPCLogFilter,
+};
+use evm_state::{LogFilter, H256};
+use jsonrpc_core::{
+    futures::future::Either as FutureEither, BoxFuture, Call, MetaIoHandler, Metadata, Middleware,
+    Output, Request, Response,
+};
+use jsonrpc_pubsub::{
+    typed::{Sink, Subscriber},
+    PubSubMetadata, Session, SubscriptionId,
+};
+use jsonrpc_ws_server::RequestContext;
+use log::*;
+use serde_json::json;
+use solana_client::rpc_request::RpcRequest;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{EvmBridge, EvmResult};
+
+const NEW_HEADS_WORKER_PAUSE: Duration = Duration::from_secs(1);
+/// Maximum number of blocks processed in one poll, if bridge is lagging behind the chain.
+/// Also limits backfill of subscription with `lastSeenBlock`: only the last
+/// `MAX_HEADS_PER_POLL` blocks are sent, older blocks are skipped.
+const MAX_HEADS_PER_POLL: u64 = 64;
+/// Methods, that are served by pubsub handler, everything else is routed to regular handler.
+const PUBSUB_METHODS: &[&str] = &["eth_subscribe", "eth_unsubscribe"];
+/// Default maximum number of subscriptions of single websocket connection.
+pub const DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 100;
+/// Default maximum number of subscriptions of all websocket connections.
+pub const DEFAULT_MAX_SUBSCRIPTIONS: usize = 10_000;
+
+/// Metadata of websocket connection.
+#[derive(Clone)]
+pub struct WsMetadata {
+    pub bridge: Arc<EvmBridge>,
+    connection: u64,
+    session: Option<Arc<Session>>,
+}
+
+impl WsMetadata {
+    pub fn new(bridge: Arc<EvmBridge>, context: &RequestContext) -> Self {
+        Self {
+            bridge,
+            connection: context.session_id,
+            session: Some(Arc::new(Session::new(context.sender()))),
+        }
+    }
+}
+
+impl Metadata for WsMetadata {}
+
+impl PubSubMetadata for WsMetadata {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
+enum SubscriptionKind {
+    NewHeads,
+    Logs(LogFilter),
+    NewPendingTransactions,
+}
+
+impl SubscriptionKind {
+    fn parse(kind: &str, params: Option<serde_json::Value>) -> EvmResult<Self> {
+        match kind {
+            "newHeads" => Ok(Self::NewHeads),
+            "newPendingTransactions" => Ok(Self::NewPendingTransactions),
+            "logs" => {
+                // block range is ignored, only logs of new blocks are sent
+                let filter: RPCLogFilter = match params {
+                    Some(params) => {
+                        serde_json::from_value(params).map_err(|e| Error::InvalidParams {
+                            details: format!("Invalid logs filter: {}", e),
+                        })?
+                    }
+                    None => RPCLogFilter {
+                        from_block: None,
+                        to_block: None,
+                        address: None,
+                        topics: None,
+                    },
+                };
+                Ok(Self::Logs(filter.into_log_filter(0, u64::MAX)))
+            }
+            _ => Err(Error::InvalidParams {
+                details: format!("Unsupported subscription kind: {}", kind),
+            }),
+        }
+    }
+
+    fn is_block_subscription(&self) -> bool {
+        matches!(self, Self::NewHeads | Self::Logs(_))
+    }
+}
+
+/// Parses `lastSeenBlock` of resubscribing client, blocks after it are sent before new ones.
+fn last_seen_block(params: Option<&serde_json::Value>) -> EvmResult<Option<u64>> {
+    match params.and_then(|params| params.get("lastSeenBlock")) {
+        Some(block) => serde_json::from_value::<Hex<u64>>(block.clone())
+            .map(|block| Some(block.0))
+            .map_err(|e| Error::InvalidParams {
+                details: format!("Invalid lastSeenBlock: {}", e),
+            }),
+        None => Ok(None),
+    }
+}
+
+struct Subscription {
+    /// Websocket connection, that made subscription.
+    connection: u64,
+    kind: SubscriptionKind,
+    sink: Sink<RPCPubSubResult>,
+    /// Next block to notify about, new subscriptions without `lastSeenBlock`
+    /// start from the next polled block.
+    next_block: Option<u64>,
+}
+
+/// Active subscriptions of all websocket connections.
+pub struct Subscriptions {
+    max_per_connection: usize,
+    max_total: usize,
+    active: Mutex<HashMap<SubscriptionId, Subscription>>,
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION,
+            DEFAULT_MAX_SUBSCRIPTIONS,
+        )
+    }
+}
+
+impl Subscriptions {
+    pub fn new(max_per_connection: usize, max_total: usize) -> Self {
+        Self {
+            max_per_connection,
+            max_total,
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers subscription of `connection`,
+    /// subscriber is rejected if connection or all connections reached their limit.
+    fn add(
+        &self,
+        connection: u64,
+        kind: SubscriptionKind,
+        last_seen_block: Option<u64>,
+        subscriber: Subscriber<RPCPubSubResult>,
+    ) -> Option<SubscriptionId> {
+        let mut active = self.active.lock().unwrap();
+        let connection_subscriptions = active
+            .values()
+            .filter(|subscription| subscription.connection == connection)
+            .count();
+        let max = if connection_subscriptions >= self.max_per_connection {
+            Some(self.max_per_connection)
+        } else if active.len() >= self.max_total {
+            Some(self.max_total)
+        } else {
+            None
+        };
+        if let Some(max) = max {
+            let error = Error::TooManySubscriptions { max };
+            subscriber.reject(error.into()).unwrap_or_default();
+            return None;
+        }
+
+        // ids are random, so they can't be guessed by other connections
+        let id = loop {
+            let id = SubscriptionId::String(format!("{:#x}", rand::random::<u128>()));
+            if !active.contains_key(&id) {
+                break id;
+            }
+        };
+        let sink = subscriber.assign_id(id.clone()).ok()?;
+        let subscription = Subscription {
+            connection,
+            kind,
+            sink,
+            next_block: last_seen_block.map(|block| block.saturating_add(1)),
+        };
+        active.insert(id.clone(), subscription);
+        Some(id)
+    }
+
+    fn remove(&self, id: &SubscriptionId) -> bool {
+        self.active.lock().unwrap().remove(id).is_some()
+    }
+
+    /// Removes subscription, only if it was made by `connection`.
+    fn remove_owned(&self, connection: u64, id: &SubscriptionId) -> bool {
+        let mut active = self.active.lock().unwrap();
+        match active.get(id) {
+            Some(subscription) if subscription.connection == connection => {
+                active.remove(id).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn has_new_heads(&self) -> bool {
+        self.active
+            .lock()
+            .unwrap()
+            .values()
+            .any(|subscription| matches!(subscription.kind, SubscriptionKind::NewHeads))
+    }
+
+    fn has_logs(&self) -> bool {
+        self.active
+            .lock()
+            .unwrap()
+            .values()
+            .any(|subscription| matches!(subscription.kind, SubscriptionKind::Logs(_)))
+    }
+
+    /// Returns first block to poll, so that every block subscription gets blocks after its
+    /// `next_block`, but not older than `MAX_HEADS_PER_POLL` blocks before `latest`.
+    /// New subscriptions without `lastSeenBlock` start from `live_first`.
+    fn first_block(&self, live_first: u64, latest: u64) -> Option<u64> {
+        let lowest = latest.saturating_sub(MAX_HEADS_PER_POLL - 1);
+        self.active
+            .lock()
+            .unwrap()
+            .values_mut()
+            .filter(|subscription| subscription.kind.is_block_subscription())
+            .map(|subscription| *subscription.next_block.get_or_insert(live_first))
+            .min()
+            .map(|first| first.max(lowest))
+    }
+
+    /// Sends notifications, returned by `notifications` for subscription,
+    /// subscriptions of closed connections are dropped.
+    fn notify<F>(&self, mut notifications: F)
+    where
+        F: FnMut(&mut Subscription) -> Vec<RPCPubSubResult>,
+    {
+        self.active.lock().unwrap().retain(|id, subscription| {
+            for notification in notifications(subscription) {
+                if let Err(e) = subscription.sink.notify(Ok(notification)) {
+                    debug!("Dropping subscription {:?}: {:?}", id, e);
+                    return false;
+                }
+            }
+            true
+        });
+    }
+
+    /// Sends header and logs of block to subscriptions, that wait for this block.
+    fn notify_block(&self, number: u64, header: &RPCBlock, logs: &[RPCLog]) {
+        self.notify(|subscription| {
+            match subscription.next_block {
+                Some(next) if next <= number => subscription.next_block = Some(number + 1),
+                _ => return vec![],
+            }
+            match &subscription.kind {
+                SubscriptionKind::NewHeads => vec![RPCPubSubResult::Header(header.clone())],
+                SubscriptionKind::Logs(filter) => logs
+                    .iter()
+                    .filter(|log| filter.is_log_match(&(*log).clone().into()))
+                    .cloned()
+                    .map(RPCPubSubResult::Log)
+                    .collect(),
+                SubscriptionKind::NewPendingTransactions => vec![],
+            }
+        });
+    }
+
+    fn notify_pending_transaction(&self, hash: H256) {
+        self.notify(|subscription| match subscription.kind {
+            SubscriptionKind::NewPendingTransactions => {
+                vec![RPCPubSubResult::TransactionHash(Hex(hash))]
+            }
+            _ => vec![],
+        });
+    }
+}
+
+pub struct EthPubSubImpl;
+
+impl EthPubSubERPC for EthPubSubImpl {
+    type Metadata = WsMetadata;
+
+    fn subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RPCPubSubResult>,
+        kind: String,
+        params: Option<serde_json::Value>,
+    ) {
+        let parsed = last_seen_block(params.as_ref()).and_then(|last_seen| {
+            SubscriptionKind::parse(&kind, params).map(|parsed| (parsed, last_seen))
+        });
+        let (kind, last_seen_block) = match parsed {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                subscriber.reject(error.into()).unwrap_or_default();
+                return;
+            }
+        };
+        let subscriptions = &meta.bridge.subscriptions;
+        let id = match subscriptions.add(meta.connection, kind, last_seen_block, subscriber) {
+            Some(id) => id,
+            None => return,
+        };
+        debug!("New subscription {:?}", id);
+        if let Some(session) = &meta.session {
+            let bridge = meta.bridge.clone();
+            session.on_drop(move || {
+                if bridge.subscriptions.remove(&id) {
+                    debug!("Connection closed, subscription {:?} removed", id);
+                }
+            });
+        }
+    }
+
+    fn unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> EvmResult<bool> {
+        Ok(meta.map_or(false, |meta| {
+            meta.bridge.subscriptions.remove_owned(meta.connection, &id)
+        }))
+    }
+}
+
+/// Websocket server handler: serves subscriptions,
+/// and passes other requests to regular handler, with bridge as metadata.
+pub fn ws_io<S>(io: MetaIoHandler<Arc<EvmBridge>, S>) -> MetaIoHandler<WsMetadata, WsRouter<S>>
+where
+    S: Middleware<Arc<EvmBridge>>,
+{
+    let mut ws_io = MetaIoHandler::with_middleware(WsRouter { io });
+    ws_io.extend_with(EthPubSubImpl.to_delegate());
+    ws_io
+}
+
+pub struct WsRouter<S: Middleware<Arc<EvmBridge>>> {
+    io: MetaIoHandler<Arc<EvmBridge>, S>,
+}
+
+fn is_pubsub_call(call: &Call) -> bool {
+    let method = match call {
+        Call::MethodCall(call) => &call.method,
+        Call::Notification(notification) => &notification.method,
+        Call::Invalid { .. } => return false,
+    };
+    PUBSUB_METHODS.contains(&method.as_str())
+}
+
+impl<S: Middleware<Arc<EvmBridge>>> Middleware<WsMetadata> for WsRouter<S> {
+    type Future = BoxFuture<Option<Response>>;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_request<F, X>(
+        &self,
+        request: Request,
+        meta: WsMetadata,
+        next: F,
+    ) -> FutureEither<Self::Future, X>
+    where
+        F: Fn(Request, WsMetadata) -> X + Send + Sync,
+        X: Future<Output = Option<Response>> + Send + 'static,
+    {
+        let has_pubsub_calls = match &request {
+            Request::Single(call) => is_pubsub_call(call),
+            Request::Batch(calls) => calls.iter().any(is_pubsub_call),
+        };
+        if has_pubsub_calls {
+            // regular calls of mixed batch are routed by `on_call`
+            FutureEither::Right(next(request, meta))
+        } else {
+            FutureEither::Left(Box::pin(self.io.handle_rpc_request(request, meta.bridge)))
+        }
+    }
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: WsMetadata,
+        next: F,
+    ) -> FutureEither<Self::CallFuture, X>
+    where
+        F: Fn(Call, WsMetadata) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        if is_pubsub_call(&call) {
+            FutureEither::Right(next(call, meta))
+        } else {
+            FutureEither::Left(Box::pin(self.io.handle_call(call, meta.bridge)))
+        }
+    }
+}
+
+pub(crate) fn block_header(bridge: &EvmBridge, number: u64) -> Option<RPCBlock> {
+    let block: Option<RPCBlock> = bridge
+        .rpc_client
+        .send(
+            RpcRequest::EthGetBlockByNumber,
+            json!([BlockId::Num(Hex(number)), false]),
+        )
+        .map_err(|e| debug!("Unable to get block {}: {:?}", number, e))
+        .ok()?;
+    block.map(|block| RPCBlock {
+        transactions: Either::Left(vec![]),
+        ..block
+    })
+}
+
+fn block_logs(bridge: &EvmBridge, number: u64) -> Option<Vec<RPCLog>> {
+    let filter = RPCLogFilter {
+        from_block: Some(BlockId::Num(Hex(number))),
+        to_block: Some(BlockId::Num(Hex(number))),
+        address: None,
+        topics: None,
+    };
+    bridge
+        .rpc_client
+        .send(RpcRequest::EthGetLogs, json!([filter]))
+        .map_err(|e| debug!("Unable to get logs of block {}: {:?}", number, e))
+        .ok()
+}
+
+/// Polls latest block, and sends headers of new blocks to `newHeads` subscribers,
+/// and their logs to `logs` subscribers with matching filter.
+pub async fn worker_new_heads(bridge: Arc<EvmBridge>) {
+    info!("Running new heads notifier task...");
+    let mut last_notified = None;
+    loop {
+        tokio::time::sleep(NEW_HEADS_WORKER_PAUSE).await;
+
+        let has_new_heads = bridge.subscriptions.has_new_heads();
+        let has_logs = bridge.subscriptions.has_logs();
+        if !has_new_heads && !has_logs {
+            last_notified = None;
+            continue;
+        }
+        let latest: Hex<u64> = match bridge
+            .rpc_client
+            .send(RpcRequest::EthBlockNumber, json!([]))
+        {
+            Ok(latest) => latest,
+            Err(e) => {
+                debug!("Unable to get latest block: {:?}", e);
+                continue;
+            }
+        };
+        let live_first = match last_notified {
+            Some(last) => last + 1,
+            None => latest.0,
+        };
+        let first = match bridge.subscriptions.first_block(live_first, latest.0) {
+            Some(first) => first,
+            None => continue,
+        };
+        for number in first..=latest.0 {
+            let header = match block_header(&bridge, number) {
+                Some(header) => header,
+                None => break,
+            };
+            let logs = if has_logs {
+                match block_logs(&bridge, number) {
+                    Some(logs) => logs,
+                    None => break,
+                }
+            } else {
+                vec![]
+            };
+            bridge.subscriptions.notify_block(number, &header, &logs);
+            last_notified = Some(last_notified.map_or(number, |last| last.max(number)));
+        }
+    }
+}
+
+/// Sends hashes of transactions, imported into the pool, to `newPendingTransactions` subscribers.
+/// If this task is too slow, notifications are skipped, so import is never blocked.
+pub async fn worker_pending_transactions(bridge: Arc<EvmBridge>) {
+    info!("Running pending transactions notifier task...");
+    let mut imported = bridge.pool.subscribe_imported();
+    loop {
+        match imported.recv().await {
+            Ok(hash) => bridge.subscriptions.notify_pending_transaction(hash),
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Skipped {} pending transaction notifications", skipped)
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::PooledTransaction;
+    use crate::tests::{mocked_bridge, signed_tx};
+    use evm_rpc::{pubsub::gen_client::Client as PubSubClient, Bytes};
+    use evm_state::{Address, H256, U256};
+    use jsonrpc_core::futures::{channel::mpsc, StreamExt};
+    use jsonrpc_core_client::transports::ws;
+    use jsonrpc_ws_server::Server;
+    use solana_client::mock_sender::Mocks;
+    use std::collections::HashSet;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn start_server(mocks: Mocks) -> (Arc<EvmBridge>, Server, String) {
+        let bridge = Arc::new(mocked_bridge(mocks));
+        let meta = bridge.clone();
+        let server = jsonrpc_ws_server::ServerBuilder::with_meta_extractor(
+            ws_io(MetaIoHandler::default()),
+            move |context: &RequestContext| WsMetadata::new(meta.clone(), context),
+        )
+        .start(&"127.0.0.1:0".parse().unwrap())
+        .unwrap();
+        let url = format!("ws://{}", server.addr());
+        (bridge, server, url)
+    }
+
+    fn block_mocks(block: &RPCBlock, logs: &[RPCLog]) -> Mocks {
+        vec![
+            (RpcRequest::EthBlockNumber, json!(Hex(7u64))),
+            (
+                RpcRequest::EthGetBlockByNumber,
+                serde_json::to_value(block).unwrap(),
+            ),
+            (RpcRequest::EthGetLogs, serde_json::to_value(logs).unwrap()),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_new_heads_subscription() {
+        let block = RPCBlock {
+            number: Hex(U256::from(7)),
+            transactions: Either::Left(vec![Hex(H256::repeat_byte(0x11))]),
+            ..RPCBlock::default()
+        };
+        let (bridge, server, url) = start_server(block_mocks(&block, &[]));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.spawn(worker_new_heads(bridge.clone()));
+        let (id, header) = rt.block_on(async {
+            let client: PubSubClient = ws::try_connect(&url).unwrap().await.unwrap();
+            let mut heads = client.subscribe("newHeads".to_string(), None).unwrap();
+            let header = tokio::time::timeout(TIMEOUT, heads.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let id = bridge
+                .subscriptions
+                .active
+                .lock()
+                .unwrap()
+                .keys()
+                .next()
+                .cloned();
+            (id, header)
+        });
+
+        let header = match header {
+            RPCPubSubResult::Header(header) => header,
+            other => panic!("Expected header, got {:?}", other),
+        };
+        assert_eq!(header.number, block.number);
+        assert_eq!(header.hash, block.hash);
+        assert!(matches!(header.transactions, Either::Left(hashes) if hashes.is_empty()));
+
+        assert!(bridge.subscriptions.remove(&id.unwrap()));
+        assert!(!bridge.subscriptions.has_new_heads());
+        server.close();
+    }
+
+    fn notified_blocks(receiver: &mut mpsc::UnboundedReceiver<String>) -> Vec<u64> {
+        std::iter::from_fn(|| receiver.try_next().ok().flatten())
+            .map(|notification| {
+                let notification: serde_json::Value = serde_json::from_str(&notification).unwrap();
+                let number = notification["params"]["result"]["number"].clone();
+                serde_json::from_value::<Hex<u64>>(number).unwrap().0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resubscribe_backfill() {
+        let subscriptions = Subscriptions::default();
+        let subscribe = |params: serde_json::Value| {
+            let (subscriber, _id_receiver, receiver) = Subscriber::new_test("eth_subscription");
+            let last_seen = last_seen_block(Some(&params)).unwrap();
+            let kind = SubscriptionKind::parse("newHeads", Some(params)).unwrap();
+            subscriptions.add(0, kind, last_seen, subscriber).unwrap();
+            receiver
+        };
+        let mut live = subscribe(json!({}));
+        let mut resumed = subscribe(json!({ "lastSeenBlock": Hex(97u64) }));
+        let mut lagging = subscribe(json!({ "lastSeenBlock": Hex(0u64) }));
+
+        let latest = 100;
+        let first = subscriptions.first_block(latest, latest).unwrap();
+        assert_eq!(first, latest - MAX_HEADS_PER_POLL + 1);
+        for number in first..=latest {
+            let header = RPCBlock {
+                number: Hex(U256::from(number)),
+                ..RPCBlock::default()
+            };
+            subscriptions.notify_block(number, &header, &[]);
+        }
+
+        assert_eq!(notified_blocks(&mut live), vec![100]);
+        assert_eq!(notified_blocks(&mut resumed), vec![98, 99, 100]);
+        assert_eq!(
+            notified_blocks(&mut lagging),
+            (first..=latest).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            subscriptions.first_block(latest + 1, latest),
+            Some(latest + 1)
+        );
+
+        let invalid = json!({ "lastSeenBlock": "latest" });
+        assert!(last_seen_block(Some(&invalid)).is_err());
+    }
+
+    #[test]
+    fn test_subscription_limits() {
+        let subscriptions = Subscriptions::new(2, 3);
+        let subscribe = |connection: u64| {
+            let (subscriber, mut id_receiver, _receiver) = Subscriber::new_test("eth_subscription");
+            let kind = SubscriptionKind::parse("newHeads", None).unwrap();
+            let id = subscriptions.add(connection, kind, None, subscriber);
+            let assigned = id_receiver.try_recv().unwrap().unwrap();
+            assert_eq!(id.is_some(), assigned.is_ok());
+            id
+        };
+
+        assert!(subscribe(1).is_some());
+        let id = subscribe(1).unwrap();
+        // limit of connection
+        assert!(subscribe(1).is_none());
+        assert!(subscribe(2).is_some());
+        // limit of all connections
+        assert!(subscribe(2).is_none());
+
+        assert!(subscriptions.remove(&id));
+        assert!(subscribe(2).is_some());
+        assert!(subscribe(1).is_none());
+    }
+
+    #[test]
+    fn test_unsubscribe_only_by_owner() {
+        let bridge = Arc::new(mocked_bridge(Mocks::default()));
+        let unsubscribe = |connection: u64, id: &SubscriptionId| {
+            let meta = WsMetadata {
+                bridge: bridge.clone(),
+                connection,
+                session: None,
+            };
+            EthPubSubImpl.unsubscribe(Some(meta), id.clone()).unwrap()
+        };
+        let subscribe = |connection: u64| {
+            let (subscriber, _id_receiver, _receiver) = Subscriber::new_test("eth_subscription");
+            let kind = SubscriptionKind::parse("newHeads", None).unwrap();
+            bridge
+                .subscriptions
+                .add(connection, kind, None, subscriber)
+                .unwrap()
+        };
+        let first = subscribe(1);
+        let second = subscribe(1);
+        assert_ne!(first, second);
+
+        // other connection can't unsubscribe, even if it knows id
+        assert!(!unsubscribe(2, &first));
+        assert!(bridge.subscriptions.has_new_heads());
+        assert!(unsubscribe(1, &first));
+        assert!(!unsubscribe(1, &first));
+        assert!(bridge.subscriptions.remove(&second));
+        assert!(!bridge.subscriptions.has_new_heads());
+    }
+
+    #[test]
+    fn test_logs_subscription() {
+        let block = RPCBlock {
+            number: Hex(U256::from(7)),
+            ..RPCBlock::default()
+        };
+        let emitter = Address::repeat_byte(0x33);
+        let transfer_topic = H256::repeat_byte(0x44);
+        let log = |address: Address, topic: H256, log_index: usize| RPCLog {
+            removed: false,
+            log_index: Hex(log_index),
+            transaction_index: Hex(0),
+            transaction_hash: Hex(H256::repeat_byte(0x11)),
+            block_hash: block.hash,
+            block_number: block.number,
+            address: Hex(address),
+            data: Bytes(vec![1, 2, 3]),
+            topics: vec![Hex(topic)],
+        };
+        // only the last one matches, the others would be received first otherwise
+        let logs = vec![
+            log(Address::repeat_byte(0x55), transfer_topic, 0),
+            log(emitter, H256::repeat_byte(0x66), 1),
+            log(emitter, transfer_topic, 2),
+        ];
+        let (bridge, server, url) = start_server(block_mocks(&block, &logs));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.spawn(worker_new_heads(bridge.clone()));
+        let received = rt.block_on(async {
+            let client: PubSubClient = ws::try_connect(&url).unwrap().await.unwrap();
+
+            let invalid_filter = json!({ "address": "not an address" });
+            assert!(client
+                .subscribe("logs".to_string(), Some(invalid_filter))
+                .unwrap()
+                .next()
+                .await
+                .unwrap()
+                .is_err());
+
+            let filter = json!({ "address": Hex(emitter), "topics": [Hex(transfer_topic)] });
+            let mut logs = client.subscribe("logs".to_string(), Some(filter)).unwrap();
+            tokio::time::timeout(TIMEOUT, logs.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap()
+        });
+
+        let received = match received {
+            RPCPubSubResult::Log(log) => log,
+            other => panic!("Expected log, got {:?}", other),
+        };
+        assert_eq!(received.address, Hex(emitter));
+        assert_eq!(received.topics, vec![Hex(transfer_topic)]);
+        assert_eq!(received.log_index, Hex(2));
+
+        // client is dropped with the runtime, so subscription is removed
+        drop(rt);
+        let started = std::time::Instant::now();
+        while bridge.subscriptions.has_logs() {
+            assert!(started.elapsed() < TIMEOUT, "Subscription was not removed");
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        server.close();
+    }
+
+    #[test]
+    fn test_pending_transactions_subscription() {
+        let (bridge, server, url) = start_server(Mocks::default());
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.spawn(worker_pending_transactions(bridge.clone()));
+        let (hash, received) = rt.block_on(async {
+            let client: PubSubClient = ws::try_connect(&url).unwrap().await.unwrap();
+            let mut pending = client
+                .subscribe("newPendingTransactions".to_string(), None)
+                .unwrap();
+
+            // transaction imported before subscription is registered would be missed
+            let started = std::time::Instant::now();
+            while bridge.subscriptions.active.lock().unwrap().is_empty() {
+                assert!(started.elapsed() < TIMEOUT, "Subscription was not added");
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+            let tx = signed_tx(0);
+            let hash = tx.tx_id_hash();
+            let tx = PooledTransaction::new(tx, HashSet::new(), sender).unwrap();
+            bridge.pool.import(tx).unwrap();
+
+            let received = tokio::time::timeout(TIMEOUT, pending.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            (hash, received)
+        });
+
+        assert!(matches!(received, RPCPubSubResult::TransactionHash(h) if h == Hex(hash)));
+        server.close();
+    }
+}
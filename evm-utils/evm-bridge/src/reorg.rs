@@ -0,0 +1,197 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use evm_rpc::{BlockId, Hex, RPCBlock, RPCReorgStats};
+use evm_state::H256;
+use log::*;
+use serde_json::json;
+use solana_client::rpc_request::RpcRequest;
+use solana_metrics::datapoint_info;
+
+use crate::EvmBridge;
+
+/// How many blocks below the latest one are compared with previously seen hashes.
+const MAX_TRACKED_DEPTH: u64 = 64;
+/// Stats are reset after this period.
+const STATS_WINDOW: Duration = Duration::from_secs(60 * 60);
+const REORG_WORKER_PAUSE: Duration = Duration::from_secs(1);
+
+/// Remembers hashes of recent blocks, and records reorg when known block number gets new hash.
+#[derive(Debug)]
+pub struct ReorgTracker {
+    blocks: BTreeMap<u64, H256>,
+    window: Duration,
+    window_start: SystemTime,
+    window_started: Instant,
+    depths: Vec<u64>,
+}
+
+impl Default for ReorgTracker {
+    fn default() -> Self {
+        Self {
+            blocks: BTreeMap::new(),
+            window: STATS_WINDOW,
+            window_start: SystemTime::now(),
+            window_started: Instant::now(),
+            depths: Vec::new(),
+        }
+    }
+}
+
+impl ReorgTracker {
+    /// Walks back from `latest` block, until block with already known hash is found.
+    /// Returns depth of reorg, if some of known blocks got new hashes.
+    pub fn observe<F>(&mut self, latest: u64, mut block_hash: F) -> Option<u64>
+    where
+        F: FnMut(u64) -> Option<H256>,
+    {
+        let lowest = latest.saturating_sub(MAX_TRACKED_DEPTH - 1);
+        let mut depth = 0;
+        for number in (lowest..=latest).rev() {
+            let hash = match block_hash(number) {
+                Some(hash) => hash,
+                None => break,
+            };
+            match self.blocks.insert(number, hash) {
+                Some(known) if known == hash => break,
+                Some(_) => depth += 1,
+                None => {}
+            }
+        }
+        // forget blocks, that are too deep to be checked again
+        self.blocks = self.blocks.split_off(&lowest);
+
+        if depth == 0 {
+            return None;
+        }
+        self.reset_outdated_window();
+        self.depths.push(depth);
+        Some(depth)
+    }
+
+    pub fn stats(&mut self) -> RPCReorgStats {
+        self.reset_outdated_window();
+        let reorgs = self.depths.len() as u64;
+        let max_depth = self.depths.iter().copied().max().unwrap_or_default();
+        let avg_depth = if reorgs == 0 {
+            0.0
+        } else {
+            self.depths.iter().sum::<u64>() as f64 / reorgs as f64
+        };
+        let window_start = self
+            .window_start
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        RPCReorgStats {
+            window_start: Hex(window_start),
+            reorgs: Hex(reorgs),
+            max_depth: Hex(max_depth),
+            avg_depth,
+        }
+    }
+
+    fn reset_outdated_window(&mut self) {
+        if self.window_started.elapsed() >= self.window {
+            self.window_start = SystemTime::now();
+            self.window_started = Instant::now();
+            self.depths.clear();
+        }
+    }
+}
+
+fn block_hash(bridge: &EvmBridge, number: u64) -> Option<H256> {
+    let block: Option<RPCBlock> = bridge
+        .rpc_client
+        .send(
+            RpcRequest::EthGetBlockByNumber,
+            json!([BlockId::Num(Hex(number)), false]),
+        )
+        .map_err(|e| debug!("Unable to get block {}: {:?}", number, e))
+        .ok()?;
+    block.map(|block| block.hash.0)
+}
+
+/// Polls latest blocks and tracks reorgs of evm chain.
+pub async fn worker_reorg_tracker(bridge: Arc<EvmBridge>) {
+    info!("Running reorg tracker task...");
+    loop {
+        tokio::time::sleep(REORG_WORKER_PAUSE).await;
+
+        let latest: Hex<u64> = match bridge
+            .rpc_client
+            .send(RpcRequest::EthBlockNumber, json!([]))
+        {
+            Ok(latest) => latest,
+            Err(e) => {
+                debug!("Unable to get latest block: {:?}", e);
+                continue;
+            }
+        };
+        let depth = bridge
+            .reorg_tracker
+            .lock()
+            .unwrap()
+            .observe(latest.0, |number| block_hash(&bridge, number));
+        if let Some(depth) = depth {
+            warn!("Reorg of depth {} detected at block {}", depth, latest.0);
+            datapoint_info!(
+                "evm_bridge-reorg",
+                ("depth", depth as i64, i64),
+                ("latest_block", latest.0 as i64, i64)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(fork: u8) -> impl FnMut(u64) -> Option<H256> {
+        move |number| {
+            let mut hash = H256::from_low_u64_be(number);
+            hash.0[0] = fork;
+            Some(hash)
+        }
+    }
+
+    #[test]
+    fn test_reorg_depth_recorded() {
+        let mut tracker = ReorgTracker::default();
+        assert_eq!(tracker.observe(10, chain(0)), None);
+        assert_eq!(tracker.observe(11, chain(0)), None);
+
+        // blocks 9..=11 replaced by another fork
+        let reorged = |number| {
+            if number >= 9 {
+                chain(1)(number)
+            } else {
+                chain(0)(number)
+            }
+        };
+        assert_eq!(tracker.observe(12, reorged), Some(3));
+        assert_eq!(tracker.observe(12, reorged), None);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.reorgs, Hex(1));
+        assert_eq!(stats.max_depth, Hex(3));
+        assert!((stats.avg_depth - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_stats_window_reset() {
+        let mut tracker = ReorgTracker::default();
+        tracker.observe(5, chain(0));
+        assert_eq!(tracker.observe(5, chain(1)), Some(6));
+        assert_eq!(tracker.stats().reorgs, Hex(1));
+
+        tracker.window = Duration::from_secs(0);
+        let stats = tracker.stats();
+        assert_eq!(stats.reorgs, Hex(0));
+        assert_eq!(stats.max_depth, Hex(0));
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+use std::future::{ready, Future};
+
+use jsonrpc_core::{
+    futures::future::Either, middleware, BoxFuture, Call, Error, ErrorCode, Id, Metadata,
+    Middleware, Output, Request, Response,
+};
+
+/// Maximum length of string request id.
+const MAX_ID_LENGTH: usize = 128;
+
+/// Validates request ids, before passing request to handlers.
+/// Ids of unsupported types (objects, arrays, floats) are already rejected by `jsonrpc_core`,
+/// this middleware additionally rejects oversized ids, and ids reused in the same batch.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdValidation {
+    enabled: bool,
+}
+
+impl RequestIdValidation {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Returns error response, if request id of call is invalid.
+    fn check_call(call: &Call, duplicates: &HashSet<Id>) -> Option<Output> {
+        let call = match call {
+            Call::MethodCall(call) => call,
+            _ => return None,
+        };
+        let message = match &call.id {
+            Id::Str(id) if id.len() > MAX_ID_LENGTH => {
+                format!("Request id is longer than {} characters", MAX_ID_LENGTH)
+            }
+            id if duplicates.contains(id) => format!("Request id {:?} is reused in batch", id),
+            _ => return None,
+        };
+        let error = Error {
+            code: ErrorCode::InvalidRequest,
+            message,
+            data: None,
+        };
+        Some(Output::from(Err(error), call.id.clone(), call.jsonrpc))
+    }
+
+    fn duplicated_ids(calls: &[Call]) -> HashSet<Id> {
+        let mut seen = HashSet::new();
+        calls
+            .iter()
+            .filter_map(|call| match call {
+                Call::MethodCall(call) => Some(&call.id),
+                _ => None,
+            })
+            .filter(|id| !seen.insert(*id))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<M: Metadata> Middleware<M> for RequestIdValidation {
+    type Future = BoxFuture<Option<Response>>;
+    type CallFuture = middleware::NoopCallFuture;
+
+    fn on_request<F, X>(&self, request: Request, meta: M, next: F) -> Either<Self::Future, X>
+    where
+        F: Fn(Request, M) -> X + Send + Sync,
+        X: Future<Output = Option<Response>> + Send + 'static,
+    {
+        if !self.enabled {
+            return Either::Right(next(request, meta));
+        }
+        let calls = match request {
+            Request::Single(call) => {
+                return match Self::check_call(&call, &HashSet::new()) {
+                    Some(failure) => Either::Left(Box::pin(ready(Some(Response::Single(failure))))),
+                    None => Either::Right(next(Request::Single(call), meta)),
+                };
+            }
+            Request::Batch(calls) => calls,
+        };
+
+        let duplicates = Self::duplicated_ids(&calls);
+        let mut rejected = Vec::new();
+        let mut accepted = Vec::new();
+        for call in calls {
+            match Self::check_call(&call, &duplicates) {
+                Some(failure) => rejected.push(failure),
+                None => accepted.push(call),
+            }
+        }
+        if rejected.is_empty() {
+            return Either::Right(next(Request::Batch(accepted), meta));
+        }
+        if accepted.is_empty() {
+            return Either::Left(Box::pin(ready(Some(Response::Batch(rejected)))));
+        }
+
+        let response = next(Request::Batch(accepted), meta);
+        Either::Left(Box::pin(async move {
+            let mut outputs = match response.await {
+                Some(Response::Batch(outputs)) => outputs,
+                Some(Response::Single(output)) => vec![output],
+                None => vec![],
+            };
+            outputs.extend(rejected);
+            Some(Response::Batch(outputs))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{MetaIoHandler, Value};
+
+    fn io() -> MetaIoHandler<(), RequestIdValidation> {
+        let mut io = MetaIoHandler::with_middleware(RequestIdValidation::new(true));
+        io.add_method("ping", |_| async { Ok(Value::String("pong".to_string())) });
+        io
+    }
+
+    async fn handle(request: &str) -> Value {
+        let response = io().handle_request(request, ()).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_valid_id() {
+        let response = handle(r#"{"jsonrpc":"2.0","id":"abc","method":"ping"}"#).await;
+        assert_eq!(response["id"], "abc");
+        assert_eq!(response["result"], "pong");
+
+        let response = handle(r#"{"jsonrpc":"2.0","id":null,"method":"ping"}"#).await;
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["result"], "pong");
+    }
+
+    #[tokio::test]
+    async fn test_object_id_rejected() {
+        let response = handle(r#"{"jsonrpc":"2.0","id":{"a":1},"method":"ping"}"#).await;
+        // rejected by jsonrpc_core as invalid request
+        assert!(response["error"]["code"].is_i64());
+        assert!(response.get("result").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_id_rejected() {
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","id":"{}","method":"ping"}}"#,
+            "a".repeat(MAX_ID_LENGTH + 1)
+        );
+        let response = handle(&request).await;
+        assert_eq!(response["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_ids_in_batch() {
+        let response = handle(
+            r#"[
+                {"jsonrpc":"2.0","id":1,"method":"ping"},
+                {"jsonrpc":"2.0","id":2,"method":"ping"},
+                {"jsonrpc":"2.0","id":1,"method":"ping"}
+            ]"#,
+        )
+        .await;
+        let outputs = response.as_array().unwrap();
+        assert_eq!(outputs.len(), 3);
+        let (failed, succeeded): (Vec<_>, Vec<_>) = outputs
+            .iter()
+            .partition(|output| output.get("error").is_some());
+        assert_eq!(succeeded.len(), 1);
+        assert_eq!(succeeded[0]["id"], 2);
+        assert_eq!(failed.len(), 2);
+        assert!(failed.iter().all(|output| output["id"] == 1));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_validation() {
+        let mut io = MetaIoHandler::with_middleware(RequestIdValidation::new(false));
+        io.add_method("ping", |_| async { Ok(Value::String("pong".to_string())) });
+        let request = r#"[{"jsonrpc":"2.0","id":1,"method":"ping"},{"jsonrpc":"2.0","id":1,"method":"ping"}]"#;
+        let response = io.handle_request(request, ()).await.unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert!(response
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|output| output["result"] == "pong"));
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use evm_rpc::error::NATIVE_RPC_ERROR;
+use jsonrpc_core::{
+    futures::future::Either, middleware, BoxFuture, Call, ErrorCode, Metadata, Middleware, Output,
+    Success, Value,
+};
+use solana_metrics::datapoint_info;
+
+/// Name of http header with age in seconds of the oldest stale value in response.
+pub const STALE_HEADER: &str = "x-velas-stale";
+
+/// Maximum number of cached responses.
+const MAX_CACHED_RESPONSES: usize = 10_000;
+
+/// Idempotent methods, which responses can be served from cache.
+const CACHED_METHODS: &[&str] = &[
+    "net_version",
+    "eth_chainId",
+    "eth_blockNumber",
+    "eth_getBalance",
+    "eth_getStorageAt",
+    "eth_getTransactionCount",
+    "eth_getCode",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getTransactionByHash",
+    "eth_getTransactionReceipt",
+];
+
+tokio::task_local! {
+    /// Age of the oldest stale value, served in current request.
+    static STALE_AGE: Arc<Mutex<Option<Duration>>>;
+}
+
+/// Runs request handling, and returns its response together with age of the oldest stale value in it.
+pub async fn track<X, T>(response: X) -> (T, Option<Duration>)
+where
+    X: Future<Output = T>,
+{
+    let age = Arc::new(Mutex::new(None));
+    let response = STALE_AGE.scope(age.clone(), response).await;
+    let age = *age.lock().unwrap();
+    (response, age)
+}
+
+fn record_stale_age(age: Duration) {
+    // websocket requests are not tracked
+    let _ = STALE_AGE.try_with(|oldest| {
+        let mut oldest = oldest.lock().unwrap();
+        *oldest = Some(oldest.map_or(age, |oldest| oldest.max(age)));
+    });
+}
+
+/// Remembers latest successful responses of idempotent methods,
+/// and serves them instead of errors, while upstream node is unavailable.
+#[derive(Debug, Clone, Default)]
+pub struct StaleReads {
+    max_staleness: Option<Duration>,
+    cache: Arc<Mutex<HashMap<(String, String), (Value, Instant)>>>,
+}
+
+impl StaleReads {
+    pub fn new(max_staleness: Option<Duration>) -> Self {
+        Self {
+            max_staleness,
+            cache: Default::default(),
+        }
+    }
+
+    fn save(&self, key: (String, String), result: Value) {
+        let max_staleness = match self.max_staleness {
+            Some(max_staleness) => max_staleness,
+            None => return,
+        };
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_RESPONSES && !cache.contains_key(&key) {
+            cache.retain(|_, (_, saved)| saved.elapsed() <= max_staleness);
+            if cache.len() >= MAX_CACHED_RESPONSES {
+                return;
+            }
+        }
+        cache.insert(key, (result, Instant::now()));
+    }
+
+    fn load(&self, key: &(String, String)) -> Option<(Value, Duration)> {
+        let max_staleness = self.max_staleness?;
+        let cache = self.cache.lock().unwrap();
+        let (result, saved) = cache.get(key)?;
+        let age = saved.elapsed();
+        (age <= max_staleness).then(|| (result.clone(), age))
+    }
+}
+
+impl<M: Metadata> Middleware<M> for StaleReads {
+    type Future = middleware::NoopFuture;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, M) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let key = match (&self.max_staleness, &call) {
+            (Some(_), Call::MethodCall(method_call))
+                if CACHED_METHODS.contains(&method_call.method.as_str()) =>
+            {
+                let params = serde_json::to_string(&method_call.params).unwrap_or_default();
+                (method_call.method.clone(), params)
+            }
+            _ => return Either::Right(next(call, meta)),
+        };
+        let response = next(call, meta);
+        let stale_reads = self.clone();
+        Either::Left(Box::pin(async move {
+            match response.await {
+                Some(Output::Success(success)) => {
+                    stale_reads.save(key, success.result.clone());
+                    Some(Output::Success(success))
+                }
+                Some(Output::Failure(failure))
+                    if failure.error.code == ErrorCode::ServerError(NATIVE_RPC_ERROR) =>
+                {
+                    let (result, age) = match stale_reads.load(&key) {
+                        Some(stale) => stale,
+                        None => return Some(Output::Failure(failure)),
+                    };
+                    datapoint_info!(
+                        "evm_bridge-stale_read",
+                        ("method", key.0, String),
+                        ("age_ms", age.as_millis() as i64, i64)
+                    );
+                    record_stale_age(age);
+                    Some(Output::Success(Success {
+                        jsonrpc: failure.jsonrpc,
+                        result,
+                        id: failure.id,
+                    }))
+                }
+                output => output,
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::{Error, MetaIoHandler};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const BALANCE_REQUEST: &str =
+        r#"{"jsonrpc":"2.0","id":1,"method":"eth_getBalance","params":["0x01","latest"]}"#;
+
+    fn io(
+        max_staleness: Duration,
+        upstream_down: Arc<AtomicBool>,
+    ) -> MetaIoHandler<(), StaleReads> {
+        let mut io = MetaIoHandler::with_middleware(StaleReads::new(Some(max_staleness)));
+        io.add_method("eth_getBalance", move |_| {
+            let upstream_down = upstream_down.load(Ordering::Relaxed);
+            async move {
+                if upstream_down {
+                    return Err(Error {
+                        code: ErrorCode::ServerError(NATIVE_RPC_ERROR),
+                        message: "Connection refused".to_string(),
+                        data: None,
+                    });
+                }
+                Ok(Value::String("0x2a".to_string()))
+            }
+        });
+        io
+    }
+
+    async fn get_balance(io: &MetaIoHandler<(), StaleReads>) -> (Value, Option<Duration>) {
+        let (response, age) = track(io.handle_request(BALANCE_REQUEST, ())).await;
+        (serde_json::from_str(&response.unwrap()).unwrap(), age)
+    }
+
+    #[tokio::test]
+    async fn test_stale_balance_served_on_upstream_failure() {
+        let upstream_down = Arc::new(AtomicBool::new(false));
+        let io = io(Duration::from_secs(60), upstream_down.clone());
+
+        let (response, age) = get_balance(&io).await;
+        assert_eq!(response["result"], "0x2a");
+        assert_eq!(age, None);
+
+        upstream_down.store(true, Ordering::Relaxed);
+        let (response, age) = get_balance(&io).await;
+        assert_eq!(response["result"], "0x2a");
+        assert_eq!(response["id"], 1);
+        assert!(age.unwrap() <= Duration::from_secs(60));
+
+        // other params were never cached
+        let request =
+            r#"{"jsonrpc":"2.0","id":2,"method":"eth_getBalance","params":["0x02","latest"]}"#;
+        let (response, age) = track(io.handle_request(request, ())).await;
+        let response: Value = serde_json::from_str(&response.unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], NATIVE_RPC_ERROR);
+        assert_eq!(age, None);
+    }
+
+    #[tokio::test]
+    async fn test_stale_header() {
+        use crate::compression::CompressionMiddleware;
+        use jsonrpc_http_server::{hyper, RequestMiddleware, RequestMiddlewareAction};
+
+        let upstream_down = Arc::new(AtomicBool::new(false));
+        let io = io(Duration::from_secs(60), upstream_down.clone());
+        let middleware = CompressionMiddleware::uncompressed(io, ()).with_stale_header();
+        let respond = || async {
+            let request = hyper::Request::builder()
+                .method(hyper::Method::POST)
+                .body(hyper::Body::from(BALANCE_REQUEST))
+                .unwrap();
+            let response = match middleware.on_request(request) {
+                RequestMiddlewareAction::Respond { response, .. } => response.await.unwrap(),
+                RequestMiddlewareAction::Proceed { .. } => panic!("request should be handled"),
+            };
+            let stale = response
+                .headers()
+                .get(STALE_HEADER)
+                .map(|value| value.to_str().unwrap().to_string());
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            (body["result"].clone(), stale)
+        };
+
+        let (result, stale) = respond().await;
+        assert_eq!(result, "0x2a");
+        assert_eq!(stale, None);
+
+        upstream_down.store(true, Ordering::Relaxed);
+        let (result, stale) = respond().await;
+        assert_eq!(result, "0x2a");
+        assert_eq!(stale.as_deref(), Some("0"));
+    }
+
+    #[tokio::test]
+    async fn test_too_stale_balance_not_served() {
+        let upstream_down = Arc::new(AtomicBool::new(false));
+        let io = io(Duration::from_millis(10), upstream_down.clone());
+        get_balance(&io).await;
+
+        upstream_down.store(true, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let (response, age) = get_balance(&io).await;
+        assert_eq!(response["error"]["code"], NATIVE_RPC_ERROR);
+        assert_eq!(age, None);
+    }
+}
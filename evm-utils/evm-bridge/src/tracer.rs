@@ -0,0 +1,97 @@
+use std::error::Error;
+
+use once_cell::sync::OnceCell;
+use opentelemetry::sdk::trace::Tracer;
+use tokio::runtime::{Builder, Handle, Runtime};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::{Layer, SubscriberExt},
+    util::SubscriberInitExt,
+};
+
+/// Runtime of span exporter, used when tracing is initialized outside of tokio runtime.
+static EXPORTER_RUNTIME: OnceCell<Runtime> = OnceCell::new();
+
+/// Returns handle of current tokio runtime, or of dedicated exporter runtime if there is none.
+fn exporter_runtime() -> std::io::Result<Handle> {
+    if let Ok(handle) = Handle::try_current() {
+        return Ok(handle);
+    }
+    EXPORTER_RUNTIME
+        .get_or_try_init(|| {
+            Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("evm-bridge-tracer")
+                .enable_all()
+                .build()
+        })
+        .map(|runtime| runtime.handle().clone())
+}
+
+/// Creates tracer, that exports spans to jaeger collector in batches.
+pub fn jaeger_tracer(collector: String) -> Result<Tracer, Box<dyn Error>> {
+    // batch exporter spawns its task on runtime of current context
+    let _guard = exporter_runtime()?.enter();
+    let tracer = opentelemetry_jaeger::new_pipeline()
+        .with_service_name("evm-bridge-tracer")
+        .with_collector_endpoint(collector)
+        .install_batch(opentelemetry::runtime::Tokio)?;
+    Ok(tracer)
+}
+
+/// Installs global subscriber, that prints spans filtered by `RUST_LOG`, and exports them to jaeger collector.
+pub fn init(collector: String) -> Result<(), Box<dyn Error>> {
+    let fmt_filter = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|rust_log| match rust_log.parse::<Targets>() {
+            Ok(targets) => Some(targets),
+            Err(e) => {
+                eprintln!("failed to parse `RUST_LOG={:?}`: {}", rust_log, e);
+                None
+            }
+        })
+        .unwrap_or_else(|| Targets::default().with_default(LevelFilter::WARN));
+
+    let opentelemetry = tracing_opentelemetry::layer().with_tracer(jaeger_tracer(collector)?);
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(fmt_filter))
+        .with(opentelemetry)
+        .try_init()?;
+    Ok(())
+}
+
+/// Exports spans that are still buffered, should be called before exit.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    const COLLECTOR: &str = "http://127.0.0.1:14268/api/traces";
+
+    fn assert_span_exported(tracer: Tracer) {
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("test_span");
+            let _entered = span.enter();
+            assert!(span.context().span().span_context().is_valid());
+        });
+    }
+
+    #[test]
+    fn test_tracer_outside_of_runtime() {
+        assert!(Handle::try_current().is_err());
+        assert_span_exported(jaeger_tracer(COLLECTOR.to_string()).unwrap());
+        assert!(EXPORTER_RUNTIME.get().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tracer_inside_of_runtime() {
+        assert_span_exported(jaeger_tracer(COLLECTOR.to_string()).unwrap());
+    }
+}
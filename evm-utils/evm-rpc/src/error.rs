@@ -49,6 +49,18 @@ pub enum Error {
         source: ParseIntError,
     },
 
+    #[snafu(display(
+        "Invalid hex digit {:?} at position {} in integer({})",
+        character,
+        index,
+        input_data
+    ))]
+    InvalidHexDigit {
+        input_data: String,
+        character: char,
+        index: usize,
+    },
+
     #[snafu(display("Failed to parse BigInt({})", input_data))]
     BigIntError {
         input_data: String,
@@ -82,6 +94,8 @@ pub enum Error {
 
     #[snafu(display("Method unimplemented"))]
     Unimplemented {},
+    #[snafu(display("Method {} is disabled", method))]
+    MethodDisabled { method: String },
     #[snafu(display("ServerError(-32005)"))]
     ServerError {},
 
@@ -95,7 +109,7 @@ pub enum Error {
     KeyNotFound { account: evm_state::H160 },
     #[snafu(display("execution error: {}", format_data_with_error(data, error)))]
     CallError { data: Bytes, error: ExitError },
-    #[snafu(display("execution reverted: {}", format_data(data)))]
+    #[snafu(display("{}", revert_message(data)))]
     CallRevert { data: Bytes, error: ExitRevert },
     #[snafu(display("Fatal evm error: {:?}", error))]
     CallFatal { error: ExitFatal },
@@ -103,7 +117,40 @@ pub enum Error {
     GasPriceTooLow { need: U256 },
     #[snafu(display("Transaction was removed from mempool"))]
     TransactionRemoved {},
-    // InvalidParams {},
+    #[snafu(display("Transaction was replaced by transaction {:?}", hash))]
+    TransactionReplaced { hash: evm_state::H256 },
+    #[snafu(display("Nonce too low, got={}, expected={}", got, expected))]
+    NonceTooLow { got: U256, expected: U256 },
+    #[snafu(display("No contract code at address {:?}", address))]
+    NoContractAtAddress { address: evm_state::H160 },
+    #[snafu(display("Invalid params: {}", details))]
+    InvalidParams { details: String },
+    #[snafu(display("Input of {} bytes is too large, max={}", size, max_size))]
+    InputTooLarge { size: usize, max_size: usize },
+    #[snafu(display("Filter {:#x} not found", id))]
+    FilterNotFound { id: u64 },
+    #[snafu(display("Too many subscriptions, max={}", max))]
+    TooManySubscriptions { max: usize },
+    #[snafu(display("Too many filters, max={}", max))]
+    TooManyFilters { max: usize },
+    #[snafu(display("Invalid call of precompile {:?}: {}", address, details))]
+    InvalidPrecompileCall {
+        address: evm_state::H160,
+        details: String,
+    },
+    #[snafu(display(
+        "Request deadline of {}ms exceeded (upstream {}ms, local {}ms), mostly spent in {}",
+        deadline_ms,
+        upstream_ms,
+        local_ms,
+        phase
+    ))]
+    DeadlineExceeded {
+        deadline_ms: u64,
+        upstream_ms: u64,
+        local_ms: u64,
+        phase: String,
+    },
     // UnsupportedTrieQuery,
     // NotFound,
     // UnknownSourceMapJump
@@ -113,30 +160,70 @@ fn format_data_with_error<T: std::fmt::Debug>(data: &Bytes, error: &T) -> String
     format!("{:?}:{}", error, format_data(data))
 }
 
-pub(crate) fn format_data(data: &Bytes) -> String {
-    let func_decl = ethabi::Function {
-        name: "Error".to_string(),
-        inputs: vec![ethabi::Param {
-            name: "string".to_string(),
-            kind: ethabi::ParamType::String,
-        }],
-        outputs: vec![],
-        constant: false,
+/// Selector of `Error(string)`, used by `revert` and `require` with reason.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`, used by compiler checks since solidity 0.8.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+fn revert_message(data: &Bytes) -> String {
+    let reason = format_data(data);
+    if reason.is_empty() {
+        "execution reverted".to_string()
+    } else {
+        format!("execution reverted: {}", reason)
+    }
+}
+
+/// Description of panic code, same as in geth.
+fn panic_reason(code: U256) -> String {
+    let reason = match code.low_u64() {
+        _ if code > U256::from(u8::MAX) => None,
+        0x00 => Some("generic panic"),
+        0x01 => Some("assert(false)"),
+        0x11 => Some("arithmetic underflow or overflow"),
+        0x12 => Some("division or modulo by zero"),
+        0x21 => Some("enum overflow"),
+        0x22 => Some("invalid encoded storage byte array accessed"),
+        0x31 => Some("out-of-bounds array access; popping on an empty array"),
+        0x32 => Some("out-of-bounds access of an array or bytesN"),
+        0x41 => Some("out of memory"),
+        0x51 => Some("uninitialized function"),
+        _ => None,
     };
-    if data.0.len() > 4 {
-        let hash = &data.0[0..4];
-        // check that function hash is taken from "Error" function name
-        if dbg!(*hash == [0x08, 0xc3, 0x79, 0xa0]) {
-            if let Ok(input) = dbg!(func_decl.decode_input(&data.0[4..])) {
-                if let Some(ethabi::Token::String(s)) = input.get(0) {
-                    // on success decode return error from reason string.
-                    return s.clone();
-                }
-            }
-        }
+    match reason {
+        Some(reason) => reason.to_string(),
+        None => format!("unknown panic code: {:#x}", code),
     }
+}
+
+/// Decodes reason of revert from `Error(string)` or `Panic(uint256)` output,
+/// returns empty string if output has no known selector or can't be decoded.
+pub(crate) fn format_data(data: &Bytes) -> String {
+    if data.0.len() <= 4 {
+        return String::new();
+    }
+    let (selector, input) = data.0.split_at(4);
+    let kind = if selector == ERROR_SELECTOR {
+        ethabi::ParamType::String
+    } else if selector == PANIC_SELECTOR {
+        ethabi::ParamType::Uint(256)
+    } else {
+        return String::new();
+    };
     // if anything fail, return error from VM
-    String::new()
+    let token = ethabi::decode(&[kind], input)
+        .ok()
+        .and_then(|mut tokens| tokens.pop());
+    match token {
+        Some(ethabi::Token::String(reason)) => reason,
+        Some(ethabi::Token::Uint(code)) => panic_reason(U256(code.0)),
+        _ => String::new(),
+    }
+}
+
+/// Decoded reason of revert, `None` if output doesn't contain it.
+pub fn revert_reason(data: &Bytes) -> Option<String> {
+    Some(format_data(data)).filter(|reason| !reason.is_empty())
 }
 
 pub fn internal_error_with_details<T: ToString, U: ToString>(
@@ -159,19 +246,16 @@ pub fn internal_error<T: ToString>(code: i64, message: &T) -> JRpcError {
     }
 }
 const EVM_STATE_RPC_ERROR: i64 = 1002;
-const NATIVE_RPC_ERROR: i64 = 1003;
+pub const NATIVE_RPC_ERROR: i64 = 1003;
 
-const BLOCK_NOT_FOUND_RPC_ERROR: i64 = 2001;
-const STATE_NOT_FOUND_RPC_ERROR: i64 = 2002;
-const KEY_NOT_FOUND_RPC_ERROR: i64 = 2003;
-const FATAL_EVM_ERROR: i64 = 2004;
-const GAS_PRICE_TOO_LOW: i64 = 2005;
-const TRANSACTION_REPLACED: i64 = 2006;
-const ARCHIVE_NOT_SUPPORTED_ERROR: i64 = 2007;
+const DEADLINE_EXCEEDED: i64 = 2010;
 
 const EVM_EXECUTION_ERROR: i64 = 3; // from geth docs
 const ERROR_EVM_BASE_SUBCODE: i64 = 100; //reserved place for evm errors range: 100 - 200
 const ERROR_EVM_BASE_SUBRANGE: i64 = 100;
+/// Generic error of request processing, as in geth: missing block, rejected transaction, etc.
+const GENERIC_SERVER_ERROR: i64 = -32000;
+/// Request exceeds server limit (EIP-1474).
 const SERVER_ERROR: i64 = -32005;
 
 impl From<Error> for JRpcError {
@@ -187,6 +271,7 @@ impl From<Error> for JRpcError {
             Error::IntError { source, .. } => {
                 Self::invalid_params_with_details(err.to_string(), source)
             }
+            Error::InvalidHexDigit { .. } => Self::invalid_params(err.to_string()),
             Error::BigIntError { source, .. } => {
                 Self::invalid_params_with_details(err.to_string(), source)
             }
@@ -195,6 +280,10 @@ impl From<Error> for JRpcError {
             }
             Error::ProxyRpcError { source } => source.clone(),
             Error::WrongChainId { .. } => Self::invalid_params(err.to_string()),
+            Error::InvalidParams { .. } => Self::invalid_params(err.to_string()),
+            Error::InputTooLarge { .. } => Self::invalid_params(err.to_string()),
+            Error::FilterNotFound { .. } => Self::invalid_params(err.to_string()),
+            Error::InvalidPrecompileCall { .. } => Self::invalid_params(err.to_string()),
             Error::EvmStateError { source } => {
                 internal_error_with_details(EVM_STATE_RPC_ERROR, &err, &source)
             }
@@ -210,16 +299,16 @@ impl From<Error> for JRpcError {
                     internal_error_with_details(NATIVE_RPC_ERROR, &err, &details)
                 }
             }
-            Error::BlockNotFound { .. } => internal_error(BLOCK_NOT_FOUND_RPC_ERROR, &err),
-            Error::ArchiveNotSupported => internal_error(ARCHIVE_NOT_SUPPORTED_ERROR, &err),
-            Error::StateNotFoundForBlock { .. } => internal_error(STATE_NOT_FOUND_RPC_ERROR, &err),
-            Error::KeyNotFound { .. } => internal_error(KEY_NOT_FOUND_RPC_ERROR, &err),
-            Error::Unimplemented {} => {
+            Error::BlockNotFound { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::ArchiveNotSupported => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::StateNotFoundForBlock { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::KeyNotFound { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::Unimplemented {} | Error::MethodDisabled { .. } => {
                 let mut error = Self::invalid_request();
                 error.message = err.to_string();
                 error
             }
-            Error::CallFatal { error: _ } => internal_error(FATAL_EVM_ERROR, &err),
+            Error::CallFatal { error: _ } => internal_error(GENERIC_SERVER_ERROR, &err),
             Error::CallError { data, error } => {
                 let error_code = match error {
                     ExitError::CallTooDeep => 1,
@@ -258,9 +347,15 @@ impl From<Error> for JRpcError {
             }
             Error::ServerError {} => internal_error(SERVER_ERROR, &err),
             Error::InvalidBlocksRange { .. } => internal_error(SERVER_ERROR, &err),
-            Error::RuntimeError { .. } => internal_error(SERVER_ERROR, &err),
-            Error::GasPriceTooLow { .. } => internal_error(GAS_PRICE_TOO_LOW, &err),
-            Error::TransactionRemoved {} => internal_error(TRANSACTION_REPLACED, &err),
+            Error::TooManySubscriptions { .. } => internal_error(SERVER_ERROR, &err),
+            Error::TooManyFilters { .. } => internal_error(SERVER_ERROR, &err),
+            Error::RuntimeError { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::GasPriceTooLow { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::TransactionRemoved {} => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::TransactionReplaced { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::NonceTooLow { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::NoContractAtAddress { .. } => internal_error(GENERIC_SERVER_ERROR, &err),
+            Error::DeadlineExceeded { .. } => internal_error(DEADLINE_EXCEEDED, &err),
         }
     }
 }
@@ -300,4 +395,119 @@ mod test {
         let result = format_data(&Bytes(bytes.0[0..5].to_vec()));
         assert_eq!(&result, "");
     }
+
+    fn revert(data: &Bytes) -> JRpcError {
+        Error::CallRevert {
+            data: data.clone(),
+            error: ExitRevert::Reverted,
+        }
+        .into()
+    }
+
+    fn panic_data(code: u64) -> Bytes {
+        let mut data = PANIC_SELECTOR.to_vec();
+        data.extend_from_slice(&ethabi::encode(&[ethabi::Token::Uint(code.into())]));
+        Bytes(data)
+    }
+
+    #[test]
+    fn test_revert_with_reason_string() {
+        let bytes = Bytes::from_str("0x08c379a00000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000000d4552525f4e4f545f424f554e4400000000000000000000000000000000000000").unwrap();
+        let error = revert(&bytes);
+        assert_eq!(error.message, "execution reverted: ERR_NOT_BOUND");
+        // raw output is kept for clients that decode it themselves
+        assert_eq!(error.data, Some(json!(bytes.to_string())));
+    }
+
+    #[test]
+    fn test_revert_with_panic_code() {
+        let error = revert(&panic_data(0x11));
+        assert_eq!(
+            error.message,
+            "execution reverted: arithmetic underflow or overflow"
+        );
+        assert_eq!(error.data, Some(json!(panic_data(0x11).to_string())));
+        assert_eq!(
+            revert(&panic_data(0x01)).message,
+            "execution reverted: assert(false)"
+        );
+        assert_eq!(
+            revert(&panic_data(0x99)).message,
+            "execution reverted: unknown panic code: 0x99"
+        );
+        // truncated code is not decoded
+        let truncated = Bytes(panic_data(0x11).0[..20].to_vec());
+        assert_eq!(revert(&truncated).message, "execution reverted");
+    }
+
+    #[test]
+    fn test_revert_without_selector() {
+        let bytes = Bytes(vec![0xde, 0xad, 0xbe, 0xef, 0x01, 0x02]);
+        let error = revert(&bytes);
+        assert_eq!(error.message, "execution reverted");
+        assert_eq!(error.data, Some(json!("0xdeadbeef0102")));
+        assert_eq!(revert(&Bytes(vec![])).message, "execution reverted");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let code = |err: Error| {
+            let err: JRpcError = err.into();
+            serde_json::to_value(&err).unwrap()["code"].clone()
+        };
+
+        // execution reverted, with raw output in data
+        let data = Bytes(vec![0xde, 0xad]);
+        let err: JRpcError = Error::CallRevert {
+            data: data.clone(),
+            error: ExitRevert::Reverted,
+        }
+        .into();
+        let err = serde_json::to_value(&err).unwrap();
+        assert_eq!(err["code"], 3);
+        assert_eq!(err["data"], json!(data.to_string()));
+
+        // generic server errors
+        assert_eq!(
+            code(Error::GasPriceTooLow {
+                need: U256::from(1)
+            }),
+            -32000
+        );
+        assert_eq!(
+            code(Error::BlockNotFound {
+                block: BlockId::Num(crate::Hex(1))
+            }),
+            -32000
+        );
+        assert_eq!(
+            code(Error::NonceTooLow {
+                got: U256::from(1),
+                expected: U256::from(2)
+            }),
+            -32000
+        );
+        assert_eq!(code(Error::TransactionRemoved {}), -32000);
+
+        // malformed params
+        assert_eq!(
+            code(Error::InvalidParams {
+                details: String::new()
+            }),
+            -32602
+        );
+        assert_eq!(
+            code(Error::InvalidHexPrefix {
+                input_data: "12".to_string()
+            }),
+            -32602
+        );
+        assert_eq!(
+            code(Error::WrongChainId {
+                chain_id: 1,
+                tx_chain_id: None
+            }),
+            -32602
+        );
+    }
 }
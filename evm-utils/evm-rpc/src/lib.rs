@@ -1,6 +1,6 @@
 #![allow(clippy::upper_case_acronyms)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 use jsonrpc_core::BoxFuture;
@@ -55,6 +55,30 @@ pub struct RPCLogFilter {
     pub topics: Option<Vec<Option<RPCTopicFilter>>>,
 }
 
+impl RPCLogFilter {
+    /// Converts to filter of logs in `from_block..=to_block`,
+    /// block ids of this filter should be already resolved by caller.
+    pub fn into_log_filter(self, from_block: u64, to_block: u64) -> evm_state::LogFilter {
+        evm_state::LogFilter {
+            address: self
+                .address
+                .map(|k| match k {
+                    Either::Left(v) => v.into_iter().map(|k| k.0).collect(),
+                    Either::Right(k) => vec![k.0],
+                })
+                .unwrap_or_default(),
+            topics: self
+                .topics
+                .into_iter()
+                .flatten()
+                .map(RPCTopicFilter::into_topics)
+                .collect(),
+            from_block,
+            to_block,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RPCLog {
@@ -68,6 +92,120 @@ pub struct RPCLog {
     pub data: Bytes,
     pub topics: Vec<Hex<H256>>,
 }
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCLogsDelta {
+    pub logs: Vec<RPCLog>,
+    /// Latest block covered by `logs`, to be passed as `sinceBlock` of the next request.
+    pub latest_block: Hex<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCLogsCount {
+    pub count: Hex<usize>,
+    /// True if filter range was bigger than allowed, and only first blocks was counted.
+    pub capped: bool,
+}
+
+/// Blocks with transactions in requested range, returned by velas_getNonEmptyBlocks.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCNonEmptyBlocks {
+    /// Blocks with at least one transaction, with transaction hashes, in block order.
+    pub blocks: Vec<RPCBlock>,
+    /// Number of blocks in range without transactions.
+    pub skipped: Hex<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCTransactionPosition {
+    pub block_hash: Hex<H256>,
+    pub block_number: Hex<U256>,
+    pub transaction_index: Hex<usize>,
+}
+
+/// Result of transaction replay, returned by velas_replayBlockTransactionsWithResults.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCTransactionReplay {
+    pub hash: Hex<H256>,
+    pub success: bool,
+    pub gas_used: Hex<Gas>,
+    pub output: Bytes,
+    /// Decoded reason of revert, if output contains it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub logs: Vec<RPCLog>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCGasRange {
+    /// Minimal gas limit, with which transaction succeeds.
+    pub min: Hex<Gas>,
+    /// Minimal gas limit with buffer, that is returned by eth_estimateGas.
+    pub max: Hex<Gas>,
+}
+
+/// Number of transactions in mempool, returned by txpool_status.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct RPCTxPoolStatus {
+    pub pending: Hex<usize>,
+    pub queued: Hex<usize>,
+}
+
+/// Gas price distribution of transactions in mempool, returned by velas_getMempoolStats.
+/// Percentiles are nearest-rank, prices are absent if mempool is empty.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCMempoolStats {
+    pub count: Hex<usize>,
+    pub min_gas_price: Option<Hex<U256>>,
+    pub median_gas_price: Option<Hex<U256>>,
+    pub p90_gas_price: Option<Hex<U256>>,
+    pub max_gas_price: Option<Hex<U256>>,
+    /// Numbers of transactions in equal gas price ranges, from the cheapest.
+    pub histogram: Vec<RPCGasPriceBucket>,
+}
+
+/// Number of transactions with gas price in range `min..=max`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCGasPriceBucket {
+    pub min: Hex<U256>,
+    pub max: Hex<U256>,
+    pub count: Hex<usize>,
+}
+
+/// Transactions in mempool by sender and nonce, returned by txpool_content.
+/// Nonce is a decimal string, as in geth.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RPCTxPoolContent {
+    pub pending: BTreeMap<Hex<Address>, BTreeMap<String, RPCTransaction>>,
+    pub queued: BTreeMap<Hex<Address>, BTreeMap<String, RPCTransaction>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCBlockRange {
+    pub earliest: Hex<u64>,
+    pub latest: Hex<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCFeeHistory {
+    pub oldest_block: Hex<u64>,
+    /// Base fee of each block in range, and of the next block after `newest_block`.
+    pub base_fee_per_gas: HexVec<U256>,
+    pub gas_used_ratio: Vec<f64>,
+    /// Priority fees at requested percentiles, for each block in range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<HexVec<U256>>>,
+}
+
 impl From<RPCLog> for evm_state::Log {
     fn from(rpc: RPCLog) -> evm_state::Log {
         evm_state::Log {
@@ -81,13 +219,18 @@ impl From<RPCLog> for evm_state::Log {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RPCBlock {
+    #[serde(with = "serialize::strict_quantity")]
     pub number: Hex<U256>,
     pub hash: Hex<H256>,
     pub parent_hash: Hex<H256>,
 
+    #[serde(with = "serialize::strict_quantity")]
     pub size: Hex<usize>,
+    #[serde(with = "serialize::strict_quantity")]
     pub gas_limit: Hex<Gas>,
+    #[serde(with = "serialize::strict_quantity")]
     pub gas_used: Hex<Gas>,
+    #[serde(with = "serialize::strict_quantity")]
     pub timestamp: Hex<u64>,
     pub transactions: Either<Vec<Hex<H256>>, Vec<RPCTransaction>>,
     pub is_finalized: bool,
@@ -110,9 +253,7 @@ pub struct RPCBlock {
 }
 impl Default for RPCBlock {
     fn default() -> Self {
-        let empty_uncle: H256 =
-            H256::from_hex("0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")
-                .unwrap();
+        let empty_uncle = evm_state::empty_ommers_hash();
         RPCBlock {
             number: U256::zero().into(),
             hash: H256::zero().into(),
@@ -205,11 +346,15 @@ pub struct RPCTransaction {
     pub from: Option<Hex<Address>>,
     pub to: Option<Hex<Address>>,
     pub creates: Option<Hex<Address>>,
+    #[serde(default, with = "serialize::strict_quantity::option")]
     pub gas: Option<Hex<Gas>>,
+    #[serde(default, with = "serialize::strict_quantity::option")]
     pub gas_price: Option<Hex<Gas>>,
+    #[serde(default, with = "serialize::strict_quantity::option")]
     pub value: Option<Hex<U256>>,
     #[serde(alias = "data")]
     pub input: Option<Bytes>,
+    #[serde(default, with = "serialize::strict_quantity::option")]
     pub nonce: Option<Hex<U256>>,
 
     pub hash: Option<Hex<H256>>,
@@ -222,6 +367,74 @@ pub struct RPCTransaction {
     pub r: Option<Hex<U256>>,
     #[serde(rename = "S")]
     pub s: Option<Hex<U256>>,
+    /// EIP-2718 type of transaction envelope, 0x0 for legacy transactions.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<Hex<u64>>,
+}
+
+/// EIP-2718 type of legacy transaction.
+pub const LEGACY_TRANSACTION_TYPE: u64 = 0x0;
+
+impl RPCTransaction {
+    /// Names of fields in serialized transaction, that can be requested in compact form.
+    pub const FIELDS: &'static [&'static str] = &[
+        "from",
+        "to",
+        "creates",
+        "gas",
+        "gasPrice",
+        "value",
+        "input",
+        "nonce",
+        "hash",
+        "blockHash",
+        "blockNumber",
+        "transactionIndex",
+        "V",
+        "R",
+        "S",
+        "type",
+    ];
+
+    /// Maximum number of transactions in velas_getTransactionsCompact request.
+    pub const MAX_COMPACT_TRANSACTIONS: usize = 256;
+
+    /// Checks size of velas_getTransactionsCompact request and requested fields.
+    pub fn validate_compact_request(tx_count: usize, fields: &[String]) -> Result<(), Error> {
+        if tx_count > Self::MAX_COMPACT_TRANSACTIONS {
+            return Err(Error::InvalidParams {
+                details: format!(
+                    "Too many transactions, maximum is {}",
+                    Self::MAX_COMPACT_TRANSACTIONS
+                ),
+            });
+        }
+        Self::validate_fields(fields)
+    }
+
+    /// Checks that all requested fields exist in serialized transaction.
+    pub fn validate_fields(fields: &[String]) -> Result<(), Error> {
+        match fields
+            .iter()
+            .find(|field| !Self::FIELDS.contains(&field.as_str()))
+        {
+            Some(field) => Err(Error::InvalidParams {
+                details: format!("Unknown transaction field {:?}", field),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Serialize transaction, keeping only requested fields.
+    pub fn to_compact(&self, fields: &[String]) -> serde_json::Value {
+        match serde_json::to_value(self).expect("Transaction should be serializable") {
+            serde_json::Value::Object(map) => map
+                .into_iter()
+                .filter(|(key, _)| fields.contains(key))
+                .collect(),
+            value => value,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -242,6 +455,68 @@ pub struct RPCReceipt {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<jsonrpc_core::Error>,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RPCTransactionStatus {
+    /// Transaction is waiting in mempool, or was sent but not confirmed yet.
+    Pending,
+    /// Transaction is included in block, receipt is available.
+    Mined,
+    /// Transaction is not known.
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCTransactionLookup {
+    pub hash: Hex<H256>,
+    pub status: RPCTransactionStatus,
+    pub receipt: Option<RPCReceipt>,
+}
+
+/// Transaction, that deployed contract.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCContractCreation {
+    pub tx_hash: Hex<H256>,
+    pub block_number: Hex<u64>,
+    pub creator: Hex<Address>,
+}
+
+/// Fields of account, replaced in state before simulation of call.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    pub balance: Option<Hex<U256>>,
+    pub nonce: Option<Hex<U256>>,
+    pub code: Option<Bytes>,
+    /// Replaced storage slots, other slots of account are kept.
+    pub state_diff: Option<HashMap<Hex<H256>, Hex<H256>>>,
+}
+
+/// Overrides of accounts state, by address.
+pub type StateOverrides = HashMap<Hex<Address>, AccountOverride>;
+
+/// Receipt of transaction, with trace of its replay.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCReceiptWithTrace {
+    pub receipt: RPCReceipt,
+    pub trace: trace::TraceResultsWithTransactionHash,
+}
+
+/// Reorgs, observed since `window_start`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RPCReorgStats {
+    /// Unix timestamp of stats window start, in seconds.
+    pub window_start: Hex<u64>,
+    pub reorgs: Hex<u64>,
+    pub max_depth: Hex<u64>,
+    pub avg_depth: f64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RPCTrace {
@@ -323,7 +598,7 @@ pub struct RPCDumpAccountBasic {
 #[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
 #[serde(untagged)]
 pub enum BlockId {
-    Num(Hex<u64>),
+    Num(#[serde(with = "serialize::strict_quantity")] Hex<u64>),
     BlockHash {
         #[serde(rename = "blockHash")]
         block_hash: Hex<H256>,
@@ -334,10 +609,8 @@ pub enum BlockId {
 impl fmt::Display for BlockId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Num(n) => write!(f, "{}", n.format_hex()),
-            Self::BlockHash { block_hash } => {
-                write!(f, "{{ block_hash:{} }}", block_hash.format_hex())
-            }
+            Self::Num(n) => write!(f, "{}", n),
+            Self::BlockHash { block_hash } => write!(f, "{{ block_hash:{} }}", block_hash),
             Self::RelativeId(id) => write!(f, "{}", id),
         }
     }
@@ -349,6 +622,10 @@ pub enum BlockRelId {
     Latest,
     Pending,
     Earliest,
+    /// Latest block, which native slot is rooted.
+    Finalized,
+    /// Latest block, that is deep enough to not be reorged in practice.
+    Safe,
 }
 
 impl fmt::Display for BlockRelId {
@@ -357,6 +634,8 @@ impl fmt::Display for BlockRelId {
             Self::Latest => "latest",
             Self::Pending => "pending",
             Self::Earliest => "earliest",
+            Self::Finalized => "finalized",
+            Self::Safe => "safe",
         };
         write!(f, "{}", str_id)
     }
@@ -373,6 +652,67 @@ impl From<u64> for BlockId {
         BlockId::Num(Hex(b))
     }
 }
+
+/// Gas pricing parameters, shared by node and bridge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasPriceConfig {
+    /// Minimal gas price returned by eth_gasPrice, bridge also use it as minimal accepted price.
+    pub default_gas_price: U256,
+    /// How much (in percents) gas price should be increased to replace pending transaction,
+    /// used only by bridge, since node has no mempool.
+    pub replacement_bump_percent: u64,
+    /// Tip returned by eth_maxPriorityFeePerGas, 0 by default since gas price is fixed.
+    pub max_priority_fee: U256,
+    /// Number of latest blocks, sampled by gas price oracle, oracle is disabled if 0.
+    pub oracle_blocks: u64,
+    /// Percentile of sampled gas prices, returned by eth_gasPrice.
+    pub oracle_percentile: f64,
+}
+
+impl GasPriceConfig {
+    /// 3 lamports per gas, in wei.
+    pub const DEFAULT_GAS_PRICE: u64 = 3_000_000_000;
+
+    /// Same minimal bump as most ethereum clients require for "speed up".
+    pub const DEFAULT_REPLACEMENT_BUMP_PERCENT: u64 = 10;
+
+    pub const DEFAULT_ORACLE_BLOCKS: u64 = 20;
+
+    pub const DEFAULT_ORACLE_PERCENTILE: f64 = 60.0;
+
+    /// Minimal gas price of transaction, that can replace pending one with `old_gas_price`.
+    pub fn replacement_gas_price(&self, old_gas_price: U256) -> U256 {
+        old_gas_price.saturating_add(
+            old_gas_price.saturating_mul(self.replacement_bump_percent.into()) / 100,
+        )
+    }
+
+    /// Suggested priority fee, it never exceeds gas price.
+    pub fn max_priority_fee_per_gas(&self) -> U256 {
+        self.max_priority_fee.min(self.default_gas_price)
+    }
+}
+
+impl Default for GasPriceConfig {
+    fn default() -> Self {
+        Self {
+            default_gas_price: Self::DEFAULT_GAS_PRICE.into(),
+            replacement_bump_percent: Self::DEFAULT_REPLACEMENT_BUMP_PERCENT,
+            max_priority_fee: U256::zero(),
+            oracle_blocks: Self::DEFAULT_ORACLE_BLOCKS,
+            oracle_percentile: Self::DEFAULT_ORACLE_PERCENTILE,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+pub fn percentile(sorted: &[U256], percentile: f64) -> Option<U256> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let index = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[index.saturating_sub(1).min(sorted.len() - 1)])
+}
 pub mod trace {
     use super::*;
 
@@ -635,6 +975,7 @@ pub use bridge::BridgeERPC;
 pub use chain::ChainERPC;
 pub use general::GeneralERPC;
 pub use trace::TraceERPC;
+pub use velas::VelasERPC;
 
 pub mod general {
     use super::*;
@@ -678,6 +1019,9 @@ pub mod general {
 
         #[rpc(meta, name = "eth_gasPrice")]
         fn gas_price(&self, meta: Self::Metadata) -> Result<Hex<Gas>, Error>;
+
+        #[rpc(meta, name = "eth_maxPriorityFeePerGas")]
+        fn max_priority_fee_per_gas(&self, meta: Self::Metadata) -> Result<Hex<Gas>, Error>;
     }
 }
 
@@ -721,14 +1065,14 @@ pub mod chain {
             &self,
             meta: Self::Metadata,
             block_hash: Hex<H256>,
-        ) -> BoxFuture<Result<Hex<usize>, Error>>;
+        ) -> BoxFuture<Result<Option<Hex<usize>>, Error>>;
 
         #[rpc(meta, name = "eth_getBlockTransactionCountByNumber")]
         fn block_transaction_count_by_number(
             &self,
             meta: Self::Metadata,
             block: BlockId,
-        ) -> BoxFuture<Result<Hex<usize>, Error>>;
+        ) -> BoxFuture<Result<Option<Hex<usize>>, Error>>;
 
         #[rpc(meta, name = "eth_getCode")]
         fn code(
@@ -738,6 +1082,9 @@ pub mod chain {
             block: Option<BlockId>,
         ) -> BoxFuture<Result<Bytes, Error>>;
 
+        /// Returns block, with transaction objects if `full` is set, or with their hashes otherwise.
+        /// Node limits number of transaction objects in response, transactions of larger blocks
+        /// should be fetched by hash or index.
         #[rpc(meta, name = "eth_getBlockByHash")]
         fn block_by_hash(
             &self,
@@ -746,6 +1093,7 @@ pub mod chain {
             full: bool,
         ) -> BoxFuture<Result<Option<RPCBlock>, Error>>;
 
+        /// Same as `eth_getBlockByHash`, and has the same limit on transaction objects.
         #[rpc(meta, name = "eth_getBlockByNumber")]
         fn block_by_number(
             &self,
@@ -784,6 +1132,7 @@ pub mod chain {
             tx_hash: Hex<H256>,
         ) -> BoxFuture<Result<Option<RPCReceipt>, Error>>;
 
+        /// Simulates transaction, on state of `block` with `state_overrides` applied.
         #[rpc(meta, name = "eth_call")]
         fn call(
             &self,
@@ -791,6 +1140,7 @@ pub mod chain {
             tx: RPCTransaction,
             block: Option<BlockId>,
             meta_keys: Option<Vec<String>>,
+            state_overrides: Option<StateOverrides>,
         ) -> BoxFuture<Result<Bytes, Error>>;
 
         #[rpc(meta, name = "eth_estimateGas")]
@@ -884,25 +1234,318 @@ pub mod bridge {
 
         #[rpc(meta, name = "eth_getCompilers")]
         fn compilers(&self, meta: Self::Metadata) -> Result<Vec<String>, Error>;
+
+        /// Returns fees of `block_count` blocks, ending at `newest_block`.
+        /// `reward_percentiles` should be in range 0..=100 and in ascending order.
+        #[rpc(meta, name = "eth_feeHistory")]
+        fn fee_history(
+            &self,
+            meta: Self::Metadata,
+            block_count: Hex<u64>,
+            newest_block: BlockId,
+            reward_percentiles: Option<Vec<f64>>,
+        ) -> BoxFuture<Result<RPCFeeHistory, Error>>;
     }
 }
 
-// #[rpc]
-// pub trait FilterRPC {
-//     #[rpc(meta, name = "eth_newFilter")]
-//     fn new_filter(&self, RPCLogFilter) -> Result<String, Error>;
-//     #[rpc(meta, name = "eth_newBlockFilter")]
-//     fn new_block_filter(&self) -> Result<String, Error>;
-//     #[rpc(meta, name = "eth_newPendingTransactionFilter")]
-//     fn new_pending_transaction_filter(&self) -> Result<String, Error>;
-//     #[rpc(meta, name = "eth_uninstallFilter")]
-//     fn uninstall_filter(&self, String) -> Result<bool, Error>;
-
-//     #[rpc(meta, name = "eth_getFilterChanges")]
-//     fn filter_changes(&self, String) -> Result<Either<Vec<String>, Vec<RPCLog>>, Error>;
-//     #[rpc(meta, name = "eth_getFilterLogs")]
-//     fn filter_logs(&self, String) -> Result<Vec<RPCLog>, Error>;
-// }
+pub mod txpool {
+    use super::*;
+
+    #[rpc]
+    pub trait TxPoolERPC {
+        type Metadata;
+
+        /// Returns number of pending and queued transactions in mempool.
+        #[rpc(meta, name = "txpool_status")]
+        fn status(&self, meta: Self::Metadata) -> Result<RPCTxPoolStatus, Error>;
+
+        /// Returns pending and queued transactions in mempool, grouped by sender and nonce.
+        #[rpc(meta, name = "txpool_content")]
+        fn content(&self, meta: Self::Metadata) -> Result<RPCTxPoolContent, Error>;
+    }
+}
+
+pub mod pubsub {
+    use super::*;
+    use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+
+    /// Payload of `eth_subscription` notification.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(untagged)]
+    pub enum RPCPubSubResult {
+        Header(RPCBlock),
+        Log(RPCLog),
+        TransactionHash(Hex<H256>),
+    }
+
+    #[rpc]
+    pub trait EthPubSubERPC {
+        type Metadata;
+
+        /// Subscribes to events of `kind`: "newHeads", "newPendingTransactions",
+        /// or "logs" with optional filter in `params`,
+        /// returns subscription id to be used with `eth_unsubscribe`.
+        /// Reconnecting client can pass `lastSeenBlock` in `params` of "newHeads" and "logs",
+        /// to receive missed blocks first, at most 64 latest blocks are sent.
+        #[pubsub(subscription = "eth_subscription", subscribe, name = "eth_subscribe")]
+        fn subscribe(
+            &self,
+            meta: Self::Metadata,
+            subscriber: Subscriber<RPCPubSubResult>,
+            kind: String,
+            params: Option<serde_json::Value>,
+        );
+
+        /// Cancels subscription, only connection that made it can cancel it.
+        #[pubsub(
+            subscription = "eth_subscription",
+            unsubscribe,
+            name = "eth_unsubscribe"
+        )]
+        fn unsubscribe(
+            &self,
+            meta: Option<Self::Metadata>,
+            id: SubscriptionId,
+        ) -> Result<bool, Error>;
+    }
+}
+
+pub mod velas {
+    use super::*;
+
+    #[rpc]
+    pub trait VelasERPC {
+        type Metadata;
+
+        #[rpc(meta, name = "velas_getLogsCount")]
+        fn logs_count(
+            &self,
+            meta: Self::Metadata,
+            log_filter: RPCLogFilter,
+        ) -> BoxFuture<Result<RPCLogsCount, Error>>;
+
+        #[rpc(meta, name = "velas_getBlockRange")]
+        fn block_range(&self, meta: Self::Metadata) -> BoxFuture<Result<RPCBlockRange, Error>>;
+
+        /// Returns requested fields of transactions, at most
+        /// `RPCTransaction::MAX_COMPACT_TRANSACTIONS` per request.
+        #[rpc(meta, name = "velas_getTransactionsCompact")]
+        fn transactions_compact(
+            &self,
+            meta: Self::Metadata,
+            tx_hashes: Vec<Hex<H256>>,
+            fields: Vec<String>,
+        ) -> BoxFuture<Result<Vec<Option<serde_json::Value>>, Error>>;
+
+        /// Returns the last block with timestamp at or before `timestamp`,
+        /// or the first block at or after it if `round_up` is set.
+        #[rpc(meta, name = "velas_getBlockByTimestamp")]
+        fn block_by_timestamp(
+            &self,
+            meta: Self::Metadata,
+            timestamp: Hex<u64>,
+            round_up: bool,
+        ) -> BoxFuture<Result<Option<RPCBlock>, Error>>;
+
+        /// Returns lowest nonce, that can be used for next transaction of `address`.
+        /// Unlike `eth_getTransactionCount(address, "pending")`, which returns nonce after the
+        /// highest pending one, it fills the first gap between pending transactions.
+        #[rpc(meta, name = "velas_getNextNonce")]
+        fn next_nonce(
+            &self,
+            meta: Self::Metadata,
+            address: Hex<Address>,
+        ) -> BoxFuture<Result<Hex<U256>, Error>>;
+
+        /// Returns logs of all `addresses` in single list, ordered as on chain.
+        /// Same as `eth_getLogs` with list of addresses, but address list is required.
+        #[rpc(meta, name = "velas_getLogsForContracts")]
+        fn logs_for_contracts(
+            &self,
+            meta: Self::Metadata,
+            addresses: Vec<Hex<Address>>,
+            topics: Option<Vec<Option<RPCTopicFilter>>>,
+            from_block: Option<BlockId>,
+            to_block: Option<BlockId>,
+        ) -> BoxFuture<Result<Vec<RPCLog>, Error>>;
+
+        /// Looks up transaction by raw signed bytes, to check if it was accepted.
+        #[rpc(meta, name = "velas_findTransaction")]
+        fn find_transaction(
+            &self,
+            meta: Self::Metadata,
+            raw_tx: Bytes,
+        ) -> BoxFuture<Result<RPCTransactionLookup, Error>>;
+
+        /// Returns depth of chain reorgs, observed by bridge in current stats window.
+        #[rpc(meta, name = "velas_reorgStats")]
+        fn reorg_stats(&self, meta: Self::Metadata) -> BoxFuture<Result<RPCReorgStats, Error>>;
+
+        /// Returns gas price distribution of transactions in bridge mempool,
+        /// to estimate gas price needed for quick inclusion.
+        #[rpc(meta, name = "velas_getMempoolStats")]
+        fn mempool_stats(&self, meta: Self::Metadata) -> BoxFuture<Result<RPCMempoolStats, Error>>;
+
+        /// Returns create transaction of contract, or null if address has no creation record.
+        #[rpc(meta, name = "velas_getContractCreation")]
+        fn contract_creation(
+            &self,
+            meta: Self::Metadata,
+            address: Hex<Address>,
+        ) -> BoxFuture<Result<Option<RPCContractCreation>, Error>>;
+
+        /// Reads storage slots of contract from the same state, values are returned in slot order.
+        #[rpc(meta, name = "velas_getStorageAtMulti")]
+        fn storage_at_multi(
+            &self,
+            meta: Self::Metadata,
+            address: Hex<Address>,
+            slots: Vec<Hex<U256>>,
+            block: Option<BlockId>,
+        ) -> BoxFuture<Result<Vec<Hex<H256>>, Error>>;
+
+        /// Returns receipt of transaction together with trace of its replay,
+        /// or null if transaction is unknown.
+        #[rpc(meta, name = "velas_getReceiptWithTrace")]
+        fn receipt_with_trace(
+            &self,
+            meta: Self::Metadata,
+            tx_hash: Hex<H256>,
+            traces: Vec<String>,
+        ) -> BoxFuture<Result<Option<RPCReceiptWithTrace>, Error>>;
+
+        /// Returns blocks by their hashes in request order, null for unknown or non-canonical hashes.
+        #[rpc(meta, name = "velas_getBlocksByHashes")]
+        fn blocks_by_hashes(
+            &self,
+            meta: Self::Metadata,
+            block_hashes: Vec<Hex<H256>>,
+            full: bool,
+        ) -> BoxFuture<Result<Vec<Option<RPCBlock>>, Error>>;
+
+        /// Returns minimal gas limit, with which transaction succeeds, and the same limit with buffer.
+        /// Fails if transaction fails with any gas limit.
+        #[rpc(meta, name = "velas_estimateGasRange")]
+        fn estimate_gas_range(
+            &self,
+            meta: Self::Metadata,
+            tx: RPCTransaction,
+            block: Option<BlockId>,
+        ) -> BoxFuture<Result<RPCGasRange, Error>>;
+
+        /// Returns block and index of mined transaction, without loading transaction itself.
+        #[rpc(meta, name = "velas_getTransactionPosition")]
+        fn transaction_position(
+            &self,
+            meta: Self::Metadata,
+            tx_hash: Hex<H256>,
+        ) -> BoxFuture<Result<Option<RPCTransactionPosition>, Error>>;
+
+        /// Returns gas price, paid by mined transaction.
+        /// Fee is flat, so it is equal to gas price of transaction.
+        #[rpc(meta, name = "velas_getEffectiveGasPrice")]
+        fn effective_gas_price(
+            &self,
+            meta: Self::Metadata,
+            tx_hash: Hex<H256>,
+        ) -> BoxFuture<Result<Option<Hex<Gas>>, Error>>;
+
+        /// Returns hashes of blocks in range `from..=to` in block order.
+        /// Range is limited by the same cap as eth_getLogs.
+        #[rpc(meta, name = "velas_getBlockHashesByRange")]
+        fn block_hashes_by_range(
+            &self,
+            meta: Self::Metadata,
+            from: BlockId,
+            to: BlockId,
+        ) -> BoxFuture<Result<Vec<Hex<H256>>, Error>>;
+
+        /// Replays transactions of block on the state of its parent block,
+        /// and returns result of each transaction in block order.
+        /// Fails if block has more transactions, than can be returned in full block.
+        #[rpc(meta, name = "velas_replayBlockTransactionsWithResults")]
+        fn replay_block_transactions_with_results(
+            &self,
+            meta: Self::Metadata,
+            block: BlockId,
+        ) -> BoxFuture<Result<Vec<RPCTransactionReplay>, Error>>;
+
+        /// Returns logs matching filter in blocks after `since_block` up to the latest one,
+        /// for incremental polling. Block range of the filter is ignored.
+        /// Fails if there are more new blocks than eth_getLogs allows, so client should resync.
+        #[rpc(meta, name = "velas_getLogsDelta")]
+        fn logs_delta(
+            &self,
+            meta: Self::Metadata,
+            log_filter: RPCLogFilter,
+            since_block: Hex<u64>,
+        ) -> BoxFuture<Result<RPCLogsDelta, Error>>;
+
+        /// Returns size in bytes of account code, as EXTCODESIZE does, 0 for accounts without code.
+        #[rpc(meta, name = "velas_getCodeSize")]
+        fn code_size(
+            &self,
+            meta: Self::Metadata,
+            address: Hex<Address>,
+            block: Option<BlockId>,
+        ) -> BoxFuture<Result<Hex<usize>, Error>>;
+
+        /// Returns blocks in range `from..=to`, that have at least one transaction,
+        /// and number of skipped empty blocks. Range is limited by the same cap as eth_getLogs.
+        #[rpc(meta, name = "velas_getNonEmptyBlocks")]
+        fn non_empty_blocks(
+            &self,
+            meta: Self::Metadata,
+            from: BlockId,
+            to: BlockId,
+        ) -> BoxFuture<Result<RPCNonEmptyBlocks, Error>>;
+    }
+}
+
+pub mod filters {
+    use super::*;
+
+    #[rpc]
+    pub trait FilterERPC {
+        type Metadata;
+
+        /// Creates filter, changes of which are logs matching `log_filter` in new blocks.
+        #[rpc(meta, name = "eth_newFilter")]
+        fn new_filter(
+            &self,
+            meta: Self::Metadata,
+            log_filter: RPCLogFilter,
+        ) -> BoxFuture<Result<Hex<u64>, Error>>;
+
+        /// Creates filter, changes of which are hashes of new blocks.
+        #[rpc(meta, name = "eth_newBlockFilter")]
+        fn new_block_filter(&self, meta: Self::Metadata) -> BoxFuture<Result<Hex<u64>, Error>>;
+
+        /// Creates filter, changes of which are hashes of transactions imported into mempool.
+        #[rpc(meta, name = "eth_newPendingTransactionFilter")]
+        fn new_pending_transaction_filter(&self, meta: Self::Metadata) -> Result<Hex<u64>, Error>;
+
+        /// Returns changes of filter since previous poll: block or transaction hashes, or logs.
+        #[rpc(meta, name = "eth_getFilterChanges")]
+        fn filter_changes(
+            &self,
+            meta: Self::Metadata,
+            id: Hex<u64>,
+        ) -> BoxFuture<Result<Either<Vec<Hex<H256>>, Vec<RPCLog>>, Error>>;
+
+        /// Returns all logs matching logs filter, as eth_getLogs does.
+        #[rpc(meta, name = "eth_getFilterLogs")]
+        fn filter_logs(
+            &self,
+            meta: Self::Metadata,
+            id: Hex<u64>,
+        ) -> BoxFuture<Result<Vec<RPCLog>, Error>>;
+
+        /// Removes filter, returns false if it doesn't exist.
+        #[rpc(meta, name = "eth_uninstallFilter")]
+        fn uninstall_filter(&self, meta: Self::Metadata, id: Hex<u64>) -> Result<bool, Error>;
+    }
+}
 
 // #[rpc]
 // pub trait DebugRPC {
@@ -1058,6 +1701,8 @@ impl RPCTransaction {
             v: Some(Hex(v)),
             r: Some(Hex(r)),
             s: Some(Hex(s)),
+            // node keeps transactions in legacy form, typed ones are recognized by bridge
+            transaction_type: Some(Hex(LEGACY_TRANSACTION_TYPE)),
         })
     }
 }
@@ -1188,6 +1833,15 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn test_percentile() {
+        let values: Vec<U256> = (1..=10u64).map(U256::from).collect();
+        assert_eq!(percentile(&values, 0.0), Some(1.into()));
+        assert_eq!(percentile(&values, 60.0), Some(6.into()));
+        assert_eq!(percentile(&values, 100.0), Some(10.into()));
+        assert_eq!(percentile(&[], 60.0), None);
+    }
+
     #[test]
     fn test_block_id() {
         println!(
@@ -1222,10 +1876,83 @@ mod test {
         assert!(matches!(block, BlockId::RelativeId(BlockRelId::Pending)));
         let block: BlockId = serde_json::from_str("\"earliest\"").unwrap();
         assert!(matches!(block, BlockId::RelativeId(BlockRelId::Earliest)));
+        let block: BlockId = serde_json::from_str("\"finalized\"").unwrap();
+        assert!(matches!(block, BlockId::RelativeId(BlockRelId::Finalized)));
+        let block: BlockId = serde_json::from_str("\"safe\"").unwrap();
+        assert!(matches!(block, BlockId::RelativeId(BlockRelId::Safe)));
         let block : BlockId = serde_json::from_str("{\"blockHash\":\"0xdededededededededededededededededededededededededededededededede\"}").unwrap();
         assert!(
             matches!(block, BlockId::BlockHash{block_hash} if block_hash == Hex(H256::repeat_byte(0xde)))
         );
+
+        // block number is a quantity, it should be in canonical form
+        assert!(serde_json::from_str::<BlockId>("\"0x0123\"").is_err());
+        assert!(serde_json::from_str::<BlockId>("\"0x00\"").is_err());
+        assert!(serde_json::from_str::<BlockId>("\"0x\"").is_err());
+        let block: BlockId = serde_json::from_str("\"0x0\"").unwrap();
+        assert!(matches!(block, BlockId::Num(Hex(0))));
+    }
+
+    #[test]
+    fn test_transaction_strict_quantities() {
+        let tx: RPCTransaction = serde_json::from_str(
+            r#"{"gas":"0x5208","gasPrice":"0x3b9aca00","value":"0x0","nonce":"0x1"}"#,
+        )
+        .unwrap();
+        assert_eq!(tx.gas, Some(Hex(21000.into())));
+        assert_eq!(tx.value, Some(Hex(U256::zero())));
+        let tx: RPCTransaction = serde_json::from_str(r#"{"to":null}"#).unwrap();
+        assert!(tx.gas.is_none() && tx.gas_price.is_none() && tx.nonce.is_none());
+
+        for field in &["gas", "gasPrice", "value", "nonce"] {
+            for value in &["0x01", "0x00", "0x"] {
+                let json = format!(r#"{{"{}":"{}"}}"#, field, value);
+                assert!(
+                    serde_json::from_str::<RPCTransaction>(&json).is_err(),
+                    "{} should be rejected",
+                    json
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_transaction_compact() {
+        let tx: RPCTransaction = serde_json::from_str(
+            r#"{"hash":"0x0101010101010101010101010101010101010101010101010101010101010101","blockNumber":"0x10","nonce":"0x1"}"#,
+        )
+        .unwrap();
+
+        let fields = vec!["hash".to_string(), "blockNumber".to_string()];
+        RPCTransaction::validate_fields(&fields).unwrap();
+        let compact = tx.to_compact(&fields);
+        let compact = compact.as_object().unwrap();
+        assert_eq!(compact.len(), 2);
+        assert_eq!(compact["blockNumber"], "0x10");
+        assert!(compact.contains_key("hash"));
+        assert!(!compact.contains_key("nonce"));
+
+        assert!(RPCTransaction::validate_fields(&["status".to_string()]).is_err());
+
+        let max = RPCTransaction::MAX_COMPACT_TRANSACTIONS;
+        RPCTransaction::validate_compact_request(max, &fields).unwrap();
+        assert!(matches!(
+            RPCTransaction::validate_compact_request(max + 1, &fields),
+            Err(Error::InvalidParams { .. })
+        ));
+    }
+
+    #[test]
+    fn test_transaction_type() {
+        let tx: RPCTransaction = serde_json::from_str(r#"{"nonce":"0x1","type":"0x2"}"#).unwrap();
+        assert_eq!(tx.transaction_type, Some(Hex(2)));
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["type"], "0x2");
+
+        // type is omitted, if unknown
+        let tx: RPCTransaction = serde_json::from_str(r#"{"nonce":"0x1"}"#).unwrap();
+        let json = serde_json::to_value(&tx).unwrap();
+        assert!(json.get("type").is_none());
     }
 
     #[test]
@@ -1233,6 +1960,8 @@ mod test {
         assert_eq!(BlockRelId::Pending.to_string(), "pending");
         assert_eq!(BlockRelId::Latest.to_string(), "latest");
         assert_eq!(BlockRelId::Earliest.to_string(), "earliest");
+        assert_eq!(BlockRelId::Finalized.to_string(), "finalized");
+        assert_eq!(BlockRelId::Safe.to_string(), "safe");
 
         assert_eq!(
             BlockId::RelativeId(BlockRelId::Pending).to_string(),
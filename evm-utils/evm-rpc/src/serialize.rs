@@ -1,17 +1,28 @@
 use std::fmt::{self, LowerHex};
 use std::marker::PhantomData;
+use std::num::ParseIntError;
 use std::str::FromStr;
 
 use super::error::*;
 
 use derive_more::Deref;
-use primitive_types::{H160, H256, H512, U128, U256, U512};
+use evm_state::H64;
+use primitive_types::{H128, H160, H256, H512, U128, U256, U512};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use snafu::ResultExt;
 
 #[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deref)]
 pub struct Hex<T>(pub T);
 
+/// List of values, serialized as json array of `0x..` strings.
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq, Deref)]
+pub struct HexVec<T>(pub Vec<T>);
+
+/// Optional value, serialized as `null` if absent and as `0x..` string otherwise,
+/// so missing quantity never collapses to `0x0`.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct OptionalHex<T>(pub Option<T>);
+
 #[derive(Debug, Clone)]
 pub struct Bytes(pub Vec<u8>);
 
@@ -20,18 +31,40 @@ fn format_hex_trimmed<T: LowerHex>(val: &T) -> String {
     format!("0x{}", hex_str.trim_start_matches('0'))
 }
 
+impl<T> Hex<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.0
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Hex<U> {
+        Hex(f(self.0))
+    }
+}
+
 impl<T: FormatHex> Hex<T> {
     pub fn from_hex(data: &str) -> Result<Self, Error> {
-        if data.len() < 2 || &data[0..2] != "0x" {
-            return InvalidHexPrefix {
-                input_data: data.to_string(),
-            }
-            .fail();
+        // signed values are prefixed with sign: `-0x1`
+        if let Some(digits) = data.strip_prefix("-0x") {
+            return Ok(Hex(T::from_hex(&format!("-{}", digits))?));
         }
-        let result = if data.len() == 2 {
+        let digits = match data.strip_prefix("0x") {
+            Some(digits) => digits,
+            None => {
+                return InvalidHexPrefix {
+                    input_data: data.to_string(),
+                }
+                .fail()
+            }
+        };
+        check_unsigned_digits(digits)?;
+        let result = if digits.is_empty() {
             T::from_hex("0")?
         } else {
-            T::from_hex(&data[2..])?
+            T::from_hex(digits)?
         };
         Ok(Hex(result))
     }
@@ -44,26 +77,63 @@ impl<T: FormatHex> std::str::FromStr for Hex<T> {
     }
 }
 
+impl Bytes {
+    /// Decodes hex string, `0x` prefix is optional, empty string is decoded to empty bytes.
+    pub fn from_hex(data: &str) -> Result<Self, hex::FromHexError> {
+        let digits = data.strip_prefix("0x").unwrap_or(data);
+        decode_digits(digits, false).map(Bytes)
+    }
+}
+
+/// Decodes hex digits into buffer allocated once with exact size.
+/// In `lenient` mode odd number of digits is allowed, first digit is decoded as a single byte.
+fn decode_digits(digits: &str, lenient: bool) -> Result<Vec<u8>, hex::FromHexError> {
+    let digits = digits.as_bytes();
+    let (first, rest) = if lenient && digits.len() % 2 == 1 {
+        digits.split_at(1)
+    } else {
+        digits.split_at(0)
+    };
+    let mut buffer = vec![0u8; first.len() + rest.len() / 2];
+    if let [nibble] = first {
+        hex::decode_to_slice([b'0', *nibble], &mut buffer[..1])?;
+    }
+    hex::decode_to_slice(rest, &mut buffer[first.len()..])?;
+    Ok(buffer)
+}
+
 impl std::str::FromStr for Bytes {
     type Err = hex::FromHexError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() < 2 || &s[..2] != "0x" {
-            return Err(hex::FromHexError::InvalidStringLength);
-        }
-        if s.len() == 2 {
-            return Ok(Bytes(vec![]));
-        }
+        Self::from_hex(s)
+    }
+}
 
-        match hex::decode(&s[2..]) {
-            Ok(d) => Ok(Bytes(d)),
-            Err(e) => Err(e),
-        }
+fn hex_string<T: FormatHex>(value: &T) -> String {
+    let value = value.format_hex();
+    if value == "0x" {
+        "0x0".to_string()
+    } else {
+        value
     }
 }
 
+/// Same `0x..` form as in serialized json.
 impl<T: FormatHex> std::fmt::Display for Hex<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.format_hex())
+        f.write_str(&hex_string(&self.0))
+    }
+}
+
+/// Hex digits without `0x` prefix, alternate form (`{:#x}`) keeps the prefix.
+impl<T: FormatHex> std::fmt::LowerHex for Hex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_string();
+        if f.alternate() {
+            f.write_str(&value)
+        } else {
+            f.write_str(&value.replacen("0x", "", 1))
+        }
     }
 }
 
@@ -86,9 +156,7 @@ impl FormatHex for usize {
     }
 
     fn from_hex(data: &str) -> Result<Self, Error> {
-        Self::from_str_radix(data, 16).with_context(|| IntError {
-            input_data: data.to_string(),
-        })
+        parse_hex_int(data, Self::from_str_radix)
     }
 }
 
@@ -97,9 +165,7 @@ impl FormatHex for u8 {
         format_hex_trimmed(self)
     }
     fn from_hex(data: &str) -> Result<Self, Error> {
-        Self::from_str_radix(data, 16).with_context(|| IntError {
-            input_data: data.to_string(),
-        })
+        parse_hex_int(data, Self::from_str_radix)
     }
 }
 
@@ -108,9 +174,7 @@ impl FormatHex for u16 {
         format_hex_trimmed(self)
     }
     fn from_hex(data: &str) -> Result<Self, Error> {
-        Self::from_str_radix(data, 16).with_context(|| IntError {
-            input_data: data.to_string(),
-        })
+        parse_hex_int(data, Self::from_str_radix)
     }
 }
 impl FormatHex for u32 {
@@ -118,9 +182,7 @@ impl FormatHex for u32 {
         format_hex_trimmed(self)
     }
     fn from_hex(data: &str) -> Result<Self, Error> {
-        Self::from_str_radix(data, 16).with_context(|| IntError {
-            input_data: data.to_string(),
-        })
+        parse_hex_int(data, Self::from_str_radix)
     }
 }
 
@@ -129,9 +191,89 @@ impl FormatHex for u64 {
         format_hex_trimmed(self)
     }
     fn from_hex(data: &str) -> Result<Self, Error> {
-        Self::from_str_radix(data, 16).with_context(|| IntError {
+        parse_hex_int(data, Self::from_str_radix)
+    }
+}
+
+impl FormatHex for bool {
+    fn format_hex(&self) -> String {
+        if *self { "0x1" } else { "0x0" }.to_string()
+    }
+    fn from_hex(data: &str) -> Result<Self, Error> {
+        match u8::from_hex(data)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => InvalidParams {
+                details: format!("expected boolean 0x0 or 0x1, got 0x{}", data),
+            }
+            .fail(),
+        }
+    }
+}
+
+/// Signed values are formatted as sign and hex of absolute value, without two's complement:
+/// `-5` as `-0x5`, `0` as `0x0`, `5` as `0x5`.
+impl FormatHex for i64 {
+    fn format_hex(&self) -> String {
+        format_signed_hex(self.is_negative(), &self.unsigned_abs())
+    }
+    fn from_hex(data: &str) -> Result<Self, Error> {
+        parse_hex_int(data, Self::from_str_radix)
+    }
+}
+
+/// Same format as for `i64`.
+impl FormatHex for i128 {
+    fn format_hex(&self) -> String {
+        format_signed_hex(self.is_negative(), &self.unsigned_abs())
+    }
+    fn from_hex(data: &str) -> Result<Self, Error> {
+        parse_hex_int(data, Self::from_str_radix)
+    }
+}
+
+/// Rejects digits after `0x` prefix with sign: `0x-1`, sign is allowed only before prefix.
+fn check_unsigned_digits(digits: &str) -> Result<(), Error> {
+    if digits.starts_with('-') {
+        return InvalidHexDigit {
+            input_data: digits.to_string(),
+            character: '-',
+            index: 0_usize,
+        }
+        .fail();
+    }
+    Ok(())
+}
+
+/// Parses integer with `from_str_radix`, reporting position of the first invalid digit.
+fn parse_hex_int<T>(
+    data: &str,
+    from_str_radix: fn(&str, u32) -> Result<T, ParseIntError>,
+) -> Result<T, Error> {
+    // sign is checked by `from_str_radix`
+    let sign_len = if data.starts_with('-') { 1 } else { 0 };
+    let invalid = data[sign_len..]
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_hexdigit());
+    if let Some((index, character)) = invalid {
+        return InvalidHexDigit {
             input_data: data.to_string(),
-        })
+            character,
+            index: sign_len + index,
+        }
+        .fail();
+    }
+    from_str_radix(data, 16).with_context(|| IntError {
+        input_data: data.to_string(),
+    })
+}
+
+fn format_signed_hex<T: LowerHex>(negative: bool, abs: &T) -> String {
+    let abs = format_hex_trimmed(abs);
+    if negative {
+        format!("-{}", abs)
+    } else {
+        abs
     }
 }
 
@@ -201,16 +343,54 @@ impl FormatHex for H160 {
     }
 }
 
+impl FormatHex for H128 {
+    fn format_hex(&self) -> String {
+        format!("0x{:x}", self)
+    }
+    fn from_hex(s: &str) -> Result<Self, Error> {
+        FromStr::from_str(s).with_context(|| HexError {
+            input_data: s.to_string(),
+        })
+    }
+}
+
+impl FormatHex for H64 {
+    fn format_hex(&self) -> String {
+        format!("0x{:x}", self)
+    }
+    fn from_hex(s: &str) -> Result<Self, Error> {
+        FromStr::from_str(s).with_context(|| HexError {
+            input_data: s.to_string(),
+        })
+    }
+}
+
 impl<T: FormatHex> Serialize for Hex<T> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let value = self.0.format_hex();
-        if &value == "0x" {
-            serializer.serialize_str("0x0")
-        } else {
-            serializer.serialize_str(&value)
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<T: FormatHex> Serialize for HexVec<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0.iter().map(hex_string))
+    }
+}
+
+impl<T: FormatHex> Serialize for OptionalHex<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.0 {
+            Some(value) => serializer.serialize_str(&hex_string(value)),
+            None => serializer.serialize_none(),
         }
     }
 }
@@ -225,6 +405,8 @@ impl Serialize for Bytes {
 }
 
 struct HexVisitor<T> {
+    /// Reject non-canonical QUANTITY values with leading zeros (`0x01`).
+    strict_quantity: bool,
     _marker: PhantomData<T>,
 }
 
@@ -232,24 +414,58 @@ impl<'de, T: FormatHex> de::Visitor<'de> for HexVisitor<T> {
     type Value = Hex<T>;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("Must be a valid hex string")
+        formatter.write_str("Must be a valid hex string or unsigned integer")
     }
 
     fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        if s.len() < 3 || &s[..2] != "0x" {
-            return Err(de::Error::invalid_value(de::Unexpected::Str(s), &self));
+        if let Some(digits) = s.strip_prefix("-0x").filter(|digits| !digits.is_empty()) {
+            return T::from_hex(&format!("-{}", digits))
+                .map(Hex)
+                .map_err(|e| de::Error::custom(format_args!("invalid hex {:?}: {}", s, e)));
         }
-        match T::from_hex(&s[2..]) {
-            Ok(d) if &s[..2] == "0x" => Ok(Hex(d)),
+        match s.strip_prefix("0x") {
+            Some(digits) if self.strict_quantity && digits.len() > 1 && digits.starts_with('0') => {
+                Err(de::Error::custom(format_args!(
+                    "quantity {:?} must not have leading zeros",
+                    s
+                )))
+            }
+            Some(digits) if !digits.is_empty() => check_unsigned_digits(digits)
+                .and_then(|_| T::from_hex(digits))
+                .map(Hex)
+                .map_err(|e| de::Error::custom(format_args!("invalid hex {:?}: {}", s, e))),
             _ => Err(de::Error::invalid_value(de::Unexpected::Str(s), &self)),
         }
     }
+
+    // Some clients send quantities as plain json numbers.
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::from_hex(&format!("{:x}", v))
+            .map(Hex)
+            .map_err(|e| de::Error::custom(format_args!("invalid number {}: {}", v, e)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if v < 0 {
+            return Err(de::Error::invalid_value(de::Unexpected::Signed(v), &self));
+        }
+        self.visit_u64(v as u64)
+    }
 }
 
-struct BytesVisitor;
+struct BytesVisitor {
+    /// Accept odd number of digits, by left-padding zero nibble (`0xf` is `[0x0f]`).
+    lenient: bool,
+}
 
 impl<'de> de::Visitor<'de> for BytesVisitor {
     type Value = Bytes;
@@ -262,7 +478,13 @@ impl<'de> de::Visitor<'de> for BytesVisitor {
     where
         E: de::Error,
     {
-        Bytes::from_str(s).map_err(|_| de::Error::invalid_value(de::Unexpected::Str(s), &self))
+        let digits = match s.strip_prefix("0x") {
+            Some(digits) => digits,
+            None => return Err(de::Error::invalid_value(de::Unexpected::Str(s), &self)),
+        };
+        decode_digits(digits, self.lenient)
+            .map(Bytes)
+            .map_err(|e| de::Error::custom(format_args!("invalid hex {:?}: {}", s, e)))
     }
 }
 
@@ -271,18 +493,123 @@ impl<'de, T: FormatHex> Deserialize<'de> for Hex<T> {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(HexVisitor {
+        deserializer.deserialize_any(HexVisitor {
+            strict_quantity: false,
             _marker: PhantomData,
         })
     }
 }
 
+impl<'de, T: FormatHex> Deserialize<'de> for HexVec<T> {
+    fn deserialize<D>(deserializer: D) -> Result<HexVec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let values = Vec::<Hex<T>>::deserialize(deserializer)?;
+        Ok(HexVec(values.into_iter().map(|value| value.0).collect()))
+    }
+}
+
+impl<'de, T: FormatHex> Deserialize<'de> for OptionalHex<T> {
+    fn deserialize<D>(deserializer: D) -> Result<OptionalHex<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<Hex<T>>::deserialize(deserializer)?;
+        Ok(OptionalHex(value.map(|value| value.0)))
+    }
+}
+
+/// Serde helper for QUANTITY fields, that should be in canonical form (without leading zeros).
+/// Usage: `#[serde(with = "serialize::strict_quantity")]`
+pub mod strict_quantity {
+    use super::{FormatHex, Hex, HexVisitor};
+    use serde::{Deserializer, Serialize, Serializer};
+    use std::marker::PhantomData;
+
+    pub fn serialize<T, S>(value: &Hex<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: FormatHex,
+        S: Serializer,
+    {
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Hex<T>, D::Error>
+    where
+        T: FormatHex,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(HexVisitor {
+            strict_quantity: true,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Same as `strict_quantity`, for optional fields.
+    /// Usage: `#[serde(default, with = "serialize::strict_quantity::option")]`
+    pub mod option {
+        use super::super::{FormatHex, Hex};
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        struct Strict<T>(Hex<T>);
+
+        impl<'de, T: FormatHex> Deserialize<'de> for Strict<T> {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                super::deserialize(deserializer).map(Strict)
+            }
+        }
+
+        pub fn serialize<T, S>(value: &Option<Hex<T>>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: FormatHex,
+            S: Serializer,
+        {
+            value.serialize(serializer)
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Hex<T>>, D::Error>
+        where
+            T: FormatHex,
+            D: Deserializer<'de>,
+        {
+            let value = Option::<Strict<T>>::deserialize(deserializer)?;
+            Ok(value.map(|value| value.0))
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Bytes {
     fn deserialize<D>(deserializer: D) -> Result<Bytes, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(BytesVisitor)
+        deserializer.deserialize_str(BytesVisitor { lenient: false })
+    }
+}
+
+/// Serde helper for DATA fields, that can be sent with odd number of digits.
+/// Missing leading nibble is treated as zero: `0xf` is `[0x0f]`, `0xabc` is `[0x0a, 0xbc]`.
+/// Usage: `#[serde(with = "serialize::lenient_bytes")]`
+pub mod lenient_bytes {
+    use super::{Bytes, BytesVisitor};
+    use serde::{Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(value: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(BytesVisitor { lenient: true })
     }
 }
 
@@ -297,6 +624,18 @@ impl<T: FormatHex + FromStr> From<T> for Hex<T> {
     }
 }
 
+impl<T: FormatHex> From<Vec<T>> for HexVec<T> {
+    fn from(values: Vec<T>) -> Self {
+        HexVec(values)
+    }
+}
+
+impl<T: FormatHex> From<Option<T>> for OptionalHex<T> {
+    fn from(value: Option<T>) -> Self {
+        OptionalHex(value)
+    }
+}
+
 // The starting of removing Hex type in favour of #[serde(with)] atribute
 // Currently used only for nonce, because its u64, but should be serialized as HASH
 pub mod hex_serde {
@@ -321,12 +660,9 @@ pub mod hex_serde {
         where
             E: de::Error,
         {
-            if s.len() < 3 || &s[..2] != "0x" {
-                return Err(de::Error::invalid_value(de::Unexpected::Str(s), &self));
-            }
-
-            match T::from_hex(&s[2..]) {
-                Ok(d) if &s[..2] == "0x" => Ok(d),
+            match s.strip_prefix("0x") {
+                Some(digits) if !digits.is_empty() => T::from_hex(digits)
+                    .map_err(|e| de::Error::custom(format_args!("invalid hex {:?}: {}", s, e))),
                 _ => Err(de::Error::invalid_value(de::Unexpected::Str(s), &self)),
             }
         }
@@ -363,6 +699,18 @@ mod tests {
         assert_eq!("\"0x1\"", serde_json::to_string(&Hex(U256::one())).unwrap());
     }
 
+    #[test]
+    fn hex_inner_helpers() {
+        let value = Hex(U256::from(5));
+        assert_eq!(value.as_inner(), &U256::from(5));
+        assert_eq!(value.map(|v| v.as_u64()), Hex(5u64));
+        assert_eq!(value.into_inner(), U256::from(5));
+
+        let address: Option<Hex<H160>> = Some(Hex(H160::repeat_byte(1)));
+        let address: Option<H160> = address.map(Hex::into_inner);
+        assert_eq!(address, Some(H160::repeat_byte(1)));
+    }
+
     #[test]
     fn hex_zero() {
         assert_eq!(
@@ -371,6 +719,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hex_display() {
+        assert_eq!(format!("{}", Hex(U256::zero())), "0x0");
+        assert_eq!(format!("{}", Hex(0x400u64)), "0x400");
+        assert_eq!(format!("{:x}", Hex(0x400u64)), "400");
+        assert_eq!(format!("{:#x}", Hex(0x400u64)), "0x400");
+        assert_eq!(format!("{:x}", Hex(U256::zero())), "0");
+        assert_eq!(format!("{:x}", Hex(-0x1f_i64)), "-1f");
+        assert_eq!(
+            format!("{}", Hex(H256::repeat_byte(0x11))),
+            serde_json::to_value(Hex(H256::repeat_byte(0x11)))
+                .unwrap()
+                .as_str()
+                .unwrap()
+        );
+    }
+
     #[test]
     fn hex_deserialize() {
         assert_eq!(
@@ -405,6 +770,260 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hex_deserialize_number() {
+        assert_eq!(serde_json::from_str::<Hex<u64>>("\"0x10\"").unwrap().0, 16);
+        assert_eq!(serde_json::from_str::<Hex<u64>>("16").unwrap().0, 16);
+        assert_eq!(
+            serde_json::from_str::<Hex<U256>>("16").unwrap().0,
+            U256::from(16)
+        );
+        assert!(serde_json::from_str::<Hex<u8>>("256").is_err());
+        assert!(serde_json::from_str::<Hex<u64>>("-1").is_err());
+        assert!(serde_json::from_str::<Hex<u64>>("1.5").is_err());
+    }
+
+    #[test]
+    fn hex_strict_quantity() {
+        #[derive(Deserialize)]
+        struct Quantity {
+            #[serde(with = "strict_quantity")]
+            value: Hex<u64>,
+        }
+        let parse = |v: &str| serde_json::from_str::<Quantity>(&format!("{{\"value\":\"{}\"}}", v));
+
+        assert_eq!(parse("0x0").unwrap().value.0, 0);
+        assert_eq!(parse("0x400").unwrap().value.0, 0x400);
+        let err = parse("0x00").err().unwrap();
+        assert!(err.to_string().contains("leading zeros"));
+        assert!(parse("0x01").is_err());
+
+        // lenient by default
+        assert_eq!(serde_json::from_str::<Hex<u64>>("\"0x01\"").unwrap().0, 1);
+    }
+
+    #[test]
+    fn hex_deserialize_short_strings() {
+        for input in &["\"\"", "\"0\"", "\"x\"", "\"0x\"", "\"zz\"", "\"\u{e9}\""] {
+            assert!(
+                serde_json::from_str::<Hex<u64>>(input).is_err(),
+                "input {} should be rejected",
+                input
+            );
+        }
+        assert!(Hex::<u64>::from_hex("").is_err());
+        assert!(Hex::<u64>::from_hex("\u{e9}").is_err());
+    }
+
+    #[test]
+    fn hex_deserialize_error_message() {
+        let err = serde_json::from_str::<Hex<u64>>("\"0x1g\"").unwrap_err();
+        assert!(err.to_string().contains("in integer(1g)"));
+        let err = serde_json::from_str::<Hex<u64>>("\"0x10000000000000000\"").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Failed to parse integer(10000000000000000)"));
+
+        let err = serde_json::from_str::<Bytes>("\"0x1g\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid character 'g'"));
+    }
+
+    #[test]
+    fn hex_invalid_digit_position() {
+        let err = Hex::<u64>::from_hex("0x1g3").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidHexDigit {
+                character: 'g',
+                index: 1,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Invalid hex digit 'g' at position 1 in integer(1g3)"
+        );
+
+        let err = serde_json::from_str::<Hex<u64>>("\"0x1g3\"").unwrap_err();
+        assert!(err.to_string().contains("'g' at position 1"));
+        let err = Hex::<i64>::from_hex("-0x1z").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidHexDigit {
+                character: 'z',
+                index: 2,
+                ..
+            }
+        ));
+        let err = Hex::<i64>::from_hex("0x-1").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidHexDigit {
+                character: '-',
+                index: 0,
+                ..
+            }
+        ));
+        assert!(serde_json::from_str::<Hex<i64>>("\"0x-1\"").is_err());
+    }
+
+    #[test]
+    fn hex_bool_roundtrip() {
+        assert_eq!("\"0x1\"", serde_json::to_string(&Hex(true)).unwrap());
+        assert_eq!("\"0x0\"", serde_json::to_string(&Hex(false)).unwrap());
+        for value in &[true, false] {
+            let json = serde_json::to_string(&Hex(*value)).unwrap();
+            assert_eq!(serde_json::from_str::<Hex<bool>>(&json).unwrap().0, *value);
+        }
+        assert!(serde_json::from_str::<Hex<bool>>("\"0x01\"").unwrap().0);
+        assert!(serde_json::from_str::<Hex<bool>>("\"0x2\"").is_err());
+        assert!(serde_json::from_str::<Hex<bool>>("\"0x100\"").is_err());
+    }
+
+    #[test]
+    fn hex_signed() {
+        for (value, text) in &[(-0x1f_i64, "-0x1f"), (0, "0x0"), (0x1f, "0x1f")] {
+            let json = format!("\"{}\"", text);
+            assert_eq!(serde_json::to_string(&Hex(*value)).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Hex<i64>>(&json).unwrap().0, *value);
+            assert_eq!(Hex::<i64>::from_hex(text).unwrap().0, *value);
+
+            let wide = i128::from(*value);
+            assert_eq!(serde_json::to_string(&Hex(wide)).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Hex<i128>>(&json).unwrap().0, wide);
+        }
+        assert_eq!(
+            serde_json::to_string(&Hex(i64::MIN)).unwrap(),
+            "\"-0x8000000000000000\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Hex<i64>>("\"-0x8000000000000000\"")
+                .unwrap()
+                .0,
+            i64::MIN
+        );
+        // negative values are rejected for unsigned types
+        assert!(serde_json::from_str::<Hex<u64>>("\"-0x1\"").is_err());
+        assert!(serde_json::from_str::<Hex<i64>>("\"-0x\"").is_err());
+    }
+
+    #[test]
+    fn hex_vec_roundtrip() {
+        let values = HexVec(vec![U256::one(), U256::zero()]);
+        let json = serde_json::to_value(&values).unwrap();
+        assert_eq!(json, serde_json::json!(["0x1", "0x0"]));
+        assert_eq!(
+            serde_json::from_value::<HexVec<U256>>(json).unwrap(),
+            values
+        );
+
+        let empty = HexVec::<U256>::default();
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "[]");
+        assert!(serde_json::from_str::<HexVec<U256>>("\"0x1\"").is_err());
+        assert!(serde_json::from_str::<HexVec<U256>>("[\"0x1\", \"zz\"]").is_err());
+    }
+
+    #[test]
+    fn optional_hex_null_and_value() {
+        let absent = OptionalHex::<U256>(None);
+        assert_eq!(serde_json::to_string(&absent).unwrap(), "null");
+        assert_eq!(
+            serde_json::from_str::<OptionalHex<U256>>("null").unwrap(),
+            absent
+        );
+
+        // zero is still a value, and isn't confused with absent one
+        let zero = OptionalHex(Some(U256::zero()));
+        assert_eq!(serde_json::to_string(&zero).unwrap(), "\"0x0\"");
+        assert_eq!(
+            serde_json::from_str::<OptionalHex<U256>>("\"0x0\"").unwrap(),
+            zero
+        );
+
+        let value = OptionalHex::from(Some(0x1au64));
+        assert_eq!(serde_json::to_string(&value).unwrap(), "\"0x1a\"");
+        assert_eq!(
+            serde_json::from_str::<OptionalHex<u64>>("\"0x1a\"").unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn hex_h64_roundtrip() {
+        let nonce = H64::from_low_u64_be(0x1122334455667788);
+        let json = "\"0x1122334455667788\"";
+        assert_eq!(serde_json::to_string(&Hex(nonce)).unwrap(), json);
+        assert_eq!(serde_json::from_str::<Hex<H64>>(json).unwrap().0, nonce);
+        assert_eq!(
+            serde_json::to_string(&Hex(H64::zero())).unwrap(),
+            "\"0x0000000000000000\""
+        );
+        assert!(serde_json::from_str::<Hex<H64>>("\"0x11223344\"").is_err());
+    }
+
+    #[test]
+    fn hex_h128_roundtrip() {
+        let hash = H128::repeat_byte(0xab);
+        let json = "\"0xabababababababababababababababab\"";
+        assert_eq!(serde_json::to_string(&Hex(hash)).unwrap(), json);
+        assert_eq!(serde_json::from_str::<Hex<H128>>(json).unwrap().0, hash);
+        assert!(serde_json::from_str::<Hex<H128>>("\"0xabab\"").is_err());
+    }
+
+    #[test]
+    fn bytes_from_str() {
+        assert!(Bytes::from_str("0x").unwrap().0.is_empty());
+        assert!(Bytes::from_str("").unwrap().0.is_empty());
+        assert_eq!(Bytes::from_str("0xabcd").unwrap().0, vec![0xab, 0xcd]);
+        assert_eq!(Bytes::from_str("abcd").unwrap().0, vec![0xab, 0xcd]);
+        assert_eq!(Bytes::from_hex("abcd").unwrap().0, vec![0xab, 0xcd]);
+        assert_eq!(
+            Bytes::from_str("0xabc").unwrap_err(),
+            hex::FromHexError::OddLength
+        );
+        assert!(matches!(
+            Bytes::from_str("0xzz"),
+            Err(hex::FromHexError::InvalidHexCharacter { c: 'z', index: 0 })
+        ));
+        // json still requires prefix
+        assert!(serde_json::from_str::<Bytes>("\"abcd\"").is_err());
+    }
+
+    #[test]
+    fn bytes_lenient_odd_length() {
+        #[derive(Deserialize)]
+        struct Data {
+            #[serde(with = "lenient_bytes")]
+            data: Bytes,
+        }
+        let parse = |v: &str| serde_json::from_str::<Data>(&format!("{{\"data\":\"{}\"}}", v));
+
+        assert_eq!(parse("0xf").unwrap().data.0, vec![0x0f]);
+        assert_eq!(parse("0xabc").unwrap().data.0, vec![0x0a, 0xbc]);
+        assert_eq!(parse("0xabcd").unwrap().data.0, vec![0xab, 0xcd]);
+        assert!(parse("0x").unwrap().data.0.is_empty());
+        assert!(parse("f").is_err());
+
+        // strict by default
+        assert!(serde_json::from_str::<Bytes>("\"0xf\"").is_err());
+    }
+
+    #[test]
+    fn bytes_large_input_exact_allocation() {
+        let data: Vec<u8> = (0..256 * 1024).map(|i| i as u8).collect();
+        let json = serde_json::to_string(&Bytes(data.clone())).unwrap();
+        let decoded = serde_json::from_str::<Bytes>(&json).unwrap();
+        assert_eq!(decoded.0, data);
+        // buffer is allocated once with exact size, without growing
+        assert_eq!(decoded.0.capacity(), data.len());
+
+        // odd number of digits: first nibble of data is dropped
+        let decoded = decode_digits(&json[4..json.len() - 1], true).unwrap();
+        assert_eq!(decoded[0], data[0] & 0x0f);
+        assert_eq!(decoded[1..], data[1..]);
+        assert_eq!(decoded.capacity(), data.len());
+    }
+
     #[test]
     fn bytes_single_digit() {
         assert_eq!("\"0x01\"", serde_json::to_string(&Bytes(vec![1])).unwrap());
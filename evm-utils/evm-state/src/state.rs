@@ -293,6 +293,11 @@ impl EvmBackend<Incomming> {
         self.flush_changes()
     }
 
+    /// Drops changes of current block, that are not committed yet.
+    pub fn discard_changes(&mut self) {
+        self.state.take();
+    }
+
     pub fn new_incomming_for_root(mut self, root: H256) -> Option<Self> {
         if !self.kvs().check_root_exist(root) || self.state.is_active_changes() {
             return None;
@@ -361,6 +361,13 @@ impl TransactionInReceipt {
             TransactionInReceipt::Unsigned(tx) => tx.unsigned_tx.nonce,
         }
     }
+
+    pub fn gas_price(&self) -> Gas {
+        match self {
+            TransactionInReceipt::Signed(tx) => tx.gas_price,
+            TransactionInReceipt::Unsigned(tx) => tx.unsigned_tx.gas_price,
+        }
+    }
 }
 
 impl Encodable for TransactionInReceipt {
@@ -543,6 +550,23 @@ impl TransactionReceipt {
             TransactionInReceipt::Signed(tx) => tx.caller().ok(),
         }
     }
+
+    /// Returns address of contract, deployed by successful create transaction.
+    pub fn created_contract(&self) -> Option<H160> {
+        if !self.status.is_succeed() {
+            return None;
+        }
+        let action = match &self.transaction {
+            TransactionInReceipt::Unsigned(tx) => &tx.unsigned_tx.action,
+            TransactionInReceipt::Signed(tx) => &tx.action,
+        };
+        match action {
+            TransactionAction::Create => {
+                Some(action.address(self.caller()?, self.transaction.nonce()))
+            }
+            TransactionAction::Call(_) => None,
+        }
+    }
 }
 
 pub fn addr_from_public_key(key: &PublicKey) -> H160 {
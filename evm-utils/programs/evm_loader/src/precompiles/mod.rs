@@ -76,6 +76,15 @@ pub static NATIVE_CONTRACTS: Lazy<HashMap<H160, BuiltinEval>> = Lazy::new(|| {
     native_contracts
 });
 
+/// Checks that input of call to native contract matches contract abi.
+/// Calls to other addresses are not checked.
+pub fn check_native_call(address: H160, function_abi_input: &[u8]) -> Result<()> {
+    if address == *ETH_TO_VLX_ADDR {
+        ETH_TO_VLX_CODE.parse_abi(function_abi_input)?;
+    }
+    Ok(())
+}
+
 pub static PRECOMPILES_MAP: Lazy<HashMap<H160, BuiltinEval>> = Lazy::new(|| {
     let mut precompiles = HashMap::new();
     extend_precompile_map(&mut precompiles);
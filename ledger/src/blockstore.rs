@@ -160,6 +160,7 @@ pub struct Blockstore {
     evm_transactions_cf: LedgerColumn<cf::EvmTransactionReceipts>,
     evm_blocks_by_hash_cf: LedgerColumn<cf::EvmHeaderIndexByHash>,
     evm_blocks_by_slot_cf: LedgerColumn<cf::EvmHeaderIndexBySlot>,
+    evm_contract_creation_cf: LedgerColumn<cf::EvmContractCreation>,
 }
 
 pub struct IndexMetaWorkingSetEntry {
@@ -330,6 +331,7 @@ impl Blockstore {
         let evm_transactions_cf = db.column();
         let evm_blocks_by_hash_cf = db.column();
         let evm_blocks_by_slot_cf = db.column();
+        let evm_contract_creation_cf = db.column();
 
         let db = Arc::new(db);
 
@@ -388,6 +390,7 @@ impl Blockstore {
             evm_transactions_cf,
             evm_blocks_by_hash_cf,
             evm_blocks_by_slot_cf,
+            evm_contract_creation_cf,
         };
         if initialize_transaction_status_index {
             blockstore.initialize_transaction_status_index()?;
@@ -1992,30 +1995,7 @@ impl Blockstore {
             "blockstore-rpc-api",
             ("method", "get_evm_block".to_string(), String)
         );
-        // TODO: Integrate with cleanup service
-        // let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
-        // // lowest_cleanup_slot is the last slot that was not cleaned up by
-        // // LedgerCleanupService
-        // if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
-        //     return Err(BlockstoreError::SlotCleanedUp);
-        // }
-
-        let mut block_headers = self.read_evm_block_headers(block_number)?;
-
-        if block_headers.is_empty() {
-            return Err(BlockstoreError::SlotCleanedUp);
-        };
-        // we need to find one block from array:
-        // If confirmed block is present, then return it.
-        // Otherways return first block
-
-        let confirmed_block = block_headers
-            .iter()
-            .enumerate()
-            .find(|(_idx, b)| self.is_root(b.native_chain_slot))
-            .map(|(idx, _b)| idx);
-
-        let block_header = block_headers.remove(confirmed_block.unwrap_or_default());
+        let (block_header, confirmed) = self.find_evm_block_header(block_number)?;
 
         let mut txs = Vec::new();
         for hash in &block_header.transactions {
@@ -2035,7 +2015,6 @@ impl Blockstore {
             };
         }
 
-        let confirmed = self.is_root(block_header.native_chain_slot);
         Ok((
             evm::Block {
                 header: block_header,
@@ -2045,6 +2024,51 @@ impl Blockstore {
         ))
     }
 
+    /// Returns header of EVM block without reading its transactions,
+    /// and flag if that block was rooted (confirmed)
+    pub fn get_evm_block_header(
+        &self,
+        block_number: evm::BlockNum,
+    ) -> Result<(evm::BlockHeader, bool)> {
+        datapoint_info!(
+            "blockstore-rpc-api",
+            ("method", "get_evm_block_header".to_string(), String)
+        );
+        self.find_evm_block_header(block_number)
+    }
+
+    fn find_evm_block_header(
+        &self,
+        block_number: evm::BlockNum,
+    ) -> Result<(evm::BlockHeader, bool)> {
+        // TODO: Integrate with cleanup service
+        // let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
+        // // lowest_cleanup_slot is the last slot that was not cleaned up by
+        // // LedgerCleanupService
+        // if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
+        //     return Err(BlockstoreError::SlotCleanedUp);
+        // }
+
+        let mut block_headers = self.read_evm_block_headers(block_number)?;
+
+        if block_headers.is_empty() {
+            return Err(BlockstoreError::SlotCleanedUp);
+        };
+        // we need to find one block from array:
+        // If confirmed block is present, then return it.
+        // Otherways return first block
+
+        let confirmed_block = block_headers
+            .iter()
+            .enumerate()
+            .find(|(_idx, b)| self.is_root(b.native_chain_slot))
+            .map(|(idx, _b)| idx);
+
+        let block_header = block_headers.remove(confirmed_block.unwrap_or_default());
+        let confirmed = self.is_root(block_header.native_chain_slot);
+        Ok((block_header, confirmed))
+    }
+
     fn map_transactions_to_statuses<'a>(
         &self,
         slot: Slot,
@@ -2906,6 +2930,31 @@ impl Blockstore {
         Ok(logs)
     }
 
+    /// Counts logs of block that match filter, without collecting them.
+    pub fn count_block_logs(
+        block: &evm::Block,
+        masks: &[evm::Bloom],
+        filter: &evm::LogFilter,
+    ) -> usize {
+        if masks
+            .iter()
+            .all(|mask| !block.header.logs_bloom.contains_bloom(mask))
+        {
+            return 0;
+        }
+        block
+            .transactions
+            .iter()
+            .filter(|(_, tx)| masks.iter().any(|mask| tx.logs_bloom.contains_bloom(mask)))
+            .map(|(_, tx)| {
+                tx.logs
+                    .iter()
+                    .filter(|log| filter.is_log_match(log))
+                    .count()
+            })
+            .sum()
+    }
+
     pub fn find_evm_transaction(&self, hash: H256) -> Result<Option<evm::TransactionReceipt>> {
         // collect all transactions by hash, from both primary indexes (0 | 1), for any blocks.
         let mut transactions: Vec<_> = self
@@ -3030,6 +3079,16 @@ impl Blockstore {
             self.active_transaction_status_index.write().unwrap();
         let index =
             self.get_primary_index_to_write(block_num, &w_active_transaction_status_index)?;
+        if let (Some(address), Some(creator)) = (status.created_contract(), status.caller()) {
+            self.evm_contract_creation_cf.put(
+                (index, address, block_num, slot_index),
+                &EvmContractCreation {
+                    tx_hash: hash,
+                    block_num,
+                    creator,
+                },
+            )?;
+        }
         let status = status.into();
         self.evm_transactions_cf.put_protobuf(
             EvmTransactionReceiptsIndex {
@@ -3042,6 +3101,35 @@ impl Blockstore {
         )?;
         Ok(())
     }
+
+    /// Returns create transaction of evm contract, if it was recorded by `write_evm_transaction`.
+    /// Contract can be created in several forks, creation from rooted slot is preferred.
+    pub fn read_evm_contract_creation(
+        &self,
+        address: evm::Address,
+    ) -> Result<Option<EvmContractCreation>> {
+        let mut creations = Vec::new();
+        for primary_index in 0..=1 {
+            creations.extend(
+                self.evm_contract_creation_cf
+                    .iter(IteratorMode::From(
+                        (primary_index, address, 0, 0),
+                        IteratorDirection::Forward,
+                    ))?
+                    .take_while(|((index, found_address, _, _), _)| {
+                        *index == primary_index && *found_address == address
+                    }),
+            );
+        }
+        let creation = creations
+            .iter()
+            .find(|((_, _, _, slot), _)| self.is_root(*slot))
+            .or_else(|| creations.first());
+        match creation {
+            Some((_, data)) => Ok(Some(deserialize(data)?)),
+            None => Ok(None),
+        }
+    }
     /// Returns the entry vector for the slot starting with `shred_start_index`
     pub fn get_slot_entries(&self, slot: Slot, shred_start_index: u64) -> Result<Vec<Entry>> {
         self.get_slot_entries_with_shred_info(slot, shred_start_index, false)
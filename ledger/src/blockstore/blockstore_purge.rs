@@ -364,6 +364,10 @@ impl Blockstore {
                 .evm_transactions_cf
                 .compact_range(0, 2)
                 .unwrap_or(false)
+            && self
+                .evm_contract_creation_cf
+                .compact_range(0, 2)
+                .unwrap_or(false)
             && match evm_block_range {
                 Some((from, to)) => self.evm_blocks_cf.compact_range(from, to),
                 None => self.evm_blocks_cf.compact_range(u64::MIN, u64::MAX),
@@ -413,6 +417,18 @@ impl Blockstore {
                         batch.delete::<cf::EvmHeaderIndexByHash>((0, header.hash()))?;
                         batch.delete::<cf::EvmHeaderIndexByHash>((1, header.hash()))?;
                         for tx_hash in header.transactions {
+                            if let Ok(Some(receipt)) =
+                                self.read_evm_transaction((tx_hash, block_num, Some(slot)))
+                            {
+                                // only creation of this slot, other forks keep theirs
+                                if let Some(address) = receipt.created_contract() {
+                                    for index in 0..=1 {
+                                        batch.delete::<cf::EvmContractCreation>((
+                                            index, address, block_num, slot,
+                                        ))?;
+                                    }
+                                }
+                            }
                             for slot in std::iter::once(Some(slot)).chain(None) {
                                 for index in 0..=1 {
                                     batch.delete::<cf::EvmTransactionReceipts>(
@@ -486,6 +502,14 @@ impl Blockstore {
                         purged_index,
                         purged_index + 1,
                     )
+                    .is_ok()
+                & self
+                    .db
+                    .delete_range_cf::<cf::EvmContractCreation>(
+                        write_batch,
+                        purged_index,
+                        purged_index + 1,
+                    )
                     .is_ok();
         }
         Ok(())
@@ -1588,6 +1612,72 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_purge_evm_contract_creation_of_fork() {
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+
+        // both forks deploy contract at the same address in block 1
+        let mut tx_hashes = vec![];
+        let mut addresses = vec![];
+        for slot in 1..=2 {
+            let transaction = UnsignedTransaction {
+                gas_price: slot.into(),
+                ..create_dummy_evm_transaction()
+            };
+            let receipt = TransactionReceipt {
+                transaction: TransactionInReceipt::Unsigned(UnsignedTransactionWithCaller {
+                    unsigned_tx: transaction.clone(),
+                    caller: Default::default(),
+                    chain_id: 0,
+                    signed_compatible: false,
+                }),
+                status: ExitReason::Succeed(ExitSucceed::Stopped),
+                block_number: 1,
+                index: 0,
+                used_gas: 0,
+                logs_bloom: Default::default(),
+                logs: vec![],
+            };
+            let tx_hash = transaction.signing_hash(None);
+            let mut evm_block = create_dummy_evm_block(1, slot);
+            evm_block.transactions = vec![tx_hash];
+
+            tx_hashes.push(tx_hash);
+            addresses.push(receipt.created_contract().unwrap());
+
+            blockstore
+                .write_evm_transaction(1, slot, tx_hash, receipt)
+                .unwrap();
+            blockstore.write_evm_block_header(&evm_block).unwrap();
+        }
+        assert_eq!(addresses[0], addresses[1]);
+        let creation = || {
+            blockstore
+                .read_evm_contract_creation(addresses[0])
+                .unwrap()
+                .map(|creation| creation.tx_hash)
+        };
+
+        // creation from rooted slot is preferred
+        blockstore.set_roots(&[1]).unwrap();
+        assert_eq!(creation(), Some(tx_hashes[0]));
+
+        // purge of other fork keeps creation of rooted one
+        blockstore.purge_slots(2, 2, PurgeType::Exact);
+        assert_eq!(creation(), Some(tx_hashes[0]));
+        let slots: Vec<_> = blockstore
+            .db
+            .iter::<cf::EvmContractCreation>(IteratorMode::Start)
+            .unwrap()
+            .map(|((_, _, _, slot), _)| slot)
+            .collect();
+        assert_eq!(slots, vec![1]);
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_purge_not_existing_evm_blocks() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -1,8 +1,11 @@
 use crate::blockstore_meta;
 use bincode::{deserialize, serialize};
 use byteorder::{BigEndian, ByteOrder};
-use columns::{EvmBlockHeader, EvmHeaderIndexByHash, EvmHeaderIndexBySlot, EvmTransactionReceipts};
-use evm_state::{BlockNum, H256};
+use columns::{
+    EvmBlockHeader, EvmContractCreation, EvmHeaderIndexByHash, EvmHeaderIndexBySlot,
+    EvmTransactionReceipts,
+};
+use evm_state::{BlockNum, H160, H256};
 use log::*;
 use prost::Message;
 pub use rocksdb::Direction as IteratorDirection;
@@ -81,6 +84,7 @@ const EVM_HEADERS: &str = "evm_headers";
 const EVM_BLOCK_BY_HASH: &str = "evm_block_by_hash";
 const EVM_BLOCK_BY_SLOT: &str = "evm_block_by_slot";
 const EVM_TRANSACTIONS: &str = "evm_transactions";
+const EVM_CONTRACT_CREATION: &str = "evm_contract_creation";
 
 #[derive(Error, Debug)]
 pub enum BlockstoreError {
@@ -201,6 +205,10 @@ pub mod columns {
     #[derive(Debug)]
     /// The evm transaction with statuses.
     pub struct EvmTransactionReceipts;
+
+    #[derive(Debug)]
+    /// The evm create transaction by deployed contract address.
+    pub struct EvmContractCreation;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -408,6 +416,10 @@ impl Rocks {
             EvmTransactionReceipts::NAME,
             get_cf_options::<EvmTransactionReceipts>(&access_type, &oldest_slot, &oldest_block_num),
         );
+        let evm_contract_creation_cf_descriptor = ColumnFamilyDescriptor::new(
+            EvmContractCreation::NAME,
+            get_cf_options::<EvmContractCreation>(&access_type, &oldest_slot, &oldest_block_num),
+        );
 
         let cfs = vec![
             (SlotMeta::NAME, meta_cf_descriptor),
@@ -440,6 +452,10 @@ impl Rocks {
                 EvmHeaderIndexBySlot::NAME,
                 evm_headers_by_slot_cf_descriptor,
             ),
+            (
+                EvmContractCreation::NAME,
+                evm_contract_creation_cf_descriptor,
+            ),
         ];
         let cf_names: Vec<_> = cfs.iter().map(|c| c.0).collect();
 
@@ -484,8 +500,11 @@ impl Rocks {
             for cf_name in cf_names {
                 // this special column family must be excluded from LedgerCleanupService's rocksdb
                 // compactions
-                if cf_name == TransactionStatusIndex::NAME || cf_name == EvmTransactionReceipts::NAME
-                    || cf_name == EvmHeaderIndexByHash::NAME {
+                if cf_name == TransactionStatusIndex::NAME
+                    || cf_name == EvmTransactionReceipts::NAME
+                    || cf_name == EvmHeaderIndexByHash::NAME
+                    || cf_name == EvmContractCreation::NAME
+                {
                     continue;
                 }
 
@@ -569,6 +588,7 @@ impl Rocks {
             EvmTransactionReceipts::NAME,
             EvmHeaderIndexByHash::NAME,
             EvmHeaderIndexBySlot::NAME,
+            EvmContractCreation::NAME,
         ]
     }
 
@@ -1157,6 +1177,51 @@ impl ColumnName for columns::EvmTransactionReceipts {
 impl ProtobufColumn for columns::EvmTransactionReceipts {
     type Type = generated_evm::TransactionReceipt;
 }
+
+impl Column for columns::EvmContractCreation {
+    type Index = (u64, H160, BlockNum, Slot);
+
+    fn key((index, address, block_num, slot): (u64, H160, BlockNum, Slot)) -> Vec<u8> {
+        let mut key = vec![0; 8 + 20 + 8 + 8]; // size_of u64 + size_of Address + size_of BlockNum + size_of Slot
+        BigEndian::write_u64(&mut key[0..8], index);
+        key[8..28].clone_from_slice(address.as_bytes());
+        BigEndian::write_u64(&mut key[28..36], block_num);
+        BigEndian::write_u64(&mut key[36..44], slot);
+        key
+    }
+
+    fn index(key: &[u8]) -> (u64, H160, BlockNum, Slot) {
+        if key.len() != 44 {
+            Self::as_index(0)
+        } else {
+            let index = BigEndian::read_u64(&key[0..8]);
+            let address = H160::from_slice(&key[8..28]);
+            let block_num = BigEndian::read_u64(&key[28..36]);
+            let slot = BigEndian::read_u64(&key[36..44]);
+            (index, address, block_num, slot)
+        }
+    }
+
+    fn primary_index(index: Self::Index) -> u64 {
+        index.0
+    }
+
+    fn as_index(index: u64) -> Self::Index {
+        (index, H160::default(), 0, 0)
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.3
+    }
+}
+
+impl ColumnName for columns::EvmContractCreation {
+    const NAME: &'static str = EVM_CONTRACT_CREATION;
+}
+
+impl TypedColumn for columns::EvmContractCreation {
+    type Type = blockstore_meta::EvmContractCreation;
+}
 #[derive(Debug, Clone)]
 pub struct Database {
     backend: Arc<Rocks>,
@@ -1602,6 +1667,7 @@ fn get_cf_options<C: 'static + Column + ColumnName>(
         && C::NAME != columns::TransactionStatusIndex::NAME
         && C::NAME != columns::EvmTransactionReceipts::NAME
         && C::NAME != columns::EvmHeaderIndexByHash::NAME
+        && C::NAME != columns::EvmContractCreation::NAME
         && C::NAME != columns::EvmBlockHeader::NAME
     {
         options.set_compaction_filter_factory(PurgedSlotFilterFactory::<C> {
@@ -1,4 +1,5 @@
 use crate::erasure::ErasureConfig;
+use evm_state::{BlockNum, H160, H256};
 use serde::{Deserialize, Serialize};
 use solana_sdk::clock::Slot;
 use std::{collections::BTreeSet, ops::RangeBounds};
@@ -249,6 +250,14 @@ pub struct PerfSample {
     pub sample_period_secs: u16,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+/// Transaction, that deployed evm contract.
+pub struct EvmContractCreation {
+    pub tx_hash: H256,
+    pub block_num: BlockNum,
+    pub creator: H160,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -5,6 +5,7 @@ use {
         AppSettings, Arg, ArgMatches, SubCommand,
     },
     console::style,
+    evm_rpc::GasPriceConfig,
     fd_lock::FdLock,
     log::*,
     rand::{seq::SliceRandom, thread_rng, Rng},
@@ -1025,6 +1026,18 @@ pub fn main() {
         &format!("{}-{}", VALIDATOR_PORT_RANGE.0, VALIDATOR_PORT_RANGE.1);
     let default_genesis_archive_unpacked_size = &MAX_GENESIS_ARCHIVE_UNPACKED_SIZE.to_string();
     let default_rpc_max_multiple_accounts = &MAX_MULTIPLE_ACCOUNTS.to_string();
+    let default_rpc_evm_max_full_block_transactions =
+        &solana_core::evm_rpc_impl::MAX_FULL_BLOCK_TRANSACTIONS.to_string();
+    let default_rpc_evm_estimate_gas_buffer_percent =
+        &solana_core::evm_rpc_impl::ESTIMATE_GAS_BUFFER_PERCENT.to_string();
+    let default_rpc_evm_block_hash_cache_size =
+        &solana_core::evm_rpc_impl::BLOCK_HASH_CACHE_SIZE.to_string();
+    let default_rpc_evm_max_logs_blocks = &solana_core::evm_rpc_impl::MAX_NUM_BLOCKS.to_string();
+    let default_rpc_evm_max_input_size = &solana_core::evm_rpc_impl::MAX_INPUT_SIZE.to_string();
+    let default_rpc_evm_min_gas_price = &GasPriceConfig::DEFAULT_GAS_PRICE.to_string();
+    let default_rpc_evm_gas_oracle_blocks = &GasPriceConfig::DEFAULT_ORACLE_BLOCKS.to_string();
+    let default_rpc_evm_gas_oracle_percentile =
+        &GasPriceConfig::DEFAULT_ORACLE_PERCENTILE.to_string();
     let default_rpc_pubsub_max_connections = PubSubConfig::default().max_connections.to_string();
     let default_rpc_pubsub_max_fragment_size =
         PubSubConfig::default().max_fragment_size.to_string();
@@ -1651,6 +1664,108 @@ pub fn main() {
                 .requires("enable_rpc_transaction_history")
                 .help("Verifies blockstore roots on boot and fixes any gaps"),
         )
+        .arg(
+            Arg::with_name("rpc_evm_topic0_index")
+                .long("rpc-evm-topic0-index")
+                .takes_value(false)
+                .requires("enable_rpc_transaction_history")
+                .help("Index recorded EVM blocks by event signature (topic0) to speed up eth_getLogs without address"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_safe_block_depth")
+                .long("rpc-evm-safe-block-depth")
+                .value_name("BLOCKS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value("16")
+                .help("Number of EVM blocks below the latest one, reported as \"safe\" block. \
+                       Safe block is never older than the finalized one"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_max_full_block_transactions")
+                .long("rpc-evm-max-full-block-transactions")
+                .value_name("TRANSACTIONS")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(default_rpc_evm_max_full_block_transactions)
+                .help("Maximum number of transactions in EVM block, returned by \
+                       eth_getBlockByNumber and eth_getBlockByHash with full transactions. \
+                       Larger blocks should be requested without full transactions"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_estimate_gas_buffer_percent")
+                .long("rpc-evm-estimate-gas-buffer-percent")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(default_rpc_evm_estimate_gas_buffer_percent)
+                .help("Percent of gas, added by eth_estimateGas on top of minimal gas limit, \
+                       with which transaction succeeds"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_block_hash_cache_size")
+                .long("rpc-evm-block-hash-cache-size")
+                .value_name("ENTRIES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(default_rpc_evm_block_hash_cache_size)
+                .help("Number of EVM block hash to block number lookups, cached by RPC"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_max_logs_blocks")
+                .long("rpc-evm-max-logs-blocks")
+                .value_name("BLOCKS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(default_rpc_evm_max_logs_blocks)
+                .help("Maximum number of EVM blocks in range of eth_getLogs request"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_max_input_size")
+                .long("rpc-evm-max-input-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(default_rpc_evm_max_input_size)
+                .help("Maximum size of input data in web3_sha3 request"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_min_gas_price")
+                .long("rpc-evm-min-gas-price")
+                .value_name("WEI")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(default_rpc_evm_min_gas_price)
+                .help("Minimal gas price in wei, returned by eth_gasPrice"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_gas_oracle_blocks")
+                .long("rpc-evm-gas-oracle-blocks")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(default_rpc_evm_gas_oracle_blocks)
+                .help("Number of latest blocks, which transactions are sampled by eth_gasPrice. \
+                       Set 0 to always return minimal gas price"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_gas_oracle_percentile")
+                .long("rpc-evm-gas-oracle-percentile")
+                .value_name("PERCENTILE")
+                .takes_value(true)
+                .validator(is_parsable::<f64>)
+                .default_value(default_rpc_evm_gas_oracle_percentile)
+                .help("Percentile of sampled gas prices, returned by eth_gasPrice"),
+        )
+        .arg(
+            Arg::with_name("rpc_evm_max_priority_fee")
+                .long("rpc-evm-max-priority-fee")
+                .value_name("WEI")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value("0")
+                .help("Tip in wei, returned by eth_maxPriorityFeePerGas"),
+        )
         .arg(
             Arg::with_name("halt_on_trusted_validators_accounts_hash_mismatch")
                 .long("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -2182,6 +2297,33 @@ pub fn main() {
                 .map(Duration::from_secs),
             account_indexes: account_indexes.clone(),
             rpc_scan_and_fix_roots: matches.is_present("rpc_scan_and_fix_roots"),
+            evm_topic0_index: matches.is_present("rpc_evm_topic0_index"),
+            evm_safe_block_depth: value_t_or_exit!(matches, "rpc_evm_safe_block_depth", u64),
+            evm_max_full_block_transactions: Some(value_t_or_exit!(
+                matches,
+                "rpc_evm_max_full_block_transactions",
+                usize
+            )),
+            evm_estimate_gas_buffer_percent: Some(value_t_or_exit!(
+                matches,
+                "rpc_evm_estimate_gas_buffer_percent",
+                u64
+            )),
+            evm_block_hash_cache_size: Some(value_t_or_exit!(
+                matches,
+                "rpc_evm_block_hash_cache_size",
+                usize
+            )),
+            evm_max_logs_blocks: Some(value_t_or_exit!(matches, "rpc_evm_max_logs_blocks", u64)),
+            evm_max_input_size: Some(value_t_or_exit!(matches, "rpc_evm_max_input_size", usize)),
+            gas_price_config: GasPriceConfig {
+                default_gas_price: value_t_or_exit!(matches, "rpc_evm_min_gas_price", u64).into(),
+                max_priority_fee: value_t_or_exit!(matches, "rpc_evm_max_priority_fee", u64).into(),
+                oracle_blocks: value_t_or_exit!(matches, "rpc_evm_gas_oracle_blocks", u64),
+                oracle_percentile: value_t_or_exit!(matches, "rpc_evm_gas_oracle_percentile", f64),
+                // node has no mempool, transactions are replaced only by bridge
+                ..GasPriceConfig::default()
+            },
         },
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
             (